@@ -0,0 +1,282 @@
+//! Command-line tool for inspecting how crossfont loads and rasterizes fonts.
+
+use std::env;
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::process;
+
+use crossfont::{
+    BitmapBuffer, FontFilter, FontKey, FontconfigName, GlyphKey, Rasterize, Rasterizer, Size,
+};
+
+const USAGE: &str = "\
+Usage: crossfont <COMMAND>
+
+Commands:
+    list [--monospace] [--lang <LANG>] [FAMILY]    List installed families and their faces
+    resolve <FONT>                                 Print the chosen face and its fallback fonts
+    metrics <FONT>                                 Print the font's metrics
+    rasterize <FONT> <TEXT> <OUTPUT>               Rasterize text to a .pgm or .png file
+
+Fonts use Fontconfig's name syntax, like \"Fira Code-12:style=Bold\". The size defaults to 12pt.";
+
+/// Size used when the font name doesn't specify one.
+const DEFAULT_SIZE: f32 = 12.;
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    let result = match args.as_slice() {
+        ["list", options @ ..] => list(options),
+        ["resolve", font] => resolve(font),
+        ["metrics", font] => metrics(font),
+        ["rasterize", font, text, output] => rasterize(font, text, Path::new(output)),
+        ["help" | "-h" | "--help"] => {
+            println!("{USAGE}");
+            Ok(())
+        },
+        _ => Err(format!("invalid arguments\n\n{USAGE}").into()),
+    };
+
+    if let Err(err) = result {
+        eprintln!("crossfont: {err}");
+        process::exit(1);
+    }
+}
+
+/// Load a font from its Fontconfig name.
+fn load_font(font: &str) -> Result<(Rasterizer, FontKey, Size), Box<dyn Error>> {
+    let name: FontconfigName = font.parse()?;
+    let size = name.size.unwrap_or_else(|| Size::new(DEFAULT_SIZE));
+
+    let mut rasterizer = Rasterizer::new()?;
+    let font_key = rasterizer.load_font(&name.desc, size)?;
+
+    Ok((rasterizer, font_key, size))
+}
+
+fn list(mut options: &[&str]) -> Result<(), Box<dyn Error>> {
+    let mut filter = FontFilter::default();
+    let mut family = None;
+
+    while let [option, rest @ ..] = options {
+        options = rest;
+        match *option {
+            "--monospace" => filter.monospace = true,
+            "--lang" => match options {
+                [language, rest @ ..] => {
+                    filter.language = Some(language.to_string());
+                    options = rest;
+                },
+                [] => return Err("missing language for --lang".into()),
+            },
+            name if family.is_none() && !name.starts_with('-') => family = Some(name),
+            option => return Err(format!("unexpected argument {option:?}").into()),
+        }
+    }
+
+    let rasterizer = Rasterizer::new()?;
+    let families = rasterizer.font_families(&filter)?;
+    let families = families
+        .iter()
+        .filter(|info| family.map_or(true, |family| info.name.eq_ignore_ascii_case(family)));
+
+    for family in families {
+        println!("{}", family.name);
+
+        for face in &family.faces {
+            let mut attributes =
+                vec![format!("weight {}", face.weight), format!("width {}%", face.width)];
+            attributes.push(format!("{:?}", face.slant).to_lowercase());
+            if face.monospace {
+                attributes.push("monospace".into());
+            }
+            if face.color {
+                attributes.push("color".into());
+            }
+            if !face.scalable {
+                attributes.push("bitmap".into());
+            }
+
+            print!("    {} ({})", face.style, attributes.join(", "));
+            match &face.path {
+                Some(path) => println!(" {}", path.display()),
+                None => println!(),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+fn resolve(font: &str) -> Result<(), Box<dyn Error>> {
+    use crossfont::ft::render_mode_name;
+
+    let (rasterizer, font_key, _) = load_font(font)?;
+    let details = rasterizer.face_details(font_key).ok_or("font was not loaded")?;
+
+    println!("{} {}: {}", details.family, details.style, details.location);
+
+    let properties = details.properties;
+    println!("Fontconfig properties:");
+    println!("    antialias: {}", properties.antialias);
+    println!("    hinting: {}", properties.hinting);
+    println!("    hintstyle: {}", properties.hintstyle);
+    println!("    rgba: {}", properties.rgba);
+    println!("    lcdfilter: {}", properties.lcdfilter);
+    println!("    autohint: {}", properties.autohint);
+    println!("    embeddedbitmap: {}", properties.embedded_bitmaps);
+    println!("    embolden: {}", properties.embolden);
+    println!("    scalable: {}", properties.scalable);
+    println!("    color: {}", properties.color);
+
+    let load_flags: Vec<_> = details.load_flags.iter_names().map(|(name, _)| name).collect();
+    println!("FreeType load flags: {}", load_flags.join(" | "));
+    println!("FreeType render mode: {}", render_mode_name(details.render_mode));
+
+    println!("Fallback fonts:");
+    for fallback in rasterizer.fallback_faces(font_key) {
+        let loaded = if fallback.loaded { " (loaded)" } else { "" };
+        println!("    {} {}: {}{loaded}", fallback.family, fallback.style, fallback.location);
+    }
+
+    Ok(())
+}
+
+#[cfg(any(target_os = "macos", windows))]
+fn resolve(font: &str) -> Result<(), Box<dyn Error>> {
+    load_font(font)?;
+    Err("face details are only available with FreeType".into())
+}
+
+fn metrics(font: &str) -> Result<(), Box<dyn Error>> {
+    let (rasterizer, font_key, size) = load_font(font)?;
+    let metrics = rasterizer.metrics(font_key, size)?;
+
+    println!("size: {}pt ({}px)", size.as_pt(), size.as_px());
+    println!("average_advance: {}", metrics.average_advance);
+    println!("max_advance: {}", metrics.max_advance);
+    println!("line_height: {}", metrics.line_height);
+    println!("ascent: {}", metrics.ascent);
+    println!("descent: {}", metrics.descent);
+    println!("line_gap: {}", metrics.line_gap);
+    println!("cap_height: {}", metrics.cap_height);
+    println!("x_height: {}", metrics.x_height);
+    println!("underline_position: {}", metrics.underline_position);
+    println!("underline_thickness: {}", metrics.underline_thickness);
+    println!("strikeout_position: {}", metrics.strikeout_position);
+    println!("strikeout_thickness: {}", metrics.strikeout_thickness);
+
+    Ok(())
+}
+
+/// RGB image with white text on a black background.
+struct Canvas {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+}
+
+fn rasterize(font: &str, text: &str, output: &Path) -> Result<(), Box<dyn Error>> {
+    let (mut rasterizer, font_key, size) = load_font(font)?;
+    let metrics = rasterizer.metrics(font_key, size)?;
+
+    // Position all glyphs relative to the pen on the baseline.
+    let mut glyphs = Vec::new();
+    let mut pen = 0.;
+    let mut previous: Option<GlyphKey> = None;
+    for character in text.chars() {
+        let glyph_key = GlyphKey { font_key, character, size };
+        if let Some(previous) = previous {
+            pen += rasterizer.kerning(previous, glyph_key).0;
+        }
+
+        let glyph = rasterizer.get_glyph(glyph_key)?;
+        let x = pen.round() as i32 + glyph.left;
+        pen += glyph.advance.0 as f32;
+        previous = Some(glyph_key);
+
+        glyphs.push((x, glyph));
+    }
+
+    // Include the line and all glyph bitmaps.
+    let baseline = (metrics.line_height as f32 + metrics.descent).round() as i32;
+    let mut bounds = (0, 0, pen.ceil() as i32, metrics.line_height.ceil() as i32);
+    for (x, glyph) in &glyphs {
+        let y = baseline - glyph.top;
+        bounds.0 = bounds.0.min(*x);
+        bounds.1 = bounds.1.min(y);
+        bounds.2 = bounds.2.max(x + glyph.width);
+        bounds.3 = bounds.3.max(y + glyph.height);
+    }
+
+    let width = (bounds.2 - bounds.0).max(1) as usize;
+    let height = (bounds.3 - bounds.1).max(1) as usize;
+    let mut canvas = Canvas { width, height, pixels: vec![0; width * height * 3] };
+
+    for (x, glyph) in &glyphs {
+        let left = (x - bounds.0) as usize;
+        let top = (baseline - glyph.top - bounds.1) as usize;
+        for row in 0..glyph.height as usize {
+            for column in 0..glyph.width as usize {
+                let target = ((top + row) * canvas.width + left + column) * 3;
+                let target = &mut canvas.pixels[target..target + 3];
+                let pixel = row * glyph.width as usize + column;
+
+                match &glyph.buffer {
+                    // Coverage per channel.
+                    BitmapBuffer::Rgb(buffer) => {
+                        for (target, coverage) in target.iter_mut().zip(&buffer[pixel * 3..]) {
+                            *target = (*target).max(*coverage);
+                        }
+                    },
+                    // Premultiplied color.
+                    BitmapBuffer::Rgba(buffer) => {
+                        let alpha = u16::from(buffer[pixel * 4 + 3]);
+                        for (target, color) in target.iter_mut().zip(&buffer[pixel * 4..]) {
+                            let background = u16::from(*target) * (255 - alpha) / 255;
+                            *target = (u16::from(*color) + background).min(255) as u8;
+                        }
+                    },
+                }
+            }
+        }
+    }
+
+    let extension = output.extension().and_then(|extension| extension.to_str());
+    match extension.map(str::to_ascii_lowercase).as_deref() {
+        Some("pgm") => write_pgm(&canvas, output),
+        Some("png") => write_png(&canvas, output),
+        _ => Err(format!("unsupported output format {output:?}, expected .pgm or .png").into()),
+    }
+}
+
+/// Write the image as binary grayscale PGM.
+fn write_pgm(canvas: &Canvas, output: &Path) -> Result<(), Box<dyn Error>> {
+    let mut file = BufWriter::new(File::create(output)?);
+    write!(file, "P5\n{} {}\n255\n", canvas.width, canvas.height)?;
+
+    let gray: Vec<u8> = canvas
+        .pixels
+        .chunks_exact(3)
+        .map(|pixel| ((u16::from(pixel[0]) + u16::from(pixel[1]) + u16::from(pixel[2])) / 3) as u8)
+        .collect();
+    file.write_all(&gray)?;
+    file.flush()?;
+
+    Ok(())
+}
+
+fn write_png(canvas: &Canvas, output: &Path) -> Result<(), Box<dyn Error>> {
+    let file = BufWriter::new(File::create(output)?);
+    let mut encoder = png::Encoder::new(file, canvas.width as u32, canvas.height as u32);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.write_header()?.write_image_data(&canvas.pixels)?;
+
+    Ok(())
+}
@@ -19,10 +19,11 @@ use core_text::font::{
     cascade_list_for_languages as ct_cascade_list_for_languages,
     new_from_descriptor as ct_new_from_descriptor, new_from_name, CTFont,
 };
-use core_text::font_collection::create_for_family;
+use core_text::font_collection::{create_for_all_families, create_for_family};
 use core_text::font_descriptor::{
     self, kCTFontColorGlyphsTrait, kCTFontDefaultOrientation, kCTFontEnabledAttribute,
-    CTFontDescriptor, SymbolicTraitAccessors,
+    kCTFontFormatBitmap, kCTFontLanguagesAttribute, CTFontDescriptor, SymbolicTraitAccessors,
+    TraitAccessors,
 };
 use objc2::rc::{autoreleasepool, Retained};
 use objc2_foundation::{ns_string, NSNumber, NSObject, NSObjectProtocol, NSString, NSUserDefaults};
@@ -34,8 +35,8 @@ pub mod byte_order;
 use byte_order::kCGBitmapByteOrder32Host;
 
 use super::{
-    BitmapBuffer, Error, FontDesc, FontKey, GlyphKey, Metrics, RasterizedGlyph, Size, Slant, Style,
-    Weight,
+    BitmapBuffer, Error, FaceInfo, FamilyInfo, FontDesc, FontFilter, FontKey, GlyphKey, Metrics,
+    RasterizedGlyph, Size, Slant, Style, Weight,
 };
 
 /// According to the documentation, the index of 0 must be a missing glyph character:
@@ -63,15 +64,16 @@ impl Descriptor {
     }
 
     /// Create a Font from this descriptor.
-    fn to_font(&self, size: f64, load_fallbacks: bool) -> Font {
+    ///
+    /// Fallbacks are only loaded when `fallback_languages` are passed.
+    fn to_font(&self, size: f64, fallback_languages: Option<&[String]>) -> Font {
         let ct_font = ct_new_from_descriptor(&self.ct_descriptor, size);
 
-        let fallbacks = if load_fallbacks {
-            // TODO fixme, hardcoded en for english.
-            let mut fallbacks = cascade_list_for_languages(&ct_font, &["en".to_owned()])
+        let fallbacks = if let Some(languages) = fallback_languages {
+            let mut fallbacks = cascade_list_for_languages(&ct_font, languages)
                 .into_iter()
                 .filter(|desc| !desc.font_path.as_os_str().is_empty())
-                .map(|desc| desc.to_font(size, false))
+                .map(|desc| desc.to_font(size, None))
                 .collect::<Vec<_>>();
 
             // TODO, we can't use apple's proposed
@@ -143,7 +145,12 @@ impl crate::Rasterize for CoreTextRasterizer {
         let glyph = font.get_glyph(glyph.character, glyph_index);
 
         if glyph_index == MISSING_GLYPH_INDEX {
-            Err(Error::MissingGlyph(glyph))
+            // Without any match, `font` is the primary font.
+            let tried = iter::once(font)
+                .chain(font.fallbacks.iter())
+                .map(|font| font.ct_font.family_name())
+                .collect();
+            Err(Error::MissingGlyph { glyph, tried })
         } else {
             Ok(glyph)
         }
@@ -152,6 +159,27 @@ impl crate::Rasterize for CoreTextRasterizer {
     fn kerning(&mut self, _left: GlyphKey, _right: GlyphKey) -> (f32, f32) {
         (0., 0.)
     }
+
+    fn reload(&mut self) -> Result<bool, Error> {
+        // CoreText picks up font changes automatically.
+        Ok(false)
+    }
+
+    fn font_families(&self, filter: &FontFilter) -> Result<Vec<FamilyInfo>, Error> {
+        let descriptors = create_for_all_families().get_descriptors();
+        let faces = descriptors
+            .iter()
+            .flat_map(|descriptors| descriptors.iter())
+            .filter(is_enabled)
+            .filter(|descriptor| match &filter.language {
+                Some(language) => supports_language(descriptor, language),
+                None => true,
+            })
+            .map(|descriptor| (descriptor.family_name(), face_info(&descriptor)))
+            .filter(|(_, face)| !filter.monospace || face.monospace);
+
+        Ok(crate::group_families(faces))
+    }
 }
 
 impl CoreTextRasterizer {
@@ -166,7 +194,7 @@ impl CoreTextRasterizer {
             if descriptor.style_name == style {
                 // Found the font we want.
                 let size = f64::from(size.as_pt());
-                let font = descriptor.to_font(size, true);
+                let font = descriptor.to_font(size, Some(&fallback_languages(desc)));
                 return Ok(font);
             }
         }
@@ -185,9 +213,10 @@ impl CoreTextRasterizer {
         let italic = slant != Slant::Normal;
         let size = f64::from(size.as_pt());
 
+        let languages = fallback_languages(desc);
         let descriptors = descriptors_for_family(&desc.name[..]);
         for descriptor in descriptors {
-            let font = descriptor.to_font(size, true);
+            let font = descriptor.to_font(size, Some(&languages));
             if font.is_bold() == bold && font.is_italic() == italic {
                 // Found the font we want.
                 return Ok(font);
@@ -207,6 +236,15 @@ impl CoreTextRasterizer {
     }
 }
 
+/// Languages used to order the fallback fonts, defaulting to English.
+fn fallback_languages(desc: &FontDesc) -> Vec<String> {
+    if desc.languages.is_empty() {
+        return vec!["en".to_owned()];
+    }
+
+    desc.languages.iter().map(|language| crate::bcp47_language(language)).collect()
+}
+
 /// Return fallback descriptors for font/language list.
 fn cascade_list_for_languages(ct_font: &CTFont, languages: &[String]) -> Vec<Descriptor> {
     // Convert language type &Vec<String> -> CFArray.
@@ -240,6 +278,68 @@ fn is_enabled(fontdesc: &ItemRef<'_, CTFontDescriptor>) -> bool {
     }
 }
 
+/// Normalized CoreText weights of the OpenType weight classes.
+const WEIGHTS: [(f64, u16); 9] = [
+    (-0.8, 100),
+    (-0.6, 200),
+    (-0.4, 300),
+    (0., 400),
+    (0.23, 500),
+    (0.3, 600),
+    (0.4, 700),
+    (0.56, 800),
+    (0.62, 900),
+];
+
+/// Convert a font descriptor to a face.
+fn face_info(descriptor: &CTFontDescriptor) -> FaceInfo {
+    let traits = descriptor.traits();
+    let symbolic_traits = traits.symbolic_traits();
+
+    // Pick the closest weight class.
+    let normalized_weight = traits.normalized_weight();
+    let weight = WEIGHTS
+        .iter()
+        .min_by(|(a, _), (b, _)| {
+            (a - normalized_weight).abs().total_cmp(&(b - normalized_weight).abs())
+        })
+        .map_or(400, |(_, weight)| *weight);
+
+    // CoreText uses a normalized width from -1 to 1.
+    let width = (100. * (1. + traits.normalized_width())).clamp(50., 200.) as u16;
+
+    let slant = if symbolic_traits.is_italic() { Slant::Italic } else { Slant::Normal };
+
+    FaceInfo {
+        style: descriptor.style_name(),
+        weight,
+        slant,
+        width,
+        monospace: symbolic_traits.is_monospace(),
+        scalable: descriptor.font_format() != Some(kCTFontFormatBitmap),
+        color: (symbolic_traits & kCTFontColorGlyphsTrait) != 0,
+        path: descriptor.font_path(),
+    }
+}
+
+/// Check if a font supports a language.
+///
+/// Fonts without language information are assumed to support every language.
+fn supports_language(descriptor: &ItemRef<'_, CTFontDescriptor>, language: &str) -> bool {
+    unsafe {
+        let descriptor = descriptor.as_concrete_TypeRef();
+        let languages =
+            font_descriptor::CTFontDescriptorCopyAttribute(descriptor, kCTFontLanguagesAttribute);
+
+        if languages.is_null() {
+            return true;
+        }
+
+        let languages = CFArray::<CFString>::wrap_under_create_rule(languages as _);
+        languages.iter().any(|supported| crate::language_matches(&supported.to_string(), language))
+    }
+}
+
 /// Get descriptors for family name.
 fn descriptors_for_family(family: &str) -> Vec<Descriptor> {
     let mut out = Vec::new();
@@ -338,10 +438,32 @@ impl Font {
         let strikeout_position = (line_height / 2. - descent) as f32;
         let strikeout_thickness = underline_thickness;
 
+        let cap_height = self.ct_font.cap_height() as f32;
+        let x_height = self.ct_font.x_height() as f32;
+
+        // Read the maximum advance from the `hhea` table, since CoreText doesn't provide it.
+        let max_advance = self
+            .ct_font
+            .get_font_table(u32::from_be_bytes(*b"hhea"))
+            .and_then(|hhea| {
+                let bytes = hhea.bytes().get(10..12)?;
+                let max_advance = u16::from_be_bytes([bytes[0], bytes[1]]);
+                Some(
+                    f64::from(max_advance) * self.ct_font.pt_size()
+                        / self.ct_font.units_per_em() as f64,
+                )
+            })
+            .unwrap_or_else(|| self.ct_font.bounding_box().size.width);
+
         Metrics {
             average_advance,
+            max_advance,
             line_height,
+            ascent: ascent as f32,
             descent: -(descent as f32),
+            line_gap: leading as f32,
+            cap_height,
+            x_height,
             underline_position,
             underline_thickness,
             strikeout_position,
@@ -503,7 +625,7 @@ mod tests {
         println!("{:?}", list);
 
         // Check to_font.
-        let fonts = list.iter().map(|desc| desc.to_font(72., false)).collect::<Vec<_>>();
+        let fonts = list.iter().map(|desc| desc.to_font(72., None)).collect::<Vec<_>>();
 
         for font in fonts {
             // Get a glyph.
@@ -3,11 +3,16 @@
 use std::borrow::Cow;
 use std::collections::HashMap;
 use std::ffi::OsString;
+use std::fs;
 use std::os::windows::ffi::OsStringExt;
+use std::path::PathBuf;
+use std::ptr;
+use std::time::SystemTime;
 
 use dwrote::{
-    FontCollection, FontFace, FontFallback, FontStretch, FontStyle, FontWeight, GlyphOffset,
-    GlyphRunAnalysis, TextAnalysisSource, TextAnalysisSourceMethods, DWRITE_GLYPH_RUN,
+    FontCollection, FontFace, FontFallback, FontSimulations, FontStretch, FontStyle, FontWeight,
+    GlyphOffset, GlyphRunAnalysis, InformationalStringId, TextAnalysisSource,
+    TextAnalysisSourceMethods, DWRITE_GLYPH_RUN,
 };
 
 use winapi::shared::ntdef::{HRESULT, LOCALE_NAME_MAX_LENGTH};
@@ -15,14 +20,17 @@ use winapi::um::dwrite;
 use winapi::um::winnls::GetUserDefaultLocaleName;
 
 use super::{
-    BitmapBuffer, Error, FontDesc, FontKey, GlyphKey, Metrics, RasterizedGlyph, Size, Slant, Style,
-    Weight,
+    BitmapBuffer, Error, FaceInfo, FamilyInfo, FontDesc, FontFilter, FontKey, GlyphKey, Metrics,
+    RasterizedGlyph, Size, Slant, Style, Weight,
 };
 
 /// DirectWrite uses 0 for missing glyph symbols.
 /// https://docs.microsoft.com/en-us/typography/opentype/spec/recom#glyph-0-the-notdef-glyph
 const MISSING_GLYPH_INDEX: u16 = 0;
 
+/// Tables containing colored glyphs.
+const COLOR_TABLES: [&[u8; 4]; 4] = [b"COLR", b"CBDT", b"sbix", b"SVG "];
+
 /// Cached DirectWrite font.
 struct Font {
     face: FontFace,
@@ -30,12 +38,16 @@ struct Font {
     weight: FontWeight,
     style: FontStyle,
     stretch: FontStretch,
+    /// Locale used to pick fallback fonts, instead of the user's default locale.
+    locale: Option<String>,
 }
 
 pub struct DirectWriteRasterizer {
     fonts: HashMap<FontKey, Font>,
     keys: HashMap<FontDesc, FontKey>,
     available_fonts: FontCollection,
+    /// Faces of the available fonts, only collected once DirectWrite reports an update.
+    available_faces: Option<Vec<FaceIdentity>>,
     fallback_sequence: Option<FontFallback>,
 }
 
@@ -111,7 +123,7 @@ impl DirectWriteRasterizer {
         let length = character.len_utf16() as u32;
         let utf16_codepoints = &buffer[..length as usize];
 
-        let locale = get_current_locale();
+        let locale = loaded_font.locale.clone().unwrap_or_else(get_current_locale);
 
         let text_analysis_source_data = TextAnalysisSourceData { locale: &locale, length };
         let text_analysis_source = TextAnalysisSource::from_text(
@@ -140,6 +152,7 @@ impl crate::Rasterize for DirectWriteRasterizer {
             fonts: HashMap::new(),
             keys: HashMap::new(),
             available_fonts: FontCollection::system(),
+            available_faces: None,
             fallback_sequence: FontFallback::get_system_fallback(),
         })
     }
@@ -171,9 +184,26 @@ impl crate::Rasterize for DirectWriteRasterizer {
 
         let average_advance = f64::from(hmetrics.advanceWidth) * f64::from(scale);
 
+        let cap_height = f32::from(vmetrics.capHeight) * scale;
+        let x_height = f32::from(vmetrics.xHeight) * scale;
+
+        // Read the maximum advance from the `hhea` table, since DirectWrite doesn't provide it.
+        let max_advance = face
+            .get_font_table(u32::from_le_bytes(*b"hhea"))
+            .and_then(|hhea| {
+                let max_advance = u16::from_be_bytes([*hhea.get(10)?, *hhea.get(11)?]);
+                Some(f64::from(max_advance) * f64::from(scale))
+            })
+            .unwrap_or(average_advance);
+
         Ok(Metrics {
+            ascent,
             descent,
+            line_gap,
+            cap_height,
+            x_height,
             average_advance,
+            max_advance,
             line_height,
             underline_position,
             underline_thickness,
@@ -219,9 +249,12 @@ impl crate::Rasterize for DirectWriteRasterizer {
             },
         }?;
 
+        let mut font = Font::from(font);
+        font.locale = desc.languages.first().map(|language| crate::bcp47_language(language));
+
         let key = FontKey::next();
         self.keys.insert(desc.clone(), key);
-        self.fonts.insert(key, font.into());
+        self.fonts.insert(key, font);
 
         Ok(key)
     }
@@ -244,7 +277,11 @@ impl crate::Rasterize for DirectWriteRasterizer {
             self.rasterize_glyph(&font.face, glyph.size, glyph.character, glyph_index)?;
 
         if glyph_index == MISSING_GLYPH_INDEX {
-            Err(Error::MissingGlyph(rasterized_glyph))
+            let mut tried = vec![loaded_font.family_name.clone()];
+            if !ptr::eq(font, loaded_font) {
+                tried.push(font.family_name.clone());
+            }
+            Err(Error::MissingGlyph { glyph: rasterized_glyph, tried })
         } else {
             Ok(rasterized_glyph)
         }
@@ -253,6 +290,59 @@ impl crate::Rasterize for DirectWriteRasterizer {
     fn kerning(&mut self, _left: GlyphKey, _right: GlyphKey) -> (f32, f32) {
         (0., 0.)
     }
+
+    fn reload(&mut self) -> Result<bool, Error> {
+        // DirectWrite keeps returning the same collection until the system fonts change.
+        let available_fonts = FontCollection::get_system(true);
+        if unsafe { available_fonts.as_ptr() == self.available_fonts.as_ptr() } {
+            return Ok(false);
+        }
+
+        // Updated collections don't necessarily contain different faces.
+        let available_faces = faces(&available_fonts);
+        let changed = self.available_faces.as_ref() != Some(&available_faces);
+        self.available_fonts = available_fonts;
+        self.available_faces = Some(available_faces);
+        if !changed {
+            return Ok(false);
+        }
+
+        // Descriptions must be resolved again, but existing keys stay valid.
+        self.fallback_sequence = FontFallback::get_system_fallback();
+        self.keys.clear();
+
+        Ok(true)
+    }
+
+    fn font_families(&self, filter: &FontFilter) -> Result<Vec<FamilyInfo>, Error> {
+        let mut faces = Vec::new();
+        for family in self.available_fonts.families_iter() {
+            let name = family.name();
+            for index in 0..family.get_font_count() {
+                let font = family.get_font(index);
+
+                // Ignore faces synthesized by DirectWrite.
+                if font.simulations() != FontSimulations::None {
+                    continue;
+                }
+
+                let face = face_info(&font);
+                if filter.monospace && !face.monospace {
+                    continue;
+                }
+
+                if let Some(language) = &filter.language {
+                    if !supports_language(&font, language) {
+                        continue;
+                    }
+                }
+
+                faces.push((name.clone(), face));
+            }
+        }
+
+        Ok(crate::group_families(faces))
+    }
 }
 
 impl From<dwrote::Font> for Font {
@@ -263,6 +353,7 @@ impl From<dwrote::Font> for Font {
             weight: font.weight(),
             style: font.style(),
             stretch: font.stretch(),
+            locale: None,
         }
     }
 }
@@ -286,6 +377,75 @@ impl From<Slant> for FontStyle {
     }
 }
 
+/// Face of a font collection, identified by its file and the time the file was modified.
+type FaceIdentity = (String, Option<PathBuf>, u32, Option<SystemTime>);
+
+/// Get all faces of a font collection, to detect fonts which were added, removed or replaced.
+fn faces(collection: &FontCollection) -> Vec<FaceIdentity> {
+    let mut faces = Vec::new();
+    for family in collection.families_iter() {
+        let name = family.name();
+        for index in 0..family.get_font_count() {
+            let face = family.get_font(index).create_font_face();
+            let path = face.get_files().first().and_then(|file| file.get_font_file_path());
+            let modified = path.as_ref().and_then(|path| fs::metadata(path).ok()?.modified().ok());
+            faces.push((name.clone(), path, face.get_index(), modified));
+        }
+    }
+
+    faces.sort();
+    faces
+}
+
+/// Convert a DirectWrite font to a face.
+fn face_info(font: &dwrote::Font) -> FaceInfo {
+    let face = font.create_font_face();
+
+    let slant = match font.style() {
+        FontStyle::Normal => Slant::Normal,
+        FontStyle::Oblique => Slant::Oblique,
+        FontStyle::Italic => Slant::Italic,
+    };
+
+    let width = match font.stretch() {
+        FontStretch::UltraCondensed => 50,
+        FontStretch::ExtraCondensed => 63,
+        FontStretch::Condensed => 75,
+        FontStretch::SemiCondensed => 87,
+        FontStretch::Undefined | FontStretch::Normal => 100,
+        FontStretch::SemiExpanded => 113,
+        FontStretch::Expanded => 125,
+        FontStretch::ExtraExpanded => 150,
+        FontStretch::UltraExpanded => 200,
+    };
+
+    let color =
+        COLOR_TABLES.iter().any(|tag| face.get_font_table(u32::from_le_bytes(**tag)).is_some());
+
+    FaceInfo {
+        style: font.face_name(),
+        weight: font.weight().to_u32() as u16,
+        slant,
+        width,
+        monospace: font.is_monospace().unwrap_or(false),
+        scalable: true,
+        color,
+        path: face.get_files().first().and_then(|file| file.get_font_file_path()),
+    }
+}
+
+/// Check if a font supports a language.
+///
+/// Fonts without language information are assumed to support every language.
+fn supports_language(font: &dwrote::Font, language: &str) -> bool {
+    match font.informational_string(InformationalStringId::SupportedScriptLanguageTag) {
+        Some(languages) => languages
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .any(|supported| crate::language_matches(supported, language)),
+        None => true,
+    }
+}
+
 fn get_current_locale() -> String {
     let mut buffer = vec![0u16; LOCALE_NAME_MAX_LENGTH];
     let len =
@@ -0,0 +1,269 @@
+//! Conversion of FreeType bitmaps.
+
+use freetype::bitmap::PixelMode;
+
+use super::fc::Rgba;
+use crate::BitmapBuffer;
+
+/// Bitmap data in one of FreeType's pixel modes.
+pub struct Bitmap<'a> {
+    pub buffer: &'a [u8],
+    pub rows: usize,
+    /// Width in bytes for LCD bitmaps, otherwise in pixels.
+    pub width: usize,
+    /// Offset between rows, which is negative for bitmaps stored bottom to top.
+    pub pitch: i32,
+    pub pixel_mode: PixelMode,
+}
+
+impl<'a> Bitmap<'a> {
+    pub fn new(bitmap: &'a freetype::Bitmap, pixel_mode: PixelMode) -> Self {
+        Self {
+            buffer: bitmap.buffer(),
+            rows: bitmap.rows() as usize,
+            width: bitmap.width() as usize,
+            pitch: bitmap.pitch(),
+            pixel_mode,
+        }
+    }
+
+    /// Convert the bitmap to a packed buffer with 1 byte per LCD channel, or RGBA pixels.
+    ///
+    /// Returns the height and width in pixels, together with the buffer.
+    pub fn normalize(&self, rgba: &Rgba) -> Result<(usize, usize, BitmapBuffer), String> {
+        // Rows are sliced without further checks, so malformed bitmaps must be rejected upfront.
+        let pitch = self.pitch.unsigned_abs() as usize;
+        let size = self.rows.checked_mul(pitch);
+        if self.row_bytes() > pitch || size.map_or(true, |size| size > self.buffer.len()) {
+            return Err("glyph bitmap rows exceed its buffer".into());
+        }
+
+        let mut packed = Vec::with_capacity(self.rows * self.width * 3);
+        match self.pixel_mode {
+            PixelMode::Lcd => {
+                for row in 0..self.rows {
+                    let row = &self.row(row)[..self.width];
+                    match rgba {
+                        Rgba::Bgr => {
+                            for pixel in row.chunks_exact(3) {
+                                packed.extend_from_slice(&[pixel[2], pixel[1], pixel[0]]);
+                            }
+                        },
+                        _ => packed.extend_from_slice(row),
+                    }
+                }
+                Ok((self.rows, self.width / 3, BitmapBuffer::Rgb(packed)))
+            },
+            PixelMode::LcdV => {
+                for row in 0..self.rows / 3 {
+                    for column in 0..self.width {
+                        for channel in 0..3 {
+                            let channel = match rgba {
+                                Rgba::Vbgr => 2 - channel,
+                                _ => channel,
+                            };
+                            packed.push(self.row(row * 3 + channel)[column]);
+                        }
+                    }
+                }
+                Ok((self.rows / 3, self.width, BitmapBuffer::Rgb(packed)))
+            },
+            // Mono data is stored in a packed format using 1 bit per pixel, MSB first.
+            PixelMode::Mono => {
+                self.unpack_gray(1, &mut packed);
+                Ok((self.rows, self.width, BitmapBuffer::Rgb(packed)))
+            },
+            PixelMode::Gray2 => {
+                self.unpack_gray(2, &mut packed);
+                Ok((self.rows, self.width, BitmapBuffer::Rgb(packed)))
+            },
+            PixelMode::Gray4 => {
+                self.unpack_gray(4, &mut packed);
+                Ok((self.rows, self.width, BitmapBuffer::Rgb(packed)))
+            },
+            // Gray data is stored as a value between 0 and 255 using 1 byte per pixel.
+            PixelMode::Gray => {
+                self.unpack_gray(8, &mut packed);
+                Ok((self.rows, self.width, BitmapBuffer::Rgb(packed)))
+            },
+            PixelMode::Bgra => {
+                packed.reserve(self.rows * self.width);
+                for row in 0..self.rows {
+                    for pixel in self.row(row)[..self.width * 4].chunks_exact(4) {
+                        packed.extend_from_slice(&[pixel[2], pixel[1], pixel[0], pixel[3]]);
+                    }
+                }
+                Ok((self.rows, self.width, BitmapBuffer::Rgba(packed)))
+            },
+            PixelMode::None => Err("glyph bitmap without pixel mode".into()),
+        }
+    }
+
+    /// Unpack grayscale pixels with `bits` per pixel into 3 equal bytes each.
+    fn unpack_gray(&self, bits: usize, packed: &mut Vec<u8>) {
+        let pixels_per_byte = 8 / bits;
+        let max = (1u16 << bits) - 1;
+
+        for row in 0..self.rows {
+            let row = self.row(row);
+            for column in 0..self.width {
+                let byte = row[column / pixels_per_byte];
+                let shift = 8 - bits * (column % pixels_per_byte + 1);
+                let value = (u16::from(byte) >> shift) & max;
+                let value = (value * 255 / max) as u8;
+                packed.extend_from_slice(&[value, value, value]);
+            }
+        }
+    }
+
+    /// Number of bytes used by the pixels of each row.
+    fn row_bytes(&self) -> usize {
+        match self.pixel_mode {
+            PixelMode::Mono => self.width.div_ceil(8),
+            PixelMode::Gray2 => self.width.div_ceil(4),
+            PixelMode::Gray4 => self.width.div_ceil(2),
+            PixelMode::Bgra => self.width.saturating_mul(4),
+            _ => self.width,
+        }
+    }
+
+    /// Get a row, counting from the top of the bitmap.
+    fn row(&self, row: usize) -> &[u8] {
+        let pitch = self.pitch.unsigned_abs() as usize;
+        let row = if self.pitch < 0 { self.rows - 1 - row } else { row };
+        &self.buffer[row * pitch..(row + 1) * pitch]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn normalize(
+        buffer: &[u8],
+        (rows, width, pitch): (usize, usize, i32),
+        pixel_mode: PixelMode,
+        rgba: Rgba,
+    ) -> (usize, usize, Vec<u8>) {
+        let bitmap = Bitmap { buffer, rows, width, pitch, pixel_mode };
+        match bitmap.normalize(&rgba).unwrap() {
+            (rows, width, BitmapBuffer::Rgb(buffer))
+            | (rows, width, BitmapBuffer::Rgba(buffer)) => (rows, width, buffer),
+        }
+    }
+
+    /// Expand gray values to the packed RGB format.
+    fn expand_gray(values: &[u8]) -> Vec<u8> {
+        values.iter().flat_map(|&value| [value; 3]).collect()
+    }
+
+    #[test]
+    fn mono() {
+        // Two rows of 10 pixels, padded to 4 bytes.
+        let buffer = [0b1010_0000, 0b1100_0000, 0, 0, 0b0101_1111, 0b0011_1111, 0, 0];
+        let expected = expand_gray(&[
+            255, 0, 255, 0, 0, 0, 0, 0, 255, 255, 0, 255, 0, 255, 255, 255, 255, 255, 0, 0,
+        ]);
+        assert_eq!(normalize(&buffer, (2, 10, 4), PixelMode::Mono, Rgba::None), (2, 10, expected));
+    }
+
+    #[test]
+    fn gray2() {
+        let buffer = [0b0001_1011, 0b1100_0000, 0b1110_0100, 0b0000_0000];
+        let expected = expand_gray(&[0, 85, 170, 255, 255, 255, 170, 85, 0, 0]);
+        assert_eq!(normalize(&buffer, (2, 5, 2), PixelMode::Gray2, Rgba::None), (2, 5, expected));
+    }
+
+    #[test]
+    fn gray4() {
+        let buffer = [0x0F, 0x80, 0xF0, 0x1E];
+        let expected = expand_gray(&[0, 255, 136, 255, 0, 17]);
+        assert_eq!(normalize(&buffer, (2, 3, 2), PixelMode::Gray4, Rgba::None), (2, 3, expected));
+    }
+
+    #[test]
+    fn gray8() {
+        let buffer = [1, 2, 0, 0, 3, 4, 0, 0];
+        let expected = expand_gray(&[1, 2, 3, 4]);
+        assert_eq!(normalize(&buffer, (2, 2, 4), PixelMode::Gray, Rgba::None), (2, 2, expected));
+    }
+
+    #[test]
+    fn negative_pitch() {
+        // Rows are stored bottom to top.
+        let buffer = [3, 4, 0, 0, 1, 2, 0, 0];
+        let expected = expand_gray(&[1, 2, 3, 4]);
+        assert_eq!(normalize(&buffer, (2, 2, -4), PixelMode::Gray, Rgba::None), (2, 2, expected));
+
+        let buffer = [0b0100_0000, 0b1000_0000];
+        let expected = expand_gray(&[255, 0, 0, 255]);
+        assert_eq!(normalize(&buffer, (2, 2, -1), PixelMode::Mono, Rgba::None), (2, 2, expected));
+
+        let buffer = [5, 6, 7, 8, 1, 2, 3, 4];
+        let expected = vec![3, 2, 1, 4, 7, 6, 5, 8];
+        assert_eq!(normalize(&buffer, (2, 1, -4), PixelMode::Bgra, Rgba::None), (2, 1, expected));
+    }
+
+    #[test]
+    fn lcd() {
+        let buffer = [1, 2, 3, 4, 5, 6, 0, 0];
+        let (rows, width, pitch) = (1, 6, 8);
+        let rgb = normalize(&buffer, (rows, width, pitch), PixelMode::Lcd, Rgba::Rgb);
+        assert_eq!(rgb, (1, 2, vec![1, 2, 3, 4, 5, 6]));
+        let bgr = normalize(&buffer, (rows, width, pitch), PixelMode::Lcd, Rgba::Bgr);
+        assert_eq!(bgr, (1, 2, vec![3, 2, 1, 6, 5, 4]));
+    }
+
+    #[test]
+    fn lcd_v() {
+        let buffer = [1, 2, 0, 0, 3, 4, 0, 0, 5, 6, 0, 0];
+        let (rows, width, pitch) = (3, 2, 4);
+        let vrgb = normalize(&buffer, (rows, width, pitch), PixelMode::LcdV, Rgba::Vrgb);
+        assert_eq!(vrgb, (1, 2, vec![1, 3, 5, 2, 4, 6]));
+        let vbgr = normalize(&buffer, (rows, width, pitch), PixelMode::LcdV, Rgba::Vbgr);
+        assert_eq!(vbgr, (1, 2, vec![5, 3, 1, 6, 4, 2]));
+    }
+
+    #[test]
+    fn bgra() {
+        let buffer = [1, 2, 3, 4, 5, 6, 7, 8, 0, 0, 0, 0];
+        let expected = vec![3, 2, 1, 4, 7, 6, 5, 8];
+        assert_eq!(normalize(&buffer, (1, 2, 12), PixelMode::Bgra, Rgba::None), (1, 2, expected));
+    }
+
+    #[test]
+    fn no_pixel_mode() {
+        let bitmap =
+            Bitmap { buffer: &[], rows: 0, width: 0, pitch: 0, pixel_mode: PixelMode::None };
+        assert!(bitmap.normalize(&Rgba::None).is_err());
+    }
+
+    #[test]
+    fn malformed_bitmap() {
+        let bitmap = |buffer, (rows, width, pitch), pixel_mode| Bitmap {
+            buffer,
+            rows,
+            width,
+            pitch,
+            pixel_mode,
+        };
+
+        // Rows wider than the pitch.
+        let wide = bitmap(&[0; 8], (2, 5, 4), PixelMode::Gray);
+        assert!(wide.normalize(&Rgba::None).is_err());
+        let wide = bitmap(&[0; 8], (2, 2, -4), PixelMode::Bgra);
+        assert!(wide.normalize(&Rgba::None).is_err());
+        let wide = bitmap(&[0; 2], (2, 9, 1), PixelMode::Mono);
+        assert!(wide.normalize(&Rgba::None).is_err());
+
+        // Buffers shorter than all rows.
+        let short = bitmap(&[0; 7], (2, 4, 4), PixelMode::Gray);
+        assert!(short.normalize(&Rgba::None).is_err());
+        let short = bitmap(&[0; 7], (2, 4, -4), PixelMode::Gray);
+        assert!(short.normalize(&Rgba::None).is_err());
+        let short = bitmap(&[0; 8], (6, 2, 4), PixelMode::LcdV);
+        assert!(short.normalize(&Rgba::Vrgb).is_err());
+        let overflow = bitmap(&[0; 8], (usize::MAX, 1, 4), PixelMode::Gray);
+        assert!(overflow.normalize(&Rgba::None).is_err());
+    }
+}
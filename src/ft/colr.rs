@@ -0,0 +1,1415 @@
+//! Rendering of colored outline glyphs from the OpenType `COLR` table.
+//!
+//! FreeType only exposes the layers and paint graph of `COLR` glyphs, so compositing them into
+//! an RGBA bitmap is done here. Both the layered glyphs of `COLR` version 0 and the paint graph
+//! of version 1 (solid fills, gradients, transforms, clips and composites) are supported.
+//!
+//! The paint graph of version 1 is only rendered with FreeType 2.13 or later, older versions
+//! fall back to the version 0 layers of a glyph.
+
+use std::mem::MaybeUninit;
+use std::ptr;
+
+use freetype::freetype_sys::{
+    FT_BBox, FT_Bitmap, FT_Face, FT_Fixed, FT_Library, FT_Load_Glyph, FT_Matrix, FT_Outline,
+    FT_Outline_Get_Bitmap, FT_Outline_Get_CBox, FT_Outline_Transform, FT_Outline_Translate, FT_Pos,
+    FT_UInt, FT_Vector, FT_GLYPH_FORMAT_OUTLINE, FT_LOAD_NO_BITMAP, FT_LOAD_NO_HINTING,
+    FT_LOAD_NO_SCALE, FT_PIXEL_MODE_GRAY,
+};
+use freetype::{Face as FtFace, Matrix};
+use libc::c_char;
+
+use ffi::*;
+
+/// Palette index used for the text foreground color.
+const FOREGROUND_PALETTE_INDEX: u16 = 0xFFFF;
+
+/// Color used for palette entries referencing the text foreground.
+///
+/// Since colored glyphs are returned with their final colors, there's no way to know the actual
+/// foreground color, so white is used to allow tinting by the renderer.
+const FOREGROUND_COLOR: Color = [1., 1., 1., 1.];
+
+/// Maximum depth of the paint graph, to guard against cycles in malformed fonts.
+const MAX_PAINT_DEPTH: usize = 64;
+
+/// Maximum number of pixels of a color glyph.
+const MAX_PIXELS: usize = 4096 * 4096;
+
+/// Premultiplied RGBA color.
+type Color = [f32; 4];
+
+/// A color glyph rendered into premultiplied RGBA pixels.
+pub struct ColorGlyph {
+    pub left: i32,
+    pub top: i32,
+    pub width: i32,
+    pub height: i32,
+    pub buffer: Vec<u8>,
+}
+
+/// Rasterize the `COLR` glyph at `glyph_index` with the face's current size.
+///
+/// Returns `None` if the glyph has no color layers or paint graph.
+///
+/// This will clobber the face's glyph slot, so the glyph's metrics need to be retrieved before
+/// calling this function.
+pub fn rasterize(
+    library: FT_Library,
+    ft_face: &FtFace,
+    glyph_index: u32,
+    matrix: Option<&Matrix>,
+) -> Option<ColorGlyph> {
+    let face = ft_face.raw() as *const _ as FT_Face;
+    let mut painter = Painter::new(library, face);
+
+    let root = match painter.color_glyph_paint(glyph_index) {
+        Some(paint) => Root::Paint(paint),
+        None => unsafe {
+            let mut layers = Vec::new();
+            let mut iterator = FT_LayerIterator { num_layers: 0, layer: 0, p: ptr::null_mut() };
+            let (mut layer_glyph, mut color_index) = (0, 0);
+            while FT_Get_Color_Glyph_Layer(
+                face,
+                glyph_index,
+                &mut layer_glyph,
+                &mut color_index,
+                &mut iterator,
+            ) != 0
+            {
+                layers.push((layer_glyph, color_index as u16));
+            }
+
+            if layers.is_empty() {
+                return None;
+            }
+
+            Root::Layers(layers)
+        },
+    };
+
+    // Transformation from font units to pixels.
+    let size_metrics = ft_face.size_metrics()?;
+    let scale = Transform::scale(
+        size_metrics.x_scale as f32 / 65536. / 64.,
+        size_metrics.y_scale as f32 / 65536. / 64.,
+    );
+    let base = match matrix {
+        Some(matrix) => Transform::from_ft_matrix(matrix).multiply(&scale),
+        None => scale,
+    };
+
+    let bounds = match &root {
+        Root::Paint(_) => painter.clip_box(glyph_index, matrix),
+        Root::Layers(_) => None,
+    };
+    let bounds = bounds.or_else(|| match &root {
+        Root::Paint(paint) => painter.paint_bounds(*paint, &base, 0),
+        Root::Layers(layers) => layers
+            .iter()
+            .filter_map(|(glyph, _)| painter.glyph_bounds(*glyph, &base))
+            .reduce(Bounds::union),
+    });
+
+    let bounds = match bounds {
+        Some(bounds) => bounds,
+        None => {
+            return Some(ColorGlyph { left: 0, top: 0, width: 0, height: 0, buffer: Vec::new() })
+        },
+    };
+
+    painter.left = bounds.x_min.floor() as i32;
+    painter.bottom = bounds.y_min.floor() as i32;
+    painter.width = (bounds.x_max.ceil() as i32 - painter.left).max(0) as usize;
+    painter.height = (bounds.y_max.ceil() as i32 - painter.bottom).max(0) as usize;
+
+    if painter.width * painter.height > MAX_PIXELS {
+        return None;
+    }
+
+    let mut canvas = painter.layer();
+    match root {
+        Root::Paint(paint) => painter.paint(paint, &base, &mut canvas, None, 0),
+        Root::Layers(layers) => {
+            for (glyph, color_index) in layers {
+                let color = painter.color(color_index, 1.);
+                let mask = painter.glyph_mask(glyph, &base);
+                painter.fill(&mut canvas, Some(&mask), |_, _| color);
+            }
+        },
+    }
+
+    let mut buffer = Vec::with_capacity(canvas.len() * 4);
+    for pixel in canvas {
+        buffer.extend(pixel.iter().map(|channel| (channel.clamp(0., 1.) * 255.).round() as u8));
+    }
+
+    Some(ColorGlyph {
+        left: painter.left,
+        top: painter.bottom + painter.height as i32,
+        width: painter.width as i32,
+        height: painter.height as i32,
+        buffer,
+    })
+}
+
+/// Root of a color glyph.
+enum Root {
+    /// `COLR` v1 paint graph.
+    Paint(FT_OpaquePaint),
+
+    /// `COLR` v0 layers with their palette index.
+    Layers(Vec<(FT_UInt, u16)>),
+}
+
+/// Affine transformation, mapping `(x, y)` to `(xx * x + xy * y + dx, yx * x + yy * y + dy)`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Transform {
+    xx: f32,
+    yx: f32,
+    xy: f32,
+    yy: f32,
+    dx: f32,
+    dy: f32,
+}
+
+impl Transform {
+    fn new(xx: f32, yx: f32, xy: f32, yy: f32, dx: f32, dy: f32) -> Self {
+        Self { xx, yx, xy, yy, dx, dy }
+    }
+
+    fn scale(x: f32, y: f32) -> Self {
+        Self::new(x, 0., 0., y, 0., 0.)
+    }
+
+    fn translate(dx: f32, dy: f32) -> Self {
+        Self::new(1., 0., 0., 1., dx, dy)
+    }
+
+    fn from_ft_matrix(matrix: &Matrix) -> Self {
+        Self::new(
+            from_fixed(matrix.xx),
+            from_fixed(matrix.yx),
+            from_fixed(matrix.xy),
+            from_fixed(matrix.yy),
+            0.,
+            0.,
+        )
+    }
+
+    /// Transformation around a center point.
+    fn around(self, center_x: f32, center_y: f32) -> Self {
+        Self::translate(center_x, center_y)
+            .multiply(&self)
+            .multiply(&Self::translate(-center_x, -center_y))
+    }
+
+    /// Combine two transforms, applying `other` before `self`.
+    fn multiply(&self, other: &Self) -> Self {
+        Self {
+            xx: self.xx * other.xx + self.xy * other.yx,
+            yx: self.yx * other.xx + self.yy * other.yx,
+            xy: self.xx * other.xy + self.xy * other.yy,
+            yy: self.yx * other.xy + self.yy * other.yy,
+            dx: self.xx * other.dx + self.xy * other.dy + self.dx,
+            dy: self.yx * other.dx + self.yy * other.dy + self.dy,
+        }
+    }
+
+    fn invert(&self) -> Option<Self> {
+        let det = self.xx * self.yy - self.xy * self.yx;
+        if det.abs() <= f32::EPSILON {
+            return None;
+        }
+
+        let (xx, yx, xy, yy) = (self.yy / det, -self.yx / det, -self.xy / det, self.xx / det);
+        Some(Self {
+            xx,
+            yx,
+            xy,
+            yy,
+            dx: -(xx * self.dx + xy * self.dy),
+            dy: -(yx * self.dx + yy * self.dy),
+        })
+    }
+
+    fn apply(&self, x: f32, y: f32) -> (f32, f32) {
+        (self.xx * x + self.xy * y + self.dx, self.yx * x + self.yy * y + self.dy)
+    }
+}
+
+/// Bounding box in pixels.
+#[derive(Debug, Copy, Clone)]
+struct Bounds {
+    x_min: f32,
+    y_min: f32,
+    x_max: f32,
+    y_max: f32,
+}
+
+impl Bounds {
+    fn from_points(points: &[(f32, f32)]) -> Self {
+        let mut bounds = Bounds {
+            x_min: f32::INFINITY,
+            y_min: f32::INFINITY,
+            x_max: f32::NEG_INFINITY,
+            y_max: f32::NEG_INFINITY,
+        };
+
+        for &(x, y) in points {
+            bounds.x_min = bounds.x_min.min(x);
+            bounds.y_min = bounds.y_min.min(y);
+            bounds.x_max = bounds.x_max.max(x);
+            bounds.y_max = bounds.y_max.max(y);
+        }
+
+        bounds
+    }
+
+    fn union(self, other: Self) -> Self {
+        Bounds {
+            x_min: self.x_min.min(other.x_min),
+            y_min: self.y_min.min(other.y_min),
+            x_max: self.x_max.max(other.x_max),
+            y_max: self.y_max.max(other.y_max),
+        }
+    }
+}
+
+/// Gradient color stops.
+struct ColorLine {
+    extend: FT_PaintExtend,
+    stops: Vec<(f32, Color)>,
+}
+
+impl ColorLine {
+    fn color_at(&self, t: f32) -> Color {
+        let (first, last) = match (self.stops.first(), self.stops.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return [0.; 4],
+        };
+
+        let length = last.0 - first.0;
+        let t = if length <= f32::EPSILON {
+            t.clamp(first.0, last.0)
+        } else {
+            match self.extend {
+                FT_COLR_PAINT_EXTEND_REPEAT => first.0 + (t - first.0).rem_euclid(length),
+                FT_COLR_PAINT_EXTEND_REFLECT => {
+                    let offset = (t - first.0).rem_euclid(2. * length);
+                    first.0 + if offset > length { 2. * length - offset } else { offset }
+                },
+                _ => t.clamp(first.0, last.0),
+            }
+        };
+
+        if t <= first.0 {
+            return first.1;
+        }
+
+        for window in self.stops.windows(2) {
+            let ((start, start_color), (end, end_color)) = (window[0], window[1]);
+            if t > end {
+                continue;
+            }
+
+            let range = end - start;
+            if range <= f32::EPSILON {
+                return end_color;
+            }
+
+            let factor = (t - start) / range;
+            let mut color = [0.; 4];
+            for (i, channel) in color.iter_mut().enumerate() {
+                *channel = start_color[i] + (end_color[i] - start_color[i]) * factor;
+            }
+            return color;
+        }
+
+        last.1
+    }
+}
+
+/// Paint graph renderer.
+struct Painter {
+    library: FT_Library,
+    face: FT_Face,
+    palette: Vec<Color>,
+    paint_graph: bool,
+
+    /// Canvas geometry in pixels, with the Y axis pointing up.
+    left: i32,
+    bottom: i32,
+    width: usize,
+    height: usize,
+}
+
+impl Painter {
+    fn new(library: FT_Library, face: FT_Face) -> Self {
+        let mut palette = Vec::new();
+        unsafe {
+            let mut data = MaybeUninit::<FT_Palette_Data>::zeroed();
+            let mut colors: *mut FT_Color = ptr::null_mut();
+            if FT_Palette_Data_Get(face, data.as_mut_ptr()) == 0
+                && FT_Palette_Select(face, 0, &mut colors) == 0
+                && !colors.is_null()
+            {
+                let entries = data.assume_init().num_palette_entries as usize;
+                for color in std::slice::from_raw_parts(colors, entries) {
+                    let alpha = f32::from(color.alpha) / 255.;
+                    palette.push([
+                        f32::from(color.red) / 255. * alpha,
+                        f32::from(color.green) / 255. * alpha,
+                        f32::from(color.blue) / 255. * alpha,
+                        alpha,
+                    ]);
+                }
+            }
+        }
+
+        // The paint API was experimental before FreeType 2.13 and its structures had a different
+        // layout, so the paint graph is only used with later versions.
+        let (mut major, mut minor, mut patch) = (0, 0, 0);
+        unsafe { FT_Library_Version(library, &mut major, &mut minor, &mut patch) };
+        let paint_graph = (major, minor) >= (2, 13);
+
+        Self { library, face, palette, paint_graph, left: 0, bottom: 0, width: 0, height: 0 }
+    }
+
+    /// Create an empty transparent layer covering the entire canvas.
+    fn layer(&self) -> Vec<Color> {
+        vec![[0.; 4]; self.width * self.height]
+    }
+
+    /// Resolve a palette index to a premultiplied color.
+    fn color(&self, palette_index: u16, alpha: f32) -> Color {
+        let color = match palette_index {
+            FOREGROUND_PALETTE_INDEX => FOREGROUND_COLOR,
+            index => self.palette.get(index as usize).copied().unwrap_or(FOREGROUND_COLOR),
+        };
+        color.map(|channel| channel * alpha)
+    }
+
+    /// Get the root of a glyph's paint graph.
+    fn color_glyph_paint(&self, glyph_index: FT_UInt) -> Option<FT_OpaquePaint> {
+        if !self.paint_graph {
+            return None;
+        }
+
+        unsafe {
+            let mut paint = MaybeUninit::<FT_OpaquePaint>::zeroed();
+            if FT_Get_Color_Glyph_Paint(
+                self.face,
+                glyph_index,
+                FT_COLOR_NO_ROOT_TRANSFORM,
+                paint.as_mut_ptr(),
+            ) == 0
+            {
+                return None;
+            }
+            Some(paint.assume_init())
+        }
+    }
+
+    /// Get the next layer of a `PaintColrLayers` table.
+    fn next_layer(&self, iterator: &mut FT_LayerIterator) -> Option<FT_OpaquePaint> {
+        unsafe {
+            let mut layer = MaybeUninit::<FT_OpaquePaint>::zeroed();
+            if FT_Get_Paint_Layers(self.face, iterator, layer.as_mut_ptr()) == 0 {
+                return None;
+            }
+            Some(layer.assume_init())
+        }
+    }
+
+    /// Get the clip box of a glyph in pixels.
+    fn clip_box(&self, glyph_index: u32, matrix: Option<&Matrix>) -> Option<Bounds> {
+        let clip_box = unsafe {
+            let mut clip_box = MaybeUninit::<FT_ClipBox>::zeroed();
+            if FT_Get_Color_Glyph_ClipBox(self.face, glyph_index, clip_box.as_mut_ptr()) == 0 {
+                return None;
+            }
+            clip_box.assume_init()
+        };
+
+        let transform = matrix.map(Transform::from_ft_matrix).unwrap_or(Transform::scale(1., 1.));
+        let corners =
+            [clip_box.bottom_left, clip_box.top_left, clip_box.top_right, clip_box.bottom_right]
+                .map(|corner| transform.apply(corner.x as f32 / 64., corner.y as f32 / 64.));
+
+        Some(Bounds::from_points(&corners))
+    }
+
+    /// Load a glyph's outline in font units.
+    ///
+    /// The outline is stored in the face's glyph slot and only valid until the next glyph load.
+    fn load_outline(&self, glyph_index: FT_UInt) -> Option<*mut FT_Outline> {
+        unsafe {
+            let flags = FT_LOAD_NO_SCALE | FT_LOAD_NO_BITMAP | FT_LOAD_NO_HINTING;
+            if FT_Load_Glyph(self.face, glyph_index, flags) != 0 {
+                return None;
+            }
+
+            let slot = (*self.face).glyph;
+            if (*slot).format != FT_GLYPH_FORMAT_OUTLINE {
+                return None;
+            }
+
+            Some(&mut (*slot).outline)
+        }
+    }
+
+    /// Transform a glyph's outline into pixel space, in 26.6 fixed point format.
+    unsafe fn transform_outline(outline: *mut FT_Outline, transform: &Transform) {
+        let matrix = FT_Matrix {
+            xx: to_fixed(transform.xx * 64.),
+            xy: to_fixed(transform.xy * 64.),
+            yx: to_fixed(transform.yx * 64.),
+            yy: to_fixed(transform.yy * 64.),
+        };
+        FT_Outline_Transform(outline, &matrix);
+
+        let dx = (transform.dx * 64.).round() as FT_Pos;
+        let dy = (transform.dy * 64.).round() as FT_Pos;
+        FT_Outline_Translate(outline, dx, dy);
+    }
+
+    /// Get the bounding box of a transformed glyph.
+    fn glyph_bounds(&self, glyph_index: FT_UInt, transform: &Transform) -> Option<Bounds> {
+        let outline = self.load_outline(glyph_index)?;
+
+        unsafe {
+            if (*outline).n_points == 0 {
+                return None;
+            }
+
+            Self::transform_outline(outline, transform);
+
+            let mut cbox = FT_BBox { xMin: 0, yMin: 0, xMax: 0, yMax: 0 };
+            FT_Outline_Get_CBox(outline, &mut cbox);
+
+            Some(Bounds {
+                x_min: cbox.xMin as f32 / 64.,
+                y_min: cbox.yMin as f32 / 64.,
+                x_max: cbox.xMax as f32 / 64.,
+                y_max: cbox.yMax as f32 / 64.,
+            })
+        }
+    }
+
+    /// Get the coverage of a transformed glyph for every canvas pixel.
+    fn glyph_mask(&self, glyph_index: FT_UInt, transform: &Transform) -> Vec<f32> {
+        let mut mask = vec![0u8; self.width * self.height];
+
+        if let Some(outline) = self.load_outline(glyph_index) {
+            unsafe {
+                let canvas_offset = Transform::translate(-self.left as f32, -self.bottom as f32);
+                Self::transform_outline(outline, &canvas_offset.multiply(transform));
+
+                let bitmap = FT_Bitmap {
+                    rows: self.height as _,
+                    width: self.width as _,
+                    pitch: self.width as _,
+                    buffer: mask.as_mut_ptr(),
+                    num_grays: 256,
+                    pixel_mode: FT_PIXEL_MODE_GRAY as c_char,
+                    palette_mode: 0,
+                    palette: ptr::null_mut(),
+                };
+                FT_Outline_Get_Bitmap(self.library, outline, &bitmap);
+            }
+        }
+
+        mask.into_iter().map(|coverage| f32::from(coverage) / 255.).collect()
+    }
+
+    /// Composite a paint over a layer using source-over.
+    ///
+    /// The `shader` is called with the center of every pixel in canvas space.
+    fn fill<F>(&self, target: &mut [Color], mask: Option<&[f32]>, shader: F)
+    where
+        F: Fn(f32, f32) -> Color,
+    {
+        for row in 0..self.height {
+            let y = (self.bottom + (self.height - row) as i32) as f32 - 0.5;
+            for column in 0..self.width {
+                let index = row * self.width + column;
+                let coverage = mask.map_or(1., |mask| mask[index]);
+                if coverage <= 0. {
+                    continue;
+                }
+
+                let x = (self.left + column as i32) as f32 + 0.5;
+                let source = shader(x, y).map(|channel| channel * coverage);
+                let destination = &mut target[index];
+                for i in 0..4 {
+                    destination[i] = source[i] + destination[i] * (1. - source[3]);
+                }
+            }
+        }
+    }
+
+    /// Read a paint table.
+    fn get_paint(&self, opaque: FT_OpaquePaint) -> Option<FT_COLR_Paint> {
+        unsafe {
+            let mut paint = MaybeUninit::<FT_COLR_Paint>::zeroed();
+            if FT_Get_Paint(self.face, opaque, paint.as_mut_ptr()) == 0 {
+                return None;
+            }
+            Some(paint.assume_init())
+        }
+    }
+
+    /// Read all stops of a color line.
+    fn color_line(&self, color_line: &FT_ColorLine) -> ColorLine {
+        let mut iterator = color_line.color_stop_iterator;
+        let mut stops = Vec::new();
+
+        unsafe {
+            let mut stop = MaybeUninit::<FT_ColorStop>::zeroed();
+            while FT_Get_Colorline_Stops(self.face, stop.as_mut_ptr(), &mut iterator) != 0 {
+                let stop = stop.assume_init_ref();
+                let alpha = f32::from(stop.color.alpha) / 16384.;
+                let color = self.color(stop.color.palette_index, alpha);
+                stops.push((from_fixed(stop.stop_offset), color));
+            }
+        }
+
+        stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        ColorLine { extend: color_line.extend, stops }
+    }
+
+    /// Compute the bounds of a paint graph without clip box.
+    fn paint_bounds(
+        &self,
+        opaque: FT_OpaquePaint,
+        transform: &Transform,
+        depth: usize,
+    ) -> Option<Bounds> {
+        if depth > MAX_PAINT_DEPTH {
+            return None;
+        }
+
+        let paint = self.get_paint(opaque)?;
+        unsafe {
+            match paint.format {
+                FT_COLR_PAINTFORMAT_COLR_LAYERS => {
+                    let mut iterator = paint.u.colr_layers.layer_iterator;
+                    let mut bounds = None;
+                    while let Some(layer) = self.next_layer(&mut iterator) {
+                        let layer_bounds = self.paint_bounds(layer, transform, depth + 1);
+                        bounds = union_bounds(bounds, layer_bounds);
+                    }
+                    bounds
+                },
+                FT_COLR_PAINTFORMAT_GLYPH => self.glyph_bounds(paint.u.glyph.glyphID, transform),
+                FT_COLR_PAINTFORMAT_COLR_GLYPH => {
+                    let root = self.color_glyph_paint(paint.u.colr_glyph.glyphID)?;
+                    self.paint_bounds(root, transform, depth + 1)
+                },
+                FT_COLR_PAINTFORMAT_COMPOSITE => {
+                    let composite = paint.u.composite;
+                    let source = self.paint_bounds(composite.source_paint, transform, depth + 1);
+                    let backdrop =
+                        self.paint_bounds(composite.backdrop_paint, transform, depth + 1);
+                    union_bounds(source, backdrop)
+                },
+                _ => {
+                    let (child, child_transform) = Self::transformed_child(&paint)?;
+                    self.paint_bounds(child, &transform.multiply(&child_transform), depth + 1)
+                },
+            }
+        }
+    }
+
+    /// Get the child and transformation of a transform paint.
+    unsafe fn transformed_child(paint: &FT_COLR_Paint) -> Option<(FT_OpaquePaint, Transform)> {
+        let result = match paint.format {
+            FT_COLR_PAINTFORMAT_TRANSFORM => {
+                let affine = paint.u.transform.affine;
+                let transform = Transform::new(
+                    from_fixed(affine.xx),
+                    from_fixed(affine.yx),
+                    from_fixed(affine.xy),
+                    from_fixed(affine.yy),
+                    from_fixed(affine.dx),
+                    from_fixed(affine.dy),
+                );
+                (paint.u.transform.paint, transform)
+            },
+            FT_COLR_PAINTFORMAT_TRANSLATE => {
+                let translate = paint.u.translate;
+                (
+                    translate.paint,
+                    Transform::translate(from_fixed(translate.dx), from_fixed(translate.dy)),
+                )
+            },
+            FT_COLR_PAINTFORMAT_SCALE => {
+                let scale = paint.u.scale;
+                let transform =
+                    Transform::scale(from_fixed(scale.scale_x), from_fixed(scale.scale_y))
+                        .around(from_fixed(scale.center_x), from_fixed(scale.center_y));
+                (scale.paint, transform)
+            },
+            FT_COLR_PAINTFORMAT_ROTATE => {
+                let rotate = paint.u.rotate;
+                let angle = from_fixed(rotate.angle) * std::f32::consts::PI;
+                let (sin, cos) = angle.sin_cos();
+                let transform = Transform::new(cos, sin, -sin, cos, 0., 0.)
+                    .around(from_fixed(rotate.center_x), from_fixed(rotate.center_y));
+                (rotate.paint, transform)
+            },
+            FT_COLR_PAINTFORMAT_SKEW => {
+                let skew = paint.u.skew;
+                let x_skew = (from_fixed(skew.x_skew_angle) * std::f32::consts::PI).tan();
+                let y_skew = (from_fixed(skew.y_skew_angle) * std::f32::consts::PI).tan();
+                let transform = Transform::new(1., y_skew, -x_skew, 1., 0., 0.)
+                    .around(from_fixed(skew.center_x), from_fixed(skew.center_y));
+                (skew.paint, transform)
+            },
+            _ => return None,
+        };
+
+        Some(result)
+    }
+
+    /// Render a paint graph node into the `target` layer.
+    fn paint(
+        &self,
+        opaque: FT_OpaquePaint,
+        transform: &Transform,
+        target: &mut [Color],
+        mask: Option<&[f32]>,
+        depth: usize,
+    ) {
+        if depth > MAX_PAINT_DEPTH {
+            return;
+        }
+
+        let paint = match self.get_paint(opaque) {
+            Some(paint) => paint,
+            None => return,
+        };
+
+        unsafe {
+            match paint.format {
+                FT_COLR_PAINTFORMAT_COLR_LAYERS => {
+                    let mut iterator = paint.u.colr_layers.layer_iterator;
+                    while let Some(layer) = self.next_layer(&mut iterator) {
+                        self.paint(layer, transform, target, mask, depth + 1);
+                    }
+                },
+                FT_COLR_PAINTFORMAT_SOLID => {
+                    let color = paint.u.solid.color;
+                    let color = self.color(color.palette_index, f32::from(color.alpha) / 16384.);
+                    self.fill(target, mask, |_, _| color);
+                },
+                FT_COLR_PAINTFORMAT_LINEAR_GRADIENT => {
+                    let gradient = paint.u.linear_gradient;
+                    let color_line = self.color_line(&gradient.colorline);
+                    let inverse = match transform.invert() {
+                        Some(inverse) => inverse,
+                        None => return,
+                    };
+
+                    let (x0, y0) = from_fixed_vector(gradient.p0);
+                    let (x1, y1) = from_fixed_vector(gradient.p1);
+                    let (x2, y2) = from_fixed_vector(gradient.p2);
+
+                    // Project `p1` onto the line through `p0` perpendicular to `p0p2`.
+                    let (perp_x, perp_y) = (y2 - y0, x0 - x2);
+                    let perp_length = perp_x * perp_x + perp_y * perp_y;
+                    let (dx, dy) = if perp_length <= f32::EPSILON {
+                        (x1 - x0, y1 - y0)
+                    } else {
+                        let factor = ((x1 - x0) * perp_x + (y1 - y0) * perp_y) / perp_length;
+                        (perp_x * factor, perp_y * factor)
+                    };
+                    let length = dx * dx + dy * dy;
+                    if length <= f32::EPSILON {
+                        return;
+                    }
+
+                    self.fill(target, mask, |x, y| {
+                        let (x, y) = inverse.apply(x, y);
+                        color_line.color_at(((x - x0) * dx + (y - y0) * dy) / length)
+                    });
+                },
+                FT_COLR_PAINTFORMAT_RADIAL_GRADIENT => {
+                    let gradient = paint.u.radial_gradient;
+                    let color_line = self.color_line(&gradient.colorline);
+                    let inverse = match transform.invert() {
+                        Some(inverse) => inverse,
+                        None => return,
+                    };
+
+                    let (cx0, cy0) = from_fixed_vector(gradient.c0);
+                    let (cx1, cy1) = from_fixed_vector(gradient.c1);
+                    let r0 = from_fixed(gradient.r0);
+                    let r1 = from_fixed(gradient.r1);
+
+                    self.fill(target, mask, |x, y| {
+                        let (x, y) = inverse.apply(x, y);
+                        match radial_gradient_t(x - cx0, y - cy0, cx1 - cx0, cy1 - cy0, r0, r1) {
+                            Some(t) => color_line.color_at(t),
+                            None => [0.; 4],
+                        }
+                    });
+                },
+                FT_COLR_PAINTFORMAT_SWEEP_GRADIENT => {
+                    let gradient = paint.u.sweep_gradient;
+                    let color_line = self.color_line(&gradient.colorline);
+                    let inverse = match transform.invert() {
+                        Some(inverse) => inverse,
+                        None => return,
+                    };
+
+                    let (cx, cy) = from_fixed_vector(gradient.center);
+                    // Sweep angles are stored with a bias of 180 degrees, which FreeType does
+                    // not remove.
+                    let start = (from_fixed(gradient.start_angle) + 1.) * 180.;
+                    let end = (from_fixed(gradient.end_angle) + 1.) * 180.;
+                    let range = end - start;
+
+                    self.fill(target, mask, |x, y| {
+                        let (x, y) = inverse.apply(x, y);
+                        let angle = (y - cy).atan2(x - cx).to_degrees().rem_euclid(360.);
+                        if range.abs() <= f32::EPSILON {
+                            let t = if angle < start { 0. } else { 1. };
+                            color_line.color_at(t)
+                        } else {
+                            color_line.color_at((angle - start) / range)
+                        }
+                    });
+                },
+                FT_COLR_PAINTFORMAT_GLYPH => {
+                    let glyph = paint.u.glyph;
+                    let mut glyph_mask = self.glyph_mask(glyph.glyphID, transform);
+                    if let Some(mask) = mask {
+                        for (coverage, clip) in glyph_mask.iter_mut().zip(mask) {
+                            *coverage *= clip;
+                        }
+                    }
+                    self.paint(glyph.paint, transform, target, Some(&glyph_mask), depth + 1);
+                },
+                FT_COLR_PAINTFORMAT_COLR_GLYPH => {
+                    if let Some(root) = self.color_glyph_paint(paint.u.colr_glyph.glyphID) {
+                        self.paint(root, transform, target, mask, depth + 1);
+                    }
+                },
+                FT_COLR_PAINTFORMAT_COMPOSITE => {
+                    let composite = paint.u.composite;
+
+                    let mut backdrop = self.layer();
+                    self.paint(composite.backdrop_paint, transform, &mut backdrop, None, depth + 1);
+                    let mut source = self.layer();
+                    self.paint(composite.source_paint, transform, &mut source, None, depth + 1);
+
+                    for (source, backdrop) in source.iter_mut().zip(&backdrop) {
+                        *source = composite_pixel(composite.composite_mode, source, backdrop);
+                    }
+
+                    let width = self.width;
+                    self.fill(target, mask, |x, y| {
+                        let column = (x - self.left as f32) as usize;
+                        let row = self.height - 1 - (y - self.bottom as f32) as usize;
+                        source[row * width + column]
+                    });
+                },
+                _ => {
+                    if let Some((child, child_transform)) = Self::transformed_child(&paint) {
+                        let transform = transform.multiply(&child_transform);
+                        self.paint(child, &transform, target, mask, depth + 1);
+                    }
+                },
+            }
+        }
+    }
+}
+
+/// Union of two optional bounding boxes.
+fn union_bounds(lhs: Option<Bounds>, rhs: Option<Bounds>) -> Option<Bounds> {
+    match (lhs, rhs) {
+        (Some(lhs), Some(rhs)) => Some(lhs.union(rhs)),
+        (lhs, rhs) => lhs.or(rhs),
+    }
+}
+
+/// Find the gradient position of a point relative to the start circle of a two-point conical
+/// gradient.
+///
+/// Returns the largest `t` for which the point lies on the interpolated circle with a
+/// non-negative radius.
+fn radial_gradient_t(px: f32, py: f32, cdx: f32, cdy: f32, r0: f32, r1: f32) -> Option<f32> {
+    let dr = r1 - r0;
+    let a = cdx * cdx + cdy * cdy - dr * dr;
+    let b = px * cdx + py * cdy + r0 * dr;
+    let c = px * px + py * py - r0 * r0;
+
+    if a.abs() <= f32::EPSILON {
+        if b.abs() <= f32::EPSILON {
+            return None;
+        }
+        let t = c / (2. * b);
+        return (r0 + t * dr >= 0.).then_some(t);
+    }
+
+    let discriminant = b * b - a * c;
+    if discriminant < 0. {
+        return None;
+    }
+
+    let root = discriminant.sqrt();
+    let (t1, t2) = ((b + root) / a, (b - root) / a);
+    let (max, min) = if t1 > t2 { (t1, t2) } else { (t2, t1) };
+
+    if r0 + max * dr >= 0. {
+        Some(max)
+    } else if r0 + min * dr >= 0. {
+        Some(min)
+    } else {
+        None
+    }
+}
+
+/// Composite two premultiplied pixels.
+fn composite_pixel(mode: FT_Composite_Mode, source: &Color, backdrop: &Color) -> Color {
+    let (sa, da) = (source[3], backdrop[3]);
+
+    // Porter-Duff operators as `(source factor, backdrop factor)`.
+    let porter_duff = match mode {
+        FT_COLR_COMPOSITE_CLEAR => Some((0., 0.)),
+        FT_COLR_COMPOSITE_SRC => Some((1., 0.)),
+        FT_COLR_COMPOSITE_DEST => Some((0., 1.)),
+        FT_COLR_COMPOSITE_SRC_OVER => Some((1., 1. - sa)),
+        FT_COLR_COMPOSITE_DEST_OVER => Some((1. - da, 1.)),
+        FT_COLR_COMPOSITE_SRC_IN => Some((da, 0.)),
+        FT_COLR_COMPOSITE_DEST_IN => Some((0., sa)),
+        FT_COLR_COMPOSITE_SRC_OUT => Some((1. - da, 0.)),
+        FT_COLR_COMPOSITE_DEST_OUT => Some((0., 1. - sa)),
+        FT_COLR_COMPOSITE_SRC_ATOP => Some((da, 1. - sa)),
+        FT_COLR_COMPOSITE_DEST_ATOP => Some((1. - da, sa)),
+        FT_COLR_COMPOSITE_XOR => Some((1. - da, 1. - sa)),
+        FT_COLR_COMPOSITE_PLUS => Some((1., 1.)),
+        _ => None,
+    };
+
+    if let Some((source_factor, backdrop_factor)) = porter_duff {
+        let mut result = [0.; 4];
+        for i in 0..4 {
+            result[i] = (source[i] * source_factor + backdrop[i] * backdrop_factor).min(1.);
+        }
+        return result;
+    }
+
+    // Blend modes operate on unpremultiplied colors.
+    let unpremultiply = |color: &Color| {
+        let alpha = color[3];
+        if alpha <= 0. {
+            [0.; 3]
+        } else {
+            [color[0] / alpha, color[1] / alpha, color[2] / alpha]
+        }
+    };
+    let cs = unpremultiply(source);
+    let cb = unpremultiply(backdrop);
+
+    let blended = match mode {
+        FT_COLR_COMPOSITE_HSL_HUE => set_lum(set_sat(cs, sat(cb)), lum(cb)),
+        FT_COLR_COMPOSITE_HSL_SATURATION => set_lum(set_sat(cb, sat(cs)), lum(cb)),
+        FT_COLR_COMPOSITE_HSL_COLOR => set_lum(cs, lum(cb)),
+        FT_COLR_COMPOSITE_HSL_LUMINOSITY => set_lum(cb, lum(cs)),
+        _ => {
+            let mut blended = [0.; 3];
+            for i in 0..3 {
+                blended[i] = blend_channel(mode, cs[i], cb[i]);
+            }
+            blended
+        },
+    };
+
+    let mut result = [0.; 4];
+    for i in 0..3 {
+        result[i] = (1. - da) * source[i] + (1. - sa) * backdrop[i] + sa * da * blended[i];
+    }
+    result[3] = sa + da - sa * da;
+    result
+}
+
+/// Separable blend modes.
+fn blend_channel(mode: FT_Composite_Mode, cs: f32, cb: f32) -> f32 {
+    let multiply = |cs: f32, cb: f32| cs * cb;
+    let screen = |cs: f32, cb: f32| cs + cb - cs * cb;
+    let hard_light = |cs: f32, cb: f32| {
+        if cs <= 0.5 {
+            multiply(cb, 2. * cs)
+        } else {
+            screen(cb, 2. * cs - 1.)
+        }
+    };
+
+    match mode {
+        FT_COLR_COMPOSITE_SCREEN => screen(cs, cb),
+        FT_COLR_COMPOSITE_OVERLAY => hard_light(cb, cs),
+        FT_COLR_COMPOSITE_DARKEN => cs.min(cb),
+        FT_COLR_COMPOSITE_LIGHTEN => cs.max(cb),
+        FT_COLR_COMPOSITE_COLOR_DODGE => {
+            if cb <= 0. {
+                0.
+            } else if cs >= 1. {
+                1.
+            } else {
+                (cb / (1. - cs)).min(1.)
+            }
+        },
+        FT_COLR_COMPOSITE_COLOR_BURN => {
+            if cb >= 1. {
+                1.
+            } else if cs <= 0. {
+                0.
+            } else {
+                1. - ((1. - cb) / cs).min(1.)
+            }
+        },
+        FT_COLR_COMPOSITE_HARD_LIGHT => hard_light(cs, cb),
+        FT_COLR_COMPOSITE_SOFT_LIGHT => {
+            if cs <= 0.5 {
+                cb - (1. - 2. * cs) * cb * (1. - cb)
+            } else {
+                let d = if cb <= 0.25 { ((16. * cb - 12.) * cb + 4.) * cb } else { cb.sqrt() };
+                cb + (2. * cs - 1.) * (d - cb)
+            }
+        },
+        FT_COLR_COMPOSITE_DIFFERENCE => (cs - cb).abs(),
+        FT_COLR_COMPOSITE_EXCLUSION => cs + cb - 2. * cs * cb,
+        FT_COLR_COMPOSITE_MULTIPLY => multiply(cs, cb),
+        _ => cs,
+    }
+}
+
+fn lum(color: [f32; 3]) -> f32 {
+    0.3 * color[0] + 0.59 * color[1] + 0.11 * color[2]
+}
+
+fn sat(color: [f32; 3]) -> f32 {
+    color[0].max(color[1]).max(color[2]) - color[0].min(color[1]).min(color[2])
+}
+
+fn clip_color(color: [f32; 3]) -> [f32; 3] {
+    let l = lum(color);
+    let n = color[0].min(color[1]).min(color[2]);
+    let x = color[0].max(color[1]).max(color[2]);
+
+    let mut color = color;
+    if n < 0. {
+        color = color.map(|c| l + (c - l) * l / (l - n));
+    }
+    if x > 1. {
+        color = color.map(|c| l + (c - l) * (1. - l) / (x - l));
+    }
+    color
+}
+
+fn set_lum(color: [f32; 3], l: f32) -> [f32; 3] {
+    let d = l - lum(color);
+    clip_color(color.map(|c| c + d))
+}
+
+fn set_sat(color: [f32; 3], s: f32) -> [f32; 3] {
+    let max = color[0].max(color[1]).max(color[2]);
+    let min = color[0].min(color[1]).min(color[2]);
+    if max - min <= 0. {
+        return [0.; 3];
+    }
+    color.map(|c| (c - min) * s / (max - min))
+}
+
+#[inline]
+fn from_fixed(value: FT_Fixed) -> f32 {
+    value as f32 / 65536.
+}
+
+#[inline]
+fn to_fixed(value: f32) -> FT_Fixed {
+    (value * 65536.).round() as FT_Fixed
+}
+
+#[inline]
+fn from_fixed_vector(vector: FT_Vector) -> (f32, f32) {
+    (from_fixed(vector.x), from_fixed(vector.y))
+}
+
+/// FreeType's `COLR` API, which isn't exposed by `freetype-sys`.
+#[allow(non_camel_case_types, non_snake_case)]
+mod ffi {
+    use freetype::freetype_sys::{
+        FT_Bool, FT_Byte, FT_Error, FT_F2Dot14, FT_Face, FT_Fixed, FT_Int, FT_Library, FT_Pos,
+        FT_UInt, FT_UShort, FT_Vector,
+    };
+    use libc::c_uint;
+
+    pub type FT_PaintFormat = c_uint;
+    pub const FT_COLR_PAINTFORMAT_COLR_LAYERS: FT_PaintFormat = 1;
+    pub const FT_COLR_PAINTFORMAT_SOLID: FT_PaintFormat = 2;
+    pub const FT_COLR_PAINTFORMAT_LINEAR_GRADIENT: FT_PaintFormat = 4;
+    pub const FT_COLR_PAINTFORMAT_RADIAL_GRADIENT: FT_PaintFormat = 6;
+    pub const FT_COLR_PAINTFORMAT_SWEEP_GRADIENT: FT_PaintFormat = 8;
+    pub const FT_COLR_PAINTFORMAT_GLYPH: FT_PaintFormat = 10;
+    pub const FT_COLR_PAINTFORMAT_COLR_GLYPH: FT_PaintFormat = 11;
+    pub const FT_COLR_PAINTFORMAT_TRANSFORM: FT_PaintFormat = 12;
+    pub const FT_COLR_PAINTFORMAT_TRANSLATE: FT_PaintFormat = 14;
+    pub const FT_COLR_PAINTFORMAT_SCALE: FT_PaintFormat = 16;
+    pub const FT_COLR_PAINTFORMAT_ROTATE: FT_PaintFormat = 24;
+    pub const FT_COLR_PAINTFORMAT_SKEW: FT_PaintFormat = 28;
+    pub const FT_COLR_PAINTFORMAT_COMPOSITE: FT_PaintFormat = 32;
+
+    pub type FT_PaintExtend = c_uint;
+    pub const FT_COLR_PAINT_EXTEND_REPEAT: FT_PaintExtend = 1;
+    pub const FT_COLR_PAINT_EXTEND_REFLECT: FT_PaintExtend = 2;
+
+    pub type FT_Composite_Mode = c_uint;
+    pub const FT_COLR_COMPOSITE_CLEAR: FT_Composite_Mode = 0;
+    pub const FT_COLR_COMPOSITE_SRC: FT_Composite_Mode = 1;
+    pub const FT_COLR_COMPOSITE_DEST: FT_Composite_Mode = 2;
+    pub const FT_COLR_COMPOSITE_SRC_OVER: FT_Composite_Mode = 3;
+    pub const FT_COLR_COMPOSITE_DEST_OVER: FT_Composite_Mode = 4;
+    pub const FT_COLR_COMPOSITE_SRC_IN: FT_Composite_Mode = 5;
+    pub const FT_COLR_COMPOSITE_DEST_IN: FT_Composite_Mode = 6;
+    pub const FT_COLR_COMPOSITE_SRC_OUT: FT_Composite_Mode = 7;
+    pub const FT_COLR_COMPOSITE_DEST_OUT: FT_Composite_Mode = 8;
+    pub const FT_COLR_COMPOSITE_SRC_ATOP: FT_Composite_Mode = 9;
+    pub const FT_COLR_COMPOSITE_DEST_ATOP: FT_Composite_Mode = 10;
+    pub const FT_COLR_COMPOSITE_XOR: FT_Composite_Mode = 11;
+    pub const FT_COLR_COMPOSITE_PLUS: FT_Composite_Mode = 12;
+    pub const FT_COLR_COMPOSITE_SCREEN: FT_Composite_Mode = 13;
+    pub const FT_COLR_COMPOSITE_OVERLAY: FT_Composite_Mode = 14;
+    pub const FT_COLR_COMPOSITE_DARKEN: FT_Composite_Mode = 15;
+    pub const FT_COLR_COMPOSITE_LIGHTEN: FT_Composite_Mode = 16;
+    pub const FT_COLR_COMPOSITE_COLOR_DODGE: FT_Composite_Mode = 17;
+    pub const FT_COLR_COMPOSITE_COLOR_BURN: FT_Composite_Mode = 18;
+    pub const FT_COLR_COMPOSITE_HARD_LIGHT: FT_Composite_Mode = 19;
+    pub const FT_COLR_COMPOSITE_SOFT_LIGHT: FT_Composite_Mode = 20;
+    pub const FT_COLR_COMPOSITE_DIFFERENCE: FT_Composite_Mode = 21;
+    pub const FT_COLR_COMPOSITE_EXCLUSION: FT_Composite_Mode = 22;
+    pub const FT_COLR_COMPOSITE_MULTIPLY: FT_Composite_Mode = 23;
+    pub const FT_COLR_COMPOSITE_HSL_HUE: FT_Composite_Mode = 24;
+    pub const FT_COLR_COMPOSITE_HSL_SATURATION: FT_Composite_Mode = 25;
+    pub const FT_COLR_COMPOSITE_HSL_COLOR: FT_Composite_Mode = 26;
+    pub const FT_COLR_COMPOSITE_HSL_LUMINOSITY: FT_Composite_Mode = 27;
+
+    pub type FT_Color_Root_Transform = c_uint;
+    pub const FT_COLOR_NO_ROOT_TRANSFORM: FT_Color_Root_Transform = 1;
+
+    #[repr(C)]
+    #[derive(Copy, Clone)]
+    pub struct FT_Color {
+        pub blue: FT_Byte,
+        pub green: FT_Byte,
+        pub red: FT_Byte,
+        pub alpha: FT_Byte,
+    }
+
+    #[repr(C)]
+    #[derive(Copy, Clone)]
+    pub struct FT_Palette_Data {
+        pub num_palettes: FT_UShort,
+        pub palette_name_ids: *const FT_UShort,
+        pub palette_flags: *const FT_UShort,
+        pub num_palette_entries: FT_UShort,
+        pub palette_entry_name_ids: *const FT_UShort,
+    }
+
+    #[repr(C)]
+    #[derive(Copy, Clone)]
+    pub struct FT_OpaquePaint {
+        pub p: *mut FT_Byte,
+        pub insert_root_transform: FT_Bool,
+    }
+
+    #[repr(C)]
+    #[derive(Copy, Clone)]
+    pub struct FT_ColorStopIterator {
+        pub num_color_stops: FT_UInt,
+        pub current_color_stop: FT_UInt,
+        pub p: *mut FT_Byte,
+        pub read_variable: FT_Bool,
+    }
+
+    #[repr(C)]
+    #[derive(Copy, Clone)]
+    pub struct FT_ColorIndex {
+        pub palette_index: u16,
+        pub alpha: FT_F2Dot14,
+    }
+
+    #[repr(C)]
+    #[derive(Copy, Clone)]
+    pub struct FT_ColorStop {
+        pub stop_offset: FT_Fixed,
+        pub color: FT_ColorIndex,
+    }
+
+    #[repr(C)]
+    #[derive(Copy, Clone)]
+    pub struct FT_ColorLine {
+        pub extend: FT_PaintExtend,
+        pub color_stop_iterator: FT_ColorStopIterator,
+    }
+
+    #[repr(C)]
+    #[derive(Copy, Clone)]
+    pub struct FT_Affine23 {
+        pub xx: FT_Fixed,
+        pub xy: FT_Fixed,
+        pub dx: FT_Fixed,
+        pub yx: FT_Fixed,
+        pub yy: FT_Fixed,
+        pub dy: FT_Fixed,
+    }
+
+    #[repr(C)]
+    #[derive(Copy, Clone)]
+    pub struct FT_LayerIterator {
+        pub num_layers: FT_UInt,
+        pub layer: FT_UInt,
+        pub p: *mut FT_Byte,
+    }
+
+    #[repr(C)]
+    #[derive(Copy, Clone)]
+    pub struct FT_PaintColrLayers {
+        pub layer_iterator: FT_LayerIterator,
+    }
+
+    #[repr(C)]
+    #[derive(Copy, Clone)]
+    pub struct FT_PaintSolid {
+        pub color: FT_ColorIndex,
+    }
+
+    #[repr(C)]
+    #[derive(Copy, Clone)]
+    pub struct FT_PaintLinearGradient {
+        pub colorline: FT_ColorLine,
+        pub p0: FT_Vector,
+        pub p1: FT_Vector,
+        pub p2: FT_Vector,
+    }
+
+    #[repr(C)]
+    #[derive(Copy, Clone)]
+    pub struct FT_PaintRadialGradient {
+        pub colorline: FT_ColorLine,
+        pub c0: FT_Vector,
+        pub r0: FT_Pos,
+        pub c1: FT_Vector,
+        pub r1: FT_Pos,
+    }
+
+    #[repr(C)]
+    #[derive(Copy, Clone)]
+    pub struct FT_PaintSweepGradient {
+        pub colorline: FT_ColorLine,
+        pub center: FT_Vector,
+        pub start_angle: FT_Fixed,
+        pub end_angle: FT_Fixed,
+    }
+
+    #[repr(C)]
+    #[derive(Copy, Clone)]
+    pub struct FT_PaintGlyph {
+        pub paint: FT_OpaquePaint,
+        pub glyphID: FT_UInt,
+    }
+
+    #[repr(C)]
+    #[derive(Copy, Clone)]
+    pub struct FT_PaintColrGlyph {
+        pub glyphID: FT_UInt,
+    }
+
+    #[repr(C)]
+    #[derive(Copy, Clone)]
+    pub struct FT_PaintTransform {
+        pub paint: FT_OpaquePaint,
+        pub affine: FT_Affine23,
+    }
+
+    #[repr(C)]
+    #[derive(Copy, Clone)]
+    pub struct FT_PaintTranslate {
+        pub paint: FT_OpaquePaint,
+        pub dx: FT_Fixed,
+        pub dy: FT_Fixed,
+    }
+
+    #[repr(C)]
+    #[derive(Copy, Clone)]
+    pub struct FT_PaintScale {
+        pub paint: FT_OpaquePaint,
+        pub scale_x: FT_Fixed,
+        pub scale_y: FT_Fixed,
+        pub center_x: FT_Fixed,
+        pub center_y: FT_Fixed,
+    }
+
+    #[repr(C)]
+    #[derive(Copy, Clone)]
+    pub struct FT_PaintRotate {
+        pub paint: FT_OpaquePaint,
+        pub angle: FT_Fixed,
+        pub center_x: FT_Fixed,
+        pub center_y: FT_Fixed,
+    }
+
+    #[repr(C)]
+    #[derive(Copy, Clone)]
+    pub struct FT_PaintSkew {
+        pub paint: FT_OpaquePaint,
+        pub x_skew_angle: FT_Fixed,
+        pub y_skew_angle: FT_Fixed,
+        pub center_x: FT_Fixed,
+        pub center_y: FT_Fixed,
+    }
+
+    #[repr(C)]
+    #[derive(Copy, Clone)]
+    pub struct FT_PaintComposite {
+        pub source_paint: FT_OpaquePaint,
+        pub composite_mode: FT_Composite_Mode,
+        pub backdrop_paint: FT_OpaquePaint,
+    }
+
+    #[repr(C)]
+    #[derive(Copy, Clone)]
+    pub union FT_COLR_Paint_Union {
+        pub colr_layers: FT_PaintColrLayers,
+        pub glyph: FT_PaintGlyph,
+        pub solid: FT_PaintSolid,
+        pub linear_gradient: FT_PaintLinearGradient,
+        pub radial_gradient: FT_PaintRadialGradient,
+        pub sweep_gradient: FT_PaintSweepGradient,
+        pub transform: FT_PaintTransform,
+        pub translate: FT_PaintTranslate,
+        pub scale: FT_PaintScale,
+        pub rotate: FT_PaintRotate,
+        pub skew: FT_PaintSkew,
+        pub composite: FT_PaintComposite,
+        pub colr_glyph: FT_PaintColrGlyph,
+    }
+
+    #[repr(C)]
+    #[derive(Copy, Clone)]
+    pub struct FT_COLR_Paint {
+        pub format: FT_PaintFormat,
+        pub u: FT_COLR_Paint_Union,
+    }
+
+    #[repr(C)]
+    #[derive(Copy, Clone)]
+    pub struct FT_ClipBox {
+        pub bottom_left: FT_Vector,
+        pub top_left: FT_Vector,
+        pub top_right: FT_Vector,
+        pub bottom_right: FT_Vector,
+    }
+
+    extern "C" {
+        pub fn FT_Library_Version(
+            library: FT_Library,
+            amajor: *mut FT_Int,
+            aminor: *mut FT_Int,
+            apatch: *mut FT_Int,
+        );
+        pub fn FT_Palette_Data_Get(face: FT_Face, apalette: *mut FT_Palette_Data) -> FT_Error;
+        pub fn FT_Palette_Select(
+            face: FT_Face,
+            palette_index: FT_UShort,
+            apalette: *mut *mut FT_Color,
+        ) -> FT_Error;
+        pub fn FT_Get_Color_Glyph_Layer(
+            face: FT_Face,
+            base_glyph: FT_UInt,
+            aglyph_index: *mut FT_UInt,
+            acolor_index: *mut FT_UInt,
+            iterator: *mut FT_LayerIterator,
+        ) -> FT_Bool;
+
+        // The paint API was added in FreeType 2.11 and the clip boxes in 2.12, `freetype-sys`
+        // falls back to its bundled FreeType for system libraries older than 2.12.1.
+        pub fn FT_Get_Color_Glyph_Paint(
+            face: FT_Face,
+            base_glyph: FT_UInt,
+            root_transform: FT_Color_Root_Transform,
+            paint: *mut FT_OpaquePaint,
+        ) -> FT_Bool;
+        pub fn FT_Get_Color_Glyph_ClipBox(
+            face: FT_Face,
+            base_glyph: FT_UInt,
+            clip_box: *mut FT_ClipBox,
+        ) -> FT_Bool;
+        pub fn FT_Get_Paint_Layers(
+            face: FT_Face,
+            iterator: *mut FT_LayerIterator,
+            paint: *mut FT_OpaquePaint,
+        ) -> FT_Bool;
+        pub fn FT_Get_Colorline_Stops(
+            face: FT_Face,
+            color_stop: *mut FT_ColorStop,
+            iterator: *mut FT_ColorStopIterator,
+        ) -> FT_Bool;
+        pub fn FT_Get_Paint(
+            face: FT_Face,
+            opaque_paint: FT_OpaquePaint,
+            paint: *mut FT_COLR_Paint,
+        ) -> FT_Bool;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transform_invert() {
+        let transform = Transform::new(2., 0.5, -1., 3., 10., -4.);
+        let inverse = transform.invert().unwrap();
+        let (x, y) = transform.apply(3., 7.);
+        let (x, y) = inverse.apply(x, y);
+        assert!((x - 3.).abs() < 1e-4 && (y - 7.).abs() < 1e-4);
+    }
+
+    #[test]
+    fn color_line_extend() {
+        let mut color_line = ColorLine {
+            extend: FT_COLR_PAINT_EXTEND_REPEAT,
+            stops: vec![(0., [0., 0., 0., 0.]), (1., [1., 1., 1., 1.])],
+        };
+        assert_eq!(color_line.color_at(1.25), [0.25; 4]);
+
+        color_line.extend = FT_COLR_PAINT_EXTEND_REFLECT;
+        assert_eq!(color_line.color_at(1.25), [0.75; 4]);
+
+        color_line.extend = 0;
+        assert_eq!(color_line.color_at(1.25), [1.; 4]);
+        assert_eq!(color_line.color_at(-1.), [0.; 4]);
+    }
+
+    #[test]
+    fn radial_gradient_concentric() {
+        // Concentric circles from radius 0 to 10.
+        assert_eq!(radial_gradient_t(5., 0., 0., 0., 0., 10.), Some(0.5));
+        assert_eq!(radial_gradient_t(0., 20., 0., 0., 0., 10.), Some(2.));
+    }
+
+    #[test]
+    fn composite_src_in() {
+        let source = [0.5, 0., 0., 0.5];
+        let backdrop = [0., 0., 1., 1.];
+        assert_eq!(composite_pixel(FT_COLR_COMPOSITE_SRC_IN, &source, &backdrop), source);
+        assert_eq!(composite_pixel(FT_COLR_COMPOSITE_DEST_OUT, &source, &backdrop), [
+            0., 0., 0.5, 0.5
+        ]);
+    }
+}
@@ -0,0 +1,202 @@
+//! Scaling and alignment of icon glyphs within their cells.
+
+use std::ops::RangeInclusive;
+
+use crate::CellBox;
+
+/// Size of a constrained glyph relative to its cells.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ConstraintSize {
+    /// Keep the size of the glyph.
+    #[default]
+    Keep,
+    /// Scale the glyph to the largest size fitting into its cells, keeping its aspect ratio.
+    Fit,
+    /// Scale the glyph to the height of the cell, keeping its aspect ratio.
+    ///
+    /// The glyph might be wider than its cells.
+    FitHeight,
+    /// Scale the glyph to the width of its cells, keeping its aspect ratio.
+    ///
+    /// The glyph might be taller than the cell.
+    FitWidth,
+    /// Stretch the glyph to cover its cells completely.
+    Stretch,
+}
+
+/// Rule for scaling and aligning glyphs within their cells.
+///
+/// Constraints are used for icon fonts like Nerd Fonts, whose glyphs come in arbitrary sizes and
+/// offsets.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct GlyphConstraint {
+    /// Size of the glyph relative to its cells.
+    pub size: ConstraintSize,
+    /// Center the glyph horizontally within its cells.
+    pub center_horizontally: bool,
+    /// Center the glyph vertically within the cell, instead of keeping its offset from the
+    /// baseline.
+    pub center_vertically: bool,
+    /// Maximum number of cells covered by the glyph.
+    pub cells: u32,
+}
+
+impl Default for GlyphConstraint {
+    fn default() -> Self {
+        Self {
+            size: ConstraintSize::default(),
+            center_horizontally: false,
+            center_vertically: false,
+            cells: 1,
+        }
+    }
+}
+
+impl GlyphConstraint {
+    /// Get the transformation moving a glyph's bounds into its cells.
+    pub(super) fn placement(&self, bounds: Bounds, cell_box: CellBox) -> Placement {
+        let cells = self.cells.min(cell_box.cells).max(1) as i32;
+        let box_width = f64::from(cells * cell_box.cell_width);
+        let box_height = f64::from(cell_box.cell_height);
+        let box_bottom = -f64::from(cell_box.baseline);
+
+        let (width, height) = (bounds.x_max - bounds.x_min, bounds.y_max - bounds.y_min);
+        let width_scale = if width > 0. { box_width / width } else { 1. };
+        let height_scale = if height > 0. { box_height / height } else { 1. };
+
+        let (scale_x, scale_y) = match self.size {
+            ConstraintSize::Keep => (1., 1.),
+            ConstraintSize::Fit => {
+                let scale = width_scale.min(height_scale);
+                (scale, scale)
+            },
+            ConstraintSize::FitHeight => (height_scale, height_scale),
+            ConstraintSize::FitWidth => (width_scale, width_scale),
+            ConstraintSize::Stretch => (width_scale, height_scale),
+        };
+
+        // Stretched glyphs cover the whole box, so they're always aligned with it.
+        let stretch = self.size == ConstraintSize::Stretch;
+
+        let offset_x = if self.center_horizontally || stretch {
+            (box_width - width * scale_x) / 2. - bounds.x_min * scale_x
+        } else {
+            0.
+        };
+
+        let offset_y = if self.center_vertically || stretch {
+            box_bottom + (box_height - height * scale_y) / 2. - bounds.y_min * scale_y
+        } else {
+            0.
+        };
+
+        Placement { scale_x, scale_y, offset_x, offset_y, advance: cells * cell_box.cell_width }
+    }
+}
+
+/// Find the constraint for a character, preferring earlier rules.
+pub(super) fn find_constraint(
+    constraints: &[(RangeInclusive<char>, GlyphConstraint)],
+    character: char,
+) -> Option<GlyphConstraint> {
+    constraints
+        .iter()
+        .find(|(range, _)| range.contains(&character))
+        .map(|(_, constraint)| *constraint)
+}
+
+/// Bounding box of a glyph in pixels, relative to its origin on the baseline.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(super) struct Bounds {
+    pub x_min: f64,
+    pub y_min: f64,
+    pub x_max: f64,
+    pub y_max: f64,
+}
+
+/// Transformation of a constrained glyph.
+///
+/// Points are scaled first, then moved by the offset.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(super) struct Placement {
+    pub scale_x: f64,
+    pub scale_y: f64,
+    pub offset_x: f64,
+    pub offset_y: f64,
+    /// Horizontal advance of the constrained glyph in pixels.
+    pub advance: i32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CELL_BOX: CellBox = CellBox { cells: 2, cell_width: 10, cell_height: 20, baseline: 5 };
+
+    fn transform(placement: Placement, bounds: Bounds) -> Bounds {
+        Bounds {
+            x_min: bounds.x_min * placement.scale_x + placement.offset_x,
+            y_min: bounds.y_min * placement.scale_y + placement.offset_y,
+            x_max: bounds.x_max * placement.scale_x + placement.offset_x,
+            y_max: bounds.y_max * placement.scale_y + placement.offset_y,
+        }
+    }
+
+    #[test]
+    fn constraint_placement() {
+        let bounds = Bounds { x_min: 2., y_min: 10., x_max: 6., y_max: 12. };
+
+        // Glyphs are only moved if requested.
+        let constraint = GlyphConstraint::default();
+        let placement = constraint.placement(bounds, CELL_BOX);
+        assert_eq!(transform(placement, bounds), bounds);
+        assert_eq!(placement.advance, 10);
+
+        let constraint = GlyphConstraint {
+            center_horizontally: true,
+            center_vertically: true,
+            ..Default::default()
+        };
+        let placement = constraint.placement(bounds, CELL_BOX);
+        let expected = Bounds { x_min: 3., y_min: 4., x_max: 7., y_max: 6. };
+        assert_eq!(transform(placement, bounds), expected);
+
+        // Fitting glyphs grow until they reach the edge of their cells.
+        let constraint = GlyphConstraint {
+            size: ConstraintSize::Fit,
+            center_horizontally: true,
+            cells: 2,
+            ..Default::default()
+        };
+        let placement = constraint.placement(bounds, CELL_BOX);
+        let expected = Bounds { x_min: 0., y_min: 50., x_max: 20., y_max: 60. };
+        assert_eq!(transform(placement, bounds), expected);
+
+        let constraint = GlyphConstraint { size: ConstraintSize::FitHeight, ..constraint };
+        let placement = constraint.placement(bounds, CELL_BOX);
+        assert_eq!((placement.scale_x, placement.scale_y), (10., 10.));
+        assert_eq!(placement.advance, 20);
+
+        let constraint = GlyphConstraint { size: ConstraintSize::Stretch, ..constraint };
+        let placement = constraint.placement(bounds, CELL_BOX);
+        let expected = Bounds { x_min: 0., y_min: -5., x_max: 20., y_max: 15. };
+        assert_eq!(transform(placement, bounds), expected);
+
+        // Glyphs never cover more cells than the box.
+        let cell_box = CellBox { cells: 1, ..CELL_BOX };
+        let constraint = GlyphConstraint { size: ConstraintSize::FitWidth, ..constraint };
+        let placement = constraint.placement(bounds, cell_box);
+        assert_eq!((placement.scale_x, placement.advance), (2.5, 10));
+    }
+
+    #[test]
+    fn constraint_ranges() {
+        let fit = GlyphConstraint { size: ConstraintSize::Fit, ..Default::default() };
+        let stretch = GlyphConstraint { size: ConstraintSize::Stretch, ..Default::default() };
+        let constraints = [('\u{E0B0}'..='\u{E0B3}', stretch), ('\u{E000}'..='\u{F8FF}', fit)];
+
+        assert_eq!(find_constraint(&constraints, '\u{E0B0}'), Some(stretch));
+        assert_eq!(find_constraint(&constraints, '\u{E0B4}'), Some(fit));
+        assert_eq!(find_constraint(&constraints, 'a'), None);
+    }
+}
@@ -0,0 +1,122 @@
+//! Post-processing of rasterized glyph coverage.
+
+use crate::BitmapBuffer;
+
+/// Adjustment of the alpha coverage produced by FreeType.
+///
+/// FreeType's coverage is linear, which makes light text on dark backgrounds look thin compared
+/// to other platforms. The adjustment is applied to every channel of alphamask buffers, while
+/// colored glyphs are left untouched.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct CoverageAdjustment {
+    /// Gamma applied to the coverage, values above `1.` make glyphs look bolder.
+    pub gamma: f32,
+
+    /// Boost of intermediate coverage values, `0.` leaves them unchanged.
+    pub contrast: f32,
+
+    /// Minimum value for every channel with non-zero coverage.
+    pub min_coverage: u8,
+}
+
+impl Default for CoverageAdjustment {
+    fn default() -> Self {
+        Self { gamma: 1., contrast: 0., min_coverage: 0 }
+    }
+}
+
+impl CoverageAdjustment {
+    /// Adjust a single coverage value.
+    fn adjust(&self, coverage: u8) -> u8 {
+        if coverage == 0 {
+            return 0;
+        }
+
+        let mut value = f32::from(coverage) / 255.;
+
+        if self.gamma > 0. {
+            value = value.powf(1. / self.gamma);
+        }
+
+        value = (value + self.contrast * value * (1. - value)).clamp(0., 1.);
+
+        ((value * 255.).round() as u8).max(self.min_coverage)
+    }
+}
+
+/// Lookup table for a `CoverageAdjustment`.
+#[derive(Debug, Clone)]
+pub(crate) struct CoverageTable {
+    table: [u8; 256],
+}
+
+impl CoverageTable {
+    pub fn new(adjustment: &CoverageAdjustment) -> Self {
+        let mut table = [0; 256];
+        for (coverage, value) in table.iter_mut().enumerate() {
+            *value = adjustment.adjust(coverage as u8);
+        }
+        Self { table }
+    }
+
+    /// Adjust the coverage of an alphamask buffer.
+    ///
+    /// Colored buffers are returned unchanged.
+    pub fn apply(&self, buffer: &mut BitmapBuffer) {
+        if let BitmapBuffer::Rgb(buffer) = buffer {
+            for value in buffer {
+                *value = self.table[*value as usize];
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identity() {
+        let table = CoverageTable::new(&CoverageAdjustment::default());
+        for coverage in 0..=255 {
+            assert_eq!(table.table[coverage], coverage as u8);
+        }
+    }
+
+    #[test]
+    fn gamma_and_contrast_boost_coverage() {
+        let adjustment = CoverageAdjustment { gamma: 1.8, contrast: 0.5, min_coverage: 0 };
+        let table = CoverageTable::new(&adjustment);
+
+        assert_eq!(table.table[0], 0);
+        assert_eq!(table.table[255], 255);
+        for coverage in 1..255 {
+            assert!(table.table[coverage] >= coverage as u8);
+            assert!(table.table[coverage] >= table.table[coverage - 1]);
+        }
+    }
+
+    #[test]
+    fn min_coverage() {
+        let adjustment = CoverageAdjustment { min_coverage: 64, ..Default::default() };
+        let mut buffer = BitmapBuffer::Rgb(vec![0, 1, 63, 64, 200]);
+        CoverageTable::new(&adjustment).apply(&mut buffer);
+
+        match buffer {
+            BitmapBuffer::Rgb(buffer) => assert_eq!(buffer, [0, 64, 64, 64, 200]),
+            BitmapBuffer::Rgba(_) => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn colored_buffers_untouched() {
+        let adjustment = CoverageAdjustment { gamma: 2., contrast: 1., min_coverage: 128 };
+        let mut buffer = BitmapBuffer::Rgba(vec![1, 2, 3, 4]);
+        CoverageTable::new(&adjustment).apply(&mut buffer);
+
+        match buffer {
+            BitmapBuffer::Rgba(buffer) => assert_eq!(buffer, [1, 2, 3, 4]),
+            BitmapBuffer::Rgb(_) => unreachable!(),
+        }
+    }
+}
@@ -1,7 +1,18 @@
-use foreign_types::{foreign_type, ForeignTypeRef};
+use std::ffi::{CStr, CString, OsStr};
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::ptr;
 
+use foreign_types::{foreign_type, ForeignType, ForeignTypeRef};
+use libc::c_char;
+
+use super::ffi::FcInitLoadConfig;
 use super::ffi::{FcConfig, FcConfigDestroy, FcConfigGetCurrent, FcConfigGetFonts};
-use super::{FontSetRef, SetName};
+use super::ffi::{FcConfigAppFontAddDir, FcConfigAppFontAddFile, FcConfigUptoDate};
+use super::ffi::{FcConfigBuildFonts, FcConfigCreate, FcConfigParseAndLoad, FcConfigReference};
+use super::ffi::{FcConfigGetConfigDirs, FcConfigGetConfigFiles, FcConfigGetFontDirs};
+use super::ffi::{FcStrList, FcStrListDone, FcStrListNext};
+use super::{sys, FontSetRef, SetName};
 
 foreign_type! {
     pub unsafe type Config {
@@ -15,15 +26,184 @@ impl Config {
     pub fn get_current() -> &'static ConfigRef {
         unsafe { ConfigRef::from_ptr(FcConfigGetCurrent()) }
     }
+
+    /// Get a reference to the current configuration.
+    ///
+    /// Unlike [`Config::get_current`], the configuration stays valid after it was replaced by
+    /// reinitializing Fontconfig.
+    pub fn current() -> Option<Config> {
+        unsafe {
+            let ptr = FcConfigReference(ptr::null_mut());
+            if ptr.is_null() {
+                None
+            } else {
+                Some(Config::from_ptr(ptr))
+            }
+        }
+    }
+
+    /// Create a configuration without any rules or fonts.
+    ///
+    /// Fonts can be added with [`ConfigRef::add_font_dir`] and [`ConfigRef::add_font_file`].
+    pub fn empty() -> Option<Config> {
+        unsafe {
+            let ptr = FcConfigCreate();
+            if ptr.is_null() {
+                return None;
+            }
+
+            // Build the empty system font set, so it can be listed and matched against.
+            let config = Config::from_ptr(ptr);
+            if FcConfigBuildFonts(config.as_ptr()) == 0 {
+                return None;
+            }
+
+            Some(config)
+        }
+    }
+
+    /// Load the default configuration files, without any fonts.
+    ///
+    /// This allows adding rules with [`ConfigRef::load_rules`] before the fonts are added using
+    /// [`ConfigRef::build_fonts`].
+    pub fn load_default() -> Option<Config> {
+        unsafe {
+            let ptr = FcInitLoadConfig();
+            if ptr.is_null() {
+                None
+            } else {
+                Some(Config::from_ptr(ptr))
+            }
+        }
+    }
+
+    /// Load a configuration file, including all fonts in the directories it references.
+    ///
+    /// Returns `None` if the file could not be parsed.
+    pub fn from_file(path: &Path) -> Option<Config> {
+        let path = CString::new(path.as_os_str().as_bytes()).ok()?;
+
+        unsafe {
+            let ptr = FcConfigCreate();
+            if ptr.is_null() {
+                return None;
+            }
+
+            let config = Config::from_ptr(ptr);
+            if FcConfigParseAndLoad(config.as_ptr(), path.as_ptr().cast(), 1) == 0
+                || FcConfigBuildFonts(config.as_ptr()) == 0
+            {
+                return None;
+            }
+
+            Some(config)
+        }
+    }
 }
 
 impl ConfigRef {
     /// Returns one of the two sets of fonts from the configuration as
     /// specified by `set`.
-    pub fn get_fonts(&self, set: SetName) -> &FontSetRef {
+    ///
+    /// The application set is only present once fonts were added to it.
+    pub fn get_fonts(&self, set: SetName) -> Option<&FontSetRef> {
         unsafe {
             let ptr = FcConfigGetFonts(self.as_ptr(), set as u32);
-            FontSetRef::from_ptr(ptr)
+            if ptr.is_null() {
+                None
+            } else {
+                Some(FontSetRef::from_ptr(ptr))
+            }
+        }
+    }
+
+    /// Add all fonts from the configured font directories to the system font set.
+    ///
+    /// Returns `false` if the fonts could not be loaded.
+    pub fn build_fonts(&mut self) -> bool {
+        unsafe { FcConfigBuildFonts(self.as_ptr()) == 1 }
+    }
+
+    /// Load rules from a Fontconfig configuration document, like the one formatted by
+    /// [`Rules`].
+    ///
+    /// Font selection rules, like rejected families, only apply to fonts added afterwards.
+    /// Returns `false` if the document could not be parsed.
+    ///
+    /// [`Rules`]: super::Rules
+    pub fn load_rules(&mut self, xml: &str) -> bool {
+        match CString::new(xml) {
+            Ok(xml) => unsafe {
+                sys::FcConfigParseAndLoadFromMemory(self.as_ptr(), xml.as_ptr().cast(), 1) == 1
+            },
+            Err(_) => false,
+        }
+    }
+
+    /// Add all fonts in a directory and its subdirectories to the application font set.
+    ///
+    /// Returns `false` if the directory could not be scanned.
+    pub fn add_font_dir(&mut self, dir: &Path) -> bool {
+        match CString::new(dir.as_os_str().as_bytes()) {
+            Ok(dir) => unsafe { FcConfigAppFontAddDir(self.as_ptr(), dir.as_ptr().cast()) == 1 },
+            Err(_) => false,
+        }
+    }
+
+    /// Add all faces of a font file to the application font set.
+    ///
+    /// Returns `false` if the file could not be loaded.
+    pub fn add_font_file(&mut self, file: &Path) -> bool {
+        match CString::new(file.as_os_str().as_bytes()) {
+            Ok(file) => unsafe { FcConfigAppFontAddFile(self.as_ptr(), file.as_ptr().cast()) == 1 },
+            Err(_) => false,
+        }
+    }
+
+    /// Check if this is the current configuration.
+    pub fn is_current(&self) -> bool {
+        unsafe { FcConfigGetCurrent() == self.as_ptr() }
+    }
+
+    /// Check if the configuration files and font directories changed since they were loaded.
+    pub fn up_to_date(&self) -> bool {
+        unsafe { FcConfigUptoDate(self.as_ptr()) == 1 }
+    }
+
+    /// Configuration files loaded by the configuration.
+    pub fn config_files(&self) -> Vec<PathBuf> {
+        unsafe { collect_paths(FcConfigGetConfigFiles(self.as_ptr())) }
+    }
+
+    /// Directories scanned for additional configuration files.
+    pub fn config_dirs(&self) -> Vec<PathBuf> {
+        unsafe { collect_paths(FcConfigGetConfigDirs(self.as_ptr())) }
+    }
+
+    /// Font directories, including all of their subdirectories.
+    pub fn font_dirs(&self) -> Vec<PathBuf> {
+        unsafe { collect_paths(FcConfigGetFontDirs(self.as_ptr())) }
+    }
+}
+
+/// Consume a Fontconfig string list as paths.
+unsafe fn collect_paths(list: *mut FcStrList) -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    if list.is_null() {
+        return paths;
+    }
+
+    loop {
+        let ptr = FcStrListNext(list);
+        if ptr.is_null() {
+            break;
         }
+
+        let path = CStr::from_ptr(ptr as *const c_char);
+        paths.push(PathBuf::from(OsStr::from_bytes(path.to_bytes())));
     }
+
+    FcStrListDone(list);
+
+    paths
 }
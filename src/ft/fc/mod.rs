@@ -1,16 +1,19 @@
+use std::ffi::{CStr, CString};
 use std::fmt;
 use std::ptr;
 
 use foreign_types::{ForeignType, ForeignTypeRef};
+use libc::c_int;
 
 use fontconfig_sys as ffi;
 
+use ffi::constants::{FC_CHARCELL, FC_DUAL, FC_MONO, FC_PROPORTIONAL};
 use ffi::constants::{FC_SLANT_ITALIC, FC_SLANT_OBLIQUE, FC_SLANT_ROMAN};
 use ffi::constants::{FC_WEIGHT_BLACK, FC_WEIGHT_BOLD, FC_WEIGHT_EXTRABLACK, FC_WEIGHT_EXTRABOLD};
 use ffi::constants::{FC_WEIGHT_BOOK, FC_WEIGHT_MEDIUM, FC_WEIGHT_REGULAR, FC_WEIGHT_SEMIBOLD};
 use ffi::constants::{FC_WEIGHT_EXTRALIGHT, FC_WEIGHT_LIGHT, FC_WEIGHT_THIN};
-use ffi::FcInitBringUptoDate;
 use ffi::FcResultNoMatch;
+use ffi::{FcConfigUptoDate, FcInitBringUptoDate, FcInitReinitialize};
 use ffi::{FcFontList, FcFontMatch, FcFontSort};
 use ffi::{FcMatchFont, FcMatchPattern, FcMatchScan};
 use ffi::{FcSetApplication, FcSetSystem};
@@ -30,6 +33,11 @@ pub use char_set::{CharSet, CharSetRef};
 pub mod pattern;
 pub use pattern::{FtFaceLocation, Pattern, PatternHash, PatternRef};
 
+pub mod rules;
+pub use rules::{Property, Rules};
+
+mod sys;
+
 /// Find the font closest matching the provided pattern.
 ///
 /// The returned pattern is the result of Pattern::render_prepare.
@@ -53,6 +61,18 @@ pub fn update_config() {
     }
 }
 
+/// Check if the configuration files and font directories changed since the last reload.
+pub fn config_up_to_date() -> bool {
+    unsafe { FcConfigUptoDate(ptr::null_mut()) == 1 }
+}
+
+/// Reload the configuration files and rescan all font directories.
+///
+/// Returns `false` if the new configuration could not be loaded.
+pub fn reinitialize() -> bool {
+    unsafe { FcInitReinitialize() == 1 }
+}
+
 /// List fonts by closeness to the pattern.
 pub fn font_sort(config: &ConfigRef, pattern: &PatternRef) -> Option<FontSet> {
     let ptr = unsafe {
@@ -143,7 +163,7 @@ pub enum Width {
 }
 
 impl Width {
-    fn to_isize(self) -> isize {
+    pub fn to_isize(self) -> isize {
         match self {
             Width::Ultracondensed => 50,
             Width::Extracondensed => 63,
@@ -176,8 +196,43 @@ impl From<isize> for Width {
     }
 }
 
+/// Horizontal spacing of glyphs.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Spacing {
+    Proportional = FC_PROPORTIONAL as isize,
+    Dual = FC_DUAL as isize,
+    Mono = FC_MONO as isize,
+    Charcell = FC_CHARCELL as isize,
+}
+
+/// Convert a Fontconfig weight to the OpenType weight scale.
+pub fn weight_to_opentype(weight: isize) -> isize {
+    unsafe { sys::FcWeightToOpenType(weight as c_int) as isize }
+}
+
+/// Normalize a language tag or locale to the form used by Fontconfig.
+///
+/// Locales like `ja_JP.UTF-8` are reduced to the language Fontconfig knows about, like `ja`.
+pub fn normalize_lang(lang: &str) -> String {
+    let c_lang = match CString::new(lang) {
+        Ok(c_lang) => c_lang,
+        Err(_) => return lang.into(),
+    };
+
+    unsafe {
+        let normalized = sys::FcLangNormalize(c_lang.as_ptr().cast());
+        if normalized.is_null() {
+            return lang.into();
+        }
+
+        let result = CStr::from_ptr(normalized.cast()).to_string_lossy().into_owned();
+        ffi::FcStrFree(normalized);
+        result
+    }
+}
+
 /// Subpixel geometry.
-#[derive(Debug)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum Rgba {
     Unknown,
     Rgb,
@@ -188,8 +243,8 @@ pub enum Rgba {
 }
 
 impl Rgba {
-    fn to_isize(&self) -> isize {
-        match *self {
+    fn to_isize(self) -> isize {
+        match self {
             Rgba::Unknown => 0,
             Rgba::Rgb => 1,
             Rgba::Bgr => 2,
@@ -227,12 +282,12 @@ impl From<isize> for Rgba {
 }
 
 /// Hinting Style.
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum HintStyle {
-    None,
-    Slight,
-    Medium,
-    Full,
+    None = 0,
+    Slight = 1,
+    Medium = 2,
+    Full = 3,
 }
 
 impl fmt::Display for HintStyle {
@@ -247,11 +302,12 @@ impl fmt::Display for HintStyle {
 }
 
 /// Lcd filter, used to reduce color fringing with subpixel rendering.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum LcdFilter {
-    None,
-    Default,
-    Light,
-    Legacy,
+    None = 0,
+    Default = 1,
+    Light = 2,
+    Legacy = 3,
 }
 
 impl fmt::Display for LcdFilter {
@@ -315,6 +371,46 @@ mod tests {
         }
     }
 
+    #[test]
+    fn isolated_config() {
+        let mut pattern = Pattern::new();
+        pattern.add_family("monospace");
+        let config = Config::get_current();
+        pattern.config_substitute(config, MatchKind::Pattern);
+        pattern.default_substitute();
+        let font = super::font_match(config, &pattern).expect("match font monospace");
+        let file = font.file(0).unwrap();
+
+        let mut config = Config::empty().unwrap();
+        assert_eq!(config.get_fonts(SetName::System).unwrap().into_iter().count(), 0);
+        assert!(config.get_fonts(SetName::Application).is_none());
+
+        assert!(config.add_font_file(&file));
+        let fonts = config.get_fonts(SetName::Application).unwrap();
+        assert!(fonts.into_iter().all(|font| font.file(0).as_ref() == Some(&file)));
+
+        // Loading configuration files picks up the fonts in their directories.
+        let dir = std::env::temp_dir().join(format!("crossfont-config-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let config_file = dir.join("fonts.conf");
+        let font_dir = file.parent().unwrap().display();
+        std::fs::write(&config_file, format!("<fontconfig><dir>{font_dir}</dir></fontconfig>"))
+            .unwrap();
+
+        let config = Config::from_file(&config_file).unwrap();
+        let fonts = config.get_fonts(SetName::System).unwrap();
+        assert!(fonts.into_iter().any(|font| font.file(0).as_ref() == Some(&file)));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn normalize_lang() {
+        assert_eq!(super::normalize_lang("ja_JP.UTF-8"), "ja");
+        assert_eq!(super::normalize_lang("zh_TW"), "zh-TW");
+        assert_eq!(super::normalize_lang("en"), "en");
+    }
+
     #[test]
     fn font_sort_with_glyph() {
         let mut charset = CharSet::new();
@@ -45,4 +45,39 @@ impl ObjectSetRef {
     pub fn add_style(&mut self) {
         self.add(b"style\0");
     }
+
+    #[inline]
+    pub fn add_family(&mut self) {
+        self.add(b"family\0");
+    }
+
+    #[inline]
+    pub fn add_weight(&mut self) {
+        self.add(b"weight\0");
+    }
+
+    #[inline]
+    pub fn add_slant(&mut self) {
+        self.add(b"slant\0");
+    }
+
+    #[inline]
+    pub fn add_width(&mut self) {
+        self.add(b"width\0");
+    }
+
+    #[inline]
+    pub fn add_spacing(&mut self) {
+        self.add(b"spacing\0");
+    }
+
+    #[inline]
+    pub fn add_scalable(&mut self) {
+        self.add(b"scalable\0");
+    }
+
+    #[inline]
+    pub fn add_color(&mut self) {
+        self.add(b"color\0");
+    }
 }
@@ -12,6 +12,7 @@ use super::ffi::FcMatrix;
 use super::ffi::FcResultMatch;
 use super::ffi::{FcBool, FcFontRenderPrepare, FcPatternGetBool, FcPatternGetDouble};
 use super::ffi::{FcChar8, FcConfigSubstitute, FcDefaultSubstitute, FcPattern, FcPatternHash};
+use super::ffi::{FcPatternAddBool, FcPatternDel};
 use super::ffi::{
     FcPatternAddCharSet, FcPatternDestroy, FcPatternDuplicate, FcPatternGetCharSet,
     FcPatternGetMatrix,
@@ -19,7 +20,9 @@ use super::ffi::{
 use super::ffi::{FcPatternAddDouble, FcPatternAddString, FcPatternCreate, FcPatternGetString};
 use super::ffi::{FcPatternAddInteger, FcPatternGetInteger, FcPatternPrint};
 
-use super::{CharSetRef, ConfigRef, HintStyle, LcdFilter, MatchKind, Rgba, Slant, Weight, Width};
+use super::{
+    CharSetRef, ConfigRef, HintStyle, LcdFilter, MatchKind, Rgba, Slant, Spacing, Weight, Width,
+};
 
 pub struct StringPropertyIter<'a> {
     pattern: &'a PatternRef,
@@ -140,7 +143,7 @@ pub struct HintStylePropertyIter<'a> {
 }
 
 impl<'a> HintStylePropertyIter<'a> {
-    fn new(pattern: &PatternRef) -> HintStylePropertyIter {
+    fn new(pattern: &PatternRef) -> HintStylePropertyIter<'_> {
         HintStylePropertyIter { inner: IntPropertyIter::new(pattern, b"hintstyle\0") }
     }
 
@@ -167,7 +170,7 @@ pub struct LcdFilterPropertyIter<'a> {
 }
 
 impl<'a> LcdFilterPropertyIter<'a> {
-    fn new(pattern: &PatternRef) -> LcdFilterPropertyIter {
+    fn new(pattern: &PatternRef) -> LcdFilterPropertyIter<'_> {
         LcdFilterPropertyIter { inner: IntPropertyIter::new(pattern, b"lcdfilter\0") }
     }
 
@@ -329,7 +332,7 @@ macro_rules! string_accessor {
             }
 
             #[inline]
-            pub fn $getter(&self) -> StringPropertyIter {
+            pub fn $getter(&self) -> StringPropertyIter<'_> {
                 unsafe {
                     self.get_string($object_name)
                 }
@@ -341,7 +344,7 @@ macro_rules! string_accessor {
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
 pub struct PatternHash(pub u32);
 
-#[derive(Hash, Eq, PartialEq, Debug)]
+#[derive(Hash, Eq, PartialEq, Debug, Clone)]
 pub struct FtFaceLocation {
     pub path: PathBuf,
     pub index: isize,
@@ -368,7 +371,7 @@ impl Default for Pattern {
 macro_rules! pattern_get_integer {
     ($($method:ident() => $property:expr),+) => {
         $(
-            pub fn $method(&self) -> IntPropertyIter {
+            pub fn $method(&self) -> IntPropertyIter<'_> {
                 unsafe {
                     self.get_integer($property)
                 }
@@ -380,7 +383,7 @@ macro_rules! pattern_get_integer {
 macro_rules! boolean_getter {
     ($($method:ident() => $property:expr),*) => {
         $(
-            pub fn $method(&self) -> BooleanPropertyIter {
+            pub fn $method(&self) -> BooleanPropertyIter<'_> {
                 unsafe {
                     self.get_boolean($property)
                 }
@@ -389,10 +392,24 @@ macro_rules! boolean_getter {
     }
 }
 
+macro_rules! boolean_setter {
+    ($($method:ident() => $property:expr),*) => {
+        $(
+            /// Replace all existing values of the property.
+            pub fn $method(&mut self, value: bool) -> bool {
+                unsafe {
+                    self.del($property);
+                    self.add_boolean($property, value)
+                }
+            }
+        )*
+    }
+}
+
 macro_rules! double_getter {
     ($($method:ident() => $property:expr),*) => {
         $(
-            pub fn $method(&self) -> DoublePropertyIter {
+            pub fn $method(&self) -> DoublePropertyIter<'_> {
                 unsafe {
                     self.get_double($property)
                 }
@@ -418,6 +435,13 @@ impl PatternRef {
         decorative() => b"decorative\0"
     }
 
+    boolean_setter! {
+        set_antialias() => b"antialias\0",
+        set_hinting() => b"hinting\0",
+        set_autohint() => b"autohint\0",
+        set_embeddedbitmap() => b"embeddedbitmap\0"
+    }
+
     double_getter! {
         size() => b"size\0",
         aspect() => b"aspect\0",
@@ -443,7 +467,10 @@ impl PatternRef {
     }
 
     pattern_get_integer! {
-        index() => b"index\0"
+        index() => b"index\0",
+        slant() => b"slant\0",
+        weight() => b"weight\0",
+        spacing() => b"spacing\0"
     }
 
     /// Prints the pattern to stdout.
@@ -475,6 +502,17 @@ impl PatternRef {
         FcPatternAddInteger(self.as_ptr(), object.as_ptr() as *mut c_char, int as c_int) == 1
     }
 
+    unsafe fn add_boolean(&self, object: &[u8], value: bool) -> bool {
+        FcPatternAddBool(self.as_ptr(), object.as_ptr() as *mut c_char, value as FcBool) == 1
+    }
+
+    /// Remove all values of a property from the pattern.
+    ///
+    /// Returns `true` if the property existed in the pattern.
+    unsafe fn del(&mut self, object: &[u8]) -> bool {
+        FcPatternDel(self.as_ptr(), object.as_ptr() as *mut c_char) == 1
+    }
+
     unsafe fn add_double(&self, object: &[u8], value: f64) -> bool {
         FcPatternAddDouble(self.as_ptr(), object.as_ptr() as *mut c_char, value as c_double) == 1
     }
@@ -495,14 +533,30 @@ impl PatternRef {
         BooleanPropertyIter::new(self, object)
     }
 
-    pub fn hintstyle(&self) -> HintStylePropertyIter {
+    pub fn hintstyle(&self) -> HintStylePropertyIter<'_> {
         HintStylePropertyIter::new(self)
     }
 
-    pub fn lcdfilter(&self) -> LcdFilterPropertyIter {
+    pub fn lcdfilter(&self) -> LcdFilterPropertyIter<'_> {
         LcdFilterPropertyIter::new(self)
     }
 
+    /// Replace the hint style of the pattern.
+    pub fn set_hintstyle(&mut self, hintstyle: HintStyle) -> bool {
+        unsafe {
+            self.del(b"hintstyle\0");
+            self.add_integer(b"hintstyle\0", hintstyle as isize)
+        }
+    }
+
+    /// Replace the LCD filter of the pattern.
+    pub fn set_lcdfilter(&mut self, lcdfilter: LcdFilter) -> bool {
+        unsafe {
+            self.del(b"lcdfilter\0");
+            self.add_integer(b"lcdfilter\0", lcdfilter as isize)
+        }
+    }
+
     pub fn set_slant(&mut self, slant: Slant) -> bool {
         unsafe { self.add_integer(b"slant\0", slant as isize) }
     }
@@ -515,6 +569,15 @@ impl PatternRef {
         unsafe { self.add_integer(b"weight\0", weight as isize) }
     }
 
+    pub fn set_spacing(&mut self, spacing: Spacing) -> bool {
+        unsafe { self.add_integer(b"spacing\0", spacing as isize) }
+    }
+
+    /// Add a language which must be supported by the font, as an RFC 3066 tag.
+    pub fn add_lang(&mut self, lang: &str) -> bool {
+        unsafe { self.add_string(b"lang\0", lang) }
+    }
+
     pub fn set_width(&mut self, width: Width) -> bool {
         unsafe { self.add_integer(b"width\0", width.to_isize()) }
     }
@@ -523,7 +586,7 @@ impl PatternRef {
         unsafe { self.get_integer(b"width\0").next().map(Width::from) }
     }
 
-    pub fn rgba(&self) -> RgbaPropertyIter {
+    pub fn rgba(&self) -> RgbaPropertyIter<'_> {
         RgbaPropertyIter::new(self, b"rgba\0")
     }
 
@@ -531,6 +594,14 @@ impl PatternRef {
         unsafe { self.add_integer(b"rgba\0", rgba.to_isize()) }
     }
 
+    /// Replace the subpixel geometry of the pattern.
+    pub fn replace_rgba(&mut self, rgba: &Rgba) -> bool {
+        unsafe {
+            self.del(b"rgba\0");
+            self.add_integer(b"rgba\0", rgba.to_isize())
+        }
+    }
+
     pub fn render_prepare(&self, config: &ConfigRef, request: &PatternRef) -> Pattern {
         unsafe {
             let ptr = FcFontRenderPrepare(config.as_ptr(), self.as_ptr(), request.as_ptr());
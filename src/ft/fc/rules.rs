@@ -0,0 +1,152 @@
+//! Builder for Fontconfig configuration rules.
+
+use std::fmt::{self, Display, Formatter, Write};
+
+use super::{HintStyle, LcdFilter, Rgba};
+
+/// Font property which can be overridden for a family.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Property {
+    Antialias(bool),
+    Hinting(bool),
+    HintStyle(HintStyle),
+    Autohint(bool),
+    Rgba(Rgba),
+    LcdFilter(LcdFilter),
+    EmbeddedBitmap(bool),
+    Embolden(bool),
+}
+
+impl Property {
+    /// Name and value of the property as Fontconfig XML.
+    fn to_xml(self) -> (&'static str, String) {
+        let bool_value = |value: bool| format!("<bool>{value}</bool>");
+        let int_value = |value: isize| format!("<int>{value}</int>");
+
+        match self {
+            Property::Antialias(value) => ("antialias", bool_value(value)),
+            Property::Hinting(value) => ("hinting", bool_value(value)),
+            Property::HintStyle(value) => ("hintstyle", int_value(value as isize)),
+            Property::Autohint(value) => ("autohint", bool_value(value)),
+            Property::Rgba(value) => ("rgba", int_value(value.to_isize())),
+            Property::LcdFilter(value) => ("lcdfilter", int_value(value as isize)),
+            Property::EmbeddedBitmap(value) => ("embeddedbitmap", bool_value(value)),
+            Property::Embolden(value) => ("embolden", bool_value(value)),
+        }
+    }
+}
+
+/// Fontconfig configuration rules.
+///
+/// The rules are formatted as a Fontconfig configuration document, which can be loaded with
+/// [`ConfigRef::load_rules`].
+///
+/// [`ConfigRef::load_rules`]: super::ConfigRef::load_rules
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Rules {
+    elements: String,
+}
+
+impl Rules {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add raw elements, which are placed inside the `<fontconfig>` element.
+    pub fn xml(mut self, elements: &str) -> Self {
+        self.elements.push_str(elements);
+        self
+    }
+
+    /// Prefer the `preferred` families over the ones usually matched for `family`.
+    pub fn alias<I, S>(mut self, family: &str, preferred: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let _ = write!(
+            self.elements,
+            "<alias binding=\"strong\"><family>{}</family><prefer>",
+            Escaped(family)
+        );
+        for preferred in preferred {
+            let _ = write!(self.elements, "<family>{}</family>", Escaped(preferred.as_ref()));
+        }
+        self.elements.push_str("</prefer></alias>");
+        self
+    }
+
+    /// Override a property of all fonts in a family.
+    pub fn set(mut self, family: &str, property: Property) -> Self {
+        let (name, value) = property.to_xml();
+        let _ = write!(
+            self.elements,
+            "<match target=\"font\"><test name=\"family\"><string>{}</string></test><edit \
+             name=\"{name}\" mode=\"assign\">{value}</edit></match>",
+            Escaped(family)
+        );
+        self
+    }
+
+    /// Never use any font of a family.
+    ///
+    /// This only applies to fonts added to a configuration after the rule was loaded.
+    pub fn reject_family(mut self, family: &str) -> Self {
+        let _ = write!(
+            self.elements,
+            "<selectfont><rejectfont><pattern><patelt \
+             name=\"family\"><string>{}</string></patelt></pattern></rejectfont></selectfont>",
+            Escaped(family)
+        );
+        self
+    }
+}
+
+impl Display for Rules {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "<?xml version=\"1.0\"?><fontconfig>{}</fontconfig>", self.elements)
+    }
+}
+
+/// Text escaped for use in XML.
+struct Escaped<'a>(&'a str);
+
+impl Display for Escaped<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for c in self.0.chars() {
+            match c {
+                '&' => f.write_str("&amp;")?,
+                '<' => f.write_str("&lt;")?,
+                '>' => f.write_str("&gt;")?,
+                '"' => f.write_str("&quot;")?,
+                '\'' => f.write_str("&apos;")?,
+                c => f.write_char(c)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rules_xml() {
+        let rules = Rules::new()
+            .alias("mono", ["Iosevka", "Fira & Code"])
+            .set("Iosevka", Property::HintStyle(HintStyle::Slight))
+            .reject_family("DejaVu Sans");
+
+        assert_eq!(
+            rules.to_string(),
+            "<?xml version=\"1.0\"?><fontconfig><alias \
+             binding=\"strong\"><family>mono</family><prefer><family>Iosevka</family><family>Fira \
+             &amp; Code</family></prefer></alias><match target=\"font\"><test \
+             name=\"family\"><string>Iosevka</string></test><edit name=\"hintstyle\" \
+             mode=\"assign\"><int>1</int></edit></match><selectfont><rejectfont><pattern><patelt \
+             name=\"family\"><string>DejaVu \
+             Sans</string></patelt></pattern></rejectfont></selectfont></fontconfig>"
+        );
+    }
+}
@@ -0,0 +1,20 @@
+//! Fontconfig functions missing from `fontconfig_sys`.
+
+use libc::c_int;
+
+use super::ffi::{FcBool, FcChar8, FcConfig};
+
+extern "C" {
+    /// Available since Fontconfig 2.11.91.
+    pub fn FcWeightToOpenType(fc_weight: c_int) -> c_int;
+
+    /// Available since Fontconfig 2.12.0.
+    pub fn FcConfigParseAndLoadFromMemory(
+        config: *mut FcConfig,
+        buffer: *const FcChar8,
+        complain: FcBool,
+    ) -> FcBool;
+
+    /// Available since Fontconfig 2.10.91.
+    pub fn FcLangNormalize(lang: *const FcChar8) -> *mut FcChar8;
+}
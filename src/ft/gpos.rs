@@ -0,0 +1,260 @@
+//! Pair kerning from the OpenType `GPOS` table.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use super::layout::{self, binary_search, coverage_index, range_record};
+use super::sfnt::{table_u16, Tag};
+
+/// Feature tag of kerning lookups.
+const KERN_TAG: Tag = *b"kern";
+
+/// Lookup type of pair adjustment positioning.
+const PAIR_ADJUSTMENT: u16 = 2;
+
+/// Lookup type of extension positioning.
+const EXTENSION: u16 = 9;
+
+/// Value format flags preceding the horizontal advance.
+const X_PLACEMENT: u16 = 0x0001;
+const Y_PLACEMENT: u16 = 0x0002;
+
+/// Value format flag of the horizontal advance.
+const X_ADVANCE: u16 = 0x0004;
+
+/// Pair adjustments of the `kern` feature of a font face.
+#[derive(Debug)]
+pub struct PairKerning {
+    data: Vec<u8>,
+
+    /// Offsets of the pair adjustment subtables, grouped by lookup.
+    lookups: Vec<Vec<usize>>,
+
+    /// Kerning of previously requested glyph pairs in font units.
+    cache: RefCell<HashMap<(u16, u16), i32>>,
+}
+
+impl PairKerning {
+    /// Parse the `GPOS` table.
+    ///
+    /// Returns `None` if the table has no pair adjustments for the `kern` feature.
+    pub fn new(data: Vec<u8>) -> Option<Self> {
+        let lookups: Vec<_> = layout::feature_lookups(&data, &[KERN_TAG])?
+            .into_iter()
+            .filter_map(|lookup| {
+                layout::lookup_subtables(&data, lookup, PAIR_ADJUSTMENT, EXTENSION)
+            })
+            .filter(|subtables| !subtables.is_empty())
+            .collect();
+
+        if lookups.is_empty() {
+            return None;
+        }
+
+        Some(Self { data, lookups, cache: Default::default() })
+    }
+
+    /// Horizontal kerning between two glyphs in font units.
+    pub fn kerning(&self, left: u16, right: u16) -> i32 {
+        *self.cache.borrow_mut().entry((left, right)).or_insert_with(|| {
+            self.lookups
+                .iter()
+                .filter_map(|subtables| {
+                    // Only the first subtable covering the pair is applied for every lookup.
+                    subtables
+                        .iter()
+                        .find_map(|subtable| pair_adjustment(&self.data, *subtable, left, right))
+                })
+                .map(i32::from)
+                .sum()
+        })
+    }
+}
+
+/// Get the horizontal advance adjustment of the first glyph in a pair adjustment subtable.
+///
+/// Returns `None` if the subtable does not cover the glyph pair.
+fn pair_adjustment(data: &[u8], subtable: usize, left: u16, right: u16) -> Option<i16> {
+    let format = table_u16(data, subtable)?;
+    let coverage = subtable + usize::from(table_u16(data, subtable + 2)?);
+    let value_format1 = table_u16(data, subtable + 4)?;
+    let value_format2 = table_u16(data, subtable + 6)?;
+
+    let coverage_index = coverage_index(data, coverage, left)?;
+    let value_size1 = value_record_size(value_format1);
+    let value_size2 = value_record_size(value_format2);
+
+    let value_record = match format {
+        1 => {
+            let pair_set_count = table_u16(data, subtable + 8)?;
+            if coverage_index >= pair_set_count {
+                return None;
+            }
+
+            let offset = table_u16(data, subtable + 10 + usize::from(coverage_index) * 2)?;
+            let pair_set = subtable + usize::from(offset);
+            let pair_size = 2 + value_size1 + value_size2;
+
+            // Pair value records are sorted by the second glyph.
+            let count = usize::from(table_u16(data, pair_set)?);
+            let index = binary_search(
+                count,
+                |i| {
+                    let second_glyph = table_u16(data, pair_set + 2 + i * pair_size)?;
+                    Some((second_glyph, second_glyph))
+                },
+                right,
+            )?;
+            pair_set + 2 + index * pair_size + 2
+        },
+        2 => {
+            let class_def1 = subtable + usize::from(table_u16(data, subtable + 8)?);
+            let class_def2 = subtable + usize::from(table_u16(data, subtable + 10)?);
+            let class1_count = table_u16(data, subtable + 12)?;
+            let class2_count = table_u16(data, subtable + 14)?;
+
+            let class1 = glyph_class(data, class_def1, left)?;
+            let class2 = glyph_class(data, class_def2, right)?;
+            if class1 >= class1_count || class2 >= class2_count {
+                return None;
+            }
+
+            let pair_size = value_size1 + value_size2;
+            let index = usize::from(class1) * usize::from(class2_count) + usize::from(class2);
+            subtable + 16 + index * pair_size
+        },
+        _ => return None,
+    };
+
+    if value_format1 & X_ADVANCE == 0 {
+        return Some(0);
+    }
+
+    let x_advance = value_record + value_record_size(value_format1 & (X_PLACEMENT | Y_PLACEMENT));
+    table_u16(data, x_advance).map(|x_advance| x_advance as i16)
+}
+
+/// Get the class of a glyph from a class definition table.
+fn glyph_class(data: &[u8], class_def: usize, glyph: u16) -> Option<u16> {
+    let format = table_u16(data, class_def)?;
+
+    let class = match format {
+        1 => {
+            let start = table_u16(data, class_def + 2)?;
+            let count = table_u16(data, class_def + 4)?;
+            match glyph.checked_sub(start) {
+                Some(index) if index < count => {
+                    table_u16(data, class_def + 6 + usize::from(index) * 2)?
+                },
+                _ => 0,
+            }
+        },
+        2 => {
+            let count = usize::from(table_u16(data, class_def + 2)?);
+            match range_record(data, class_def + 4, count, glyph) {
+                Some(record) => table_u16(data, record + 4)?,
+                None => 0,
+            }
+        },
+        _ => return None,
+    };
+
+    Some(class)
+}
+
+/// Size of a value record in bytes.
+fn value_record_size(value_format: u16) -> usize {
+    (value_format & 0xFF).count_ones() as usize * 2
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ft::layout::tests::{extension, layout_table, u16s};
+
+    /// Pair positioning format 1 for pairs of individual glyphs.
+    fn glyph_pairs(left: u16, pairs: &[(u16, i16)]) -> Vec<u8> {
+        let pair_set_len = 2 + 4 * pairs.len() as u16;
+        let mut data = u16s(&[1, 12 + pair_set_len, X_ADVANCE, 0, 1, 12]);
+        data.extend_from_slice(&u16s(&[pairs.len() as u16]));
+        for (right, kerning) in pairs {
+            data.extend_from_slice(&u16s(&[*right, *kerning as u16]));
+        }
+        data.extend_from_slice(&u16s(&[1, 1, left]));
+        data
+    }
+
+    /// Pair positioning format 2 with two classes for each glyph.
+    ///
+    /// Glyphs `10..=19` are covered, with glyphs `12..=19` in the first glyph's second class and
+    /// glyphs `20..=29` in the second glyph's second class.
+    fn class_pairs(kerning: [[i16; 2]; 2]) -> Vec<u8> {
+        let value_format = X_PLACEMENT | X_ADVANCE;
+        let mut data = u16s(&[2, 32, value_format, 0, 48, 58, 2, 2]);
+        for value in kerning.iter().flatten() {
+            data.extend_from_slice(&u16s(&[7, *value as u16]));
+        }
+
+        // Coverage with a range of glyphs.
+        data.extend_from_slice(&u16s(&[2, 2, 10, 11, 0, 12, 19, 2]));
+
+        // Class definition of the first glyph with ranges.
+        data.extend_from_slice(&u16s(&[2, 1, 12, 19, 1]));
+
+        // Class definition of the second glyph with a class array.
+        data.extend_from_slice(&u16s(&[1, 20, 10, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1]));
+
+        data
+    }
+
+    #[test]
+    fn glyph_pair_kerning() {
+        let data = layout_table(KERN_TAG, &[(2, &[glyph_pairs(1, &[(2, -50), (5, 30), (9, -5)])])]);
+        let kerning = PairKerning::new(data).unwrap();
+
+        assert_eq!(kerning.kerning(1, 2), -50);
+        assert_eq!(kerning.kerning(1, 5), 30);
+        assert_eq!(kerning.kerning(1, 9), -5);
+        assert_eq!(kerning.kerning(1, 3), 0);
+        assert_eq!(kerning.kerning(2, 1), 0);
+    }
+
+    #[test]
+    fn class_kerning() {
+        let data = layout_table(KERN_TAG, &[(2, &[class_pairs([[-10, -20], [-30, -40]])])]);
+        let kerning = PairKerning::new(data).unwrap();
+
+        // Class zero of the second glyph contains all glyphs without a class.
+        assert_eq!(kerning.kerning(10, 5), -10);
+        assert_eq!(kerning.kerning(11, 25), -20);
+        assert_eq!(kerning.kerning(15, 5), -30);
+        assert_eq!(kerning.kerning(19, 29), -40);
+
+        // Glyphs outside of the coverage table are never kerned.
+        assert_eq!(kerning.kerning(20, 25), 0);
+    }
+
+    #[test]
+    fn multiple_lookups() {
+        let subtables = [glyph_pairs(1, &[(2, -50)]), glyph_pairs(1, &[(2, -10), (3, -20)])];
+        let extension = [extension(2, &glyph_pairs(1, &[(2, 5)]))];
+        let data = layout_table(KERN_TAG, &[(2, &subtables), (9, &extension)]);
+        let kerning = PairKerning::new(data).unwrap();
+
+        // Only the first matching subtable of every lookup is applied.
+        assert_eq!(kerning.kerning(1, 2), -45);
+        assert_eq!(kerning.kerning(1, 3), -20);
+    }
+
+    #[test]
+    fn missing_kern_feature() {
+        let data = layout_table(*b"mark", &[(2, &[glyph_pairs(1, &[(2, -50)])])]);
+        assert!(PairKerning::new(data).is_none());
+
+        // Lookups other than pair adjustments are ignored.
+        let data = layout_table(KERN_TAG, &[(1, &[u16s(&[1, 6, 0])])]);
+        assert!(PairKerning::new(data).is_none());
+
+        assert!(PairKerning::new(Vec::new()).is_none());
+    }
+}
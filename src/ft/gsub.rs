@@ -0,0 +1,169 @@
+//! Ligature substitutions from the OpenType `GSUB` table.
+
+use super::layout::{self, coverage_index};
+use super::sfnt::{table_u16, Tag};
+
+/// Feature tags whose ligatures form emoji sequences and other grapheme clusters.
+const LIGATURE_FEATURES: [Tag; 3] = [*b"ccmp", *b"liga", *b"rlig"];
+
+/// Lookup type of ligature substitution.
+const LIGATURE_SUBSTITUTION: u16 = 4;
+
+/// Lookup type of extension substitution.
+const EXTENSION: u16 = 7;
+
+/// Ligature substitutions of a font face.
+#[derive(Debug)]
+pub struct Ligatures {
+    data: Vec<u8>,
+
+    /// Offsets of the ligature substitution subtables, grouped by lookup.
+    lookups: Vec<Vec<usize>>,
+}
+
+impl Ligatures {
+    /// Parse the `GSUB` table.
+    ///
+    /// Returns `None` if the table has no ligature substitutions for the supported features.
+    pub fn new(data: Vec<u8>) -> Option<Self> {
+        let lookups: Vec<_> = layout::feature_lookups(&data, &LIGATURE_FEATURES)?
+            .into_iter()
+            .filter_map(|lookup| {
+                layout::lookup_subtables(&data, lookup, LIGATURE_SUBSTITUTION, EXTENSION)
+            })
+            .filter(|subtables| !subtables.is_empty())
+            .collect();
+
+        if lookups.is_empty() {
+            return None;
+        }
+
+        Some(Self { data, lookups })
+    }
+
+    /// Replace all sequences of glyphs which form a ligature with the ligature glyph.
+    pub fn substitute(&self, glyphs: &mut Vec<u16>) {
+        for subtables in &self.lookups {
+            let mut index = 0;
+            while index < glyphs.len() {
+                // Only the first subtable with a matching ligature is applied for every lookup.
+                let ligature = subtables
+                    .iter()
+                    .find_map(|subtable| ligature(&self.data, *subtable, &glyphs[index..]));
+
+                if let Some((ligature, component_count)) = ligature {
+                    glyphs.splice(index..index + component_count, [ligature]);
+                }
+
+                index += 1;
+            }
+        }
+    }
+}
+
+/// Find the ligature starting with the first glyph in a ligature substitution subtable.
+///
+/// Returns the ligature glyph and the number of glyphs it replaces.
+fn ligature(data: &[u8], subtable: usize, glyphs: &[u16]) -> Option<(u16, usize)> {
+    let format = table_u16(data, subtable)?;
+    if format != 1 {
+        return None;
+    }
+
+    let coverage = subtable + usize::from(table_u16(data, subtable + 2)?);
+    let coverage_index = coverage_index(data, coverage, *glyphs.first()?)?;
+    let ligature_set_count = table_u16(data, subtable + 4)?;
+    if coverage_index >= ligature_set_count {
+        return None;
+    }
+
+    let offset = table_u16(data, subtable + 6 + usize::from(coverage_index) * 2)?;
+    let ligature_set = subtable + usize::from(offset);
+
+    // Ligatures are ordered by preference, so the first match is used.
+    let ligature_count = usize::from(table_u16(data, ligature_set)?);
+    (0..ligature_count).find_map(|i| {
+        let ligature = ligature_set + usize::from(table_u16(data, ligature_set + 2 + i * 2)?);
+        let ligature_glyph = table_u16(data, ligature)?;
+        let component_count = usize::from(table_u16(data, ligature + 2)?);
+        if component_count == 0 || component_count > glyphs.len() {
+            return None;
+        }
+
+        // The first component is implied by the coverage table.
+        for (i, glyph) in glyphs[1..component_count].iter().enumerate() {
+            if table_u16(data, ligature + 4 + i * 2)? != *glyph {
+                return None;
+            }
+        }
+
+        Some((ligature_glyph, component_count))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ft::layout::tests::{extension, layout_table, u16s};
+
+    /// Ligature substitution format 1 with ligatures of the same first glyph.
+    fn ligatures(first: u16, ligatures: &[(&[u16], u16)]) -> Vec<u8> {
+        let mut ligature_set = u16s(&[ligatures.len() as u16]);
+        let mut ligature_data = Vec::new();
+        for (components, ligature) in ligatures {
+            let offset = 2 + 2 * ligatures.len() + ligature_data.len();
+            ligature_set.extend_from_slice(&u16s(&[offset as u16]));
+            ligature_data.extend_from_slice(&u16s(&[*ligature, components.len() as u16 + 1]));
+            ligature_data.extend_from_slice(&u16s(components));
+        }
+
+        let mut data = u16s(&[1, 8, 1, 14, 1, 1, first]);
+        data.extend_from_slice(&ligature_set);
+        data.extend_from_slice(&ligature_data);
+        data
+    }
+
+    fn substitute(ligatures: &Ligatures, glyphs: &[u16]) -> Vec<u16> {
+        let mut glyphs = glyphs.to_vec();
+        ligatures.substitute(&mut glyphs);
+        glyphs
+    }
+
+    #[test]
+    fn ligature_substitution() {
+        let subtable = ligatures(1, &[(&[2, 3], 10), (&[2], 11)]);
+        let data = layout_table(*b"ccmp", &[(LIGATURE_SUBSTITUTION, &[subtable])]);
+        let ligatures = Ligatures::new(data).unwrap();
+
+        assert_eq!(substitute(&ligatures, &[1, 2, 3]), [10]);
+        assert_eq!(substitute(&ligatures, &[1, 2, 4]), [11, 4]);
+        assert_eq!(substitute(&ligatures, &[4, 1, 2, 1, 2, 3]), [4, 11, 10]);
+        assert_eq!(substitute(&ligatures, &[2, 3]), [2, 3]);
+        assert_eq!(substitute(&ligatures, &[1]), [1]);
+    }
+
+    #[test]
+    fn multiple_lookups() {
+        // Ligatures of later lookups can contain ligatures of earlier ones.
+        let first = [ligatures(1, &[(&[2], 10)])];
+        let second = [extension(LIGATURE_SUBSTITUTION, &ligatures(10, &[(&[3], 20)]))];
+        let data = layout_table(*b"liga", &[(LIGATURE_SUBSTITUTION, &first), (EXTENSION, &second)]);
+        let ligatures = Ligatures::new(data).unwrap();
+
+        assert_eq!(substitute(&ligatures, &[1, 2, 3]), [20]);
+        assert_eq!(substitute(&ligatures, &[1, 2]), [10]);
+    }
+
+    #[test]
+    fn missing_ligature_feature() {
+        let subtable = ligatures(1, &[(&[2], 10)]);
+        let data = layout_table(*b"smcp", &[(LIGATURE_SUBSTITUTION, &[subtable])]);
+        assert!(Ligatures::new(data).is_none());
+
+        // Lookups other than ligature substitutions are ignored.
+        let data = layout_table(*b"ccmp", &[(1, &[u16s(&[1, 6, 0])])]);
+        assert!(Ligatures::new(data).is_none());
+
+        assert!(Ligatures::new(Vec::new()).is_none());
+    }
+}
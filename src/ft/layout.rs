@@ -0,0 +1,200 @@
+//! Tables shared by the OpenType `GSUB` and `GPOS` layout tables.
+
+use super::sfnt::{table_u16, table_u32, Tag};
+
+/// Get the offsets of all lookups referenced by one of the features, in lookup list order.
+///
+/// Features of all scripts are combined, since the script of the text is unknown.
+pub fn feature_lookups(data: &[u8], features: &[Tag]) -> Option<Vec<usize>> {
+    let feature_list = usize::from(table_u16(data, 6)?);
+    let lookup_list = usize::from(table_u16(data, 8)?);
+
+    let mut lookup_indices = Vec::new();
+    let feature_count = usize::from(table_u16(data, feature_list)?);
+    for feature_index in 0..feature_count {
+        let record = feature_list + 2 + feature_index * 6;
+        match data.get(record..record + 4) {
+            Some(tag) if features.iter().any(|feature| feature[..] == *tag) => (),
+            _ => continue,
+        }
+
+        let feature = feature_list + usize::from(table_u16(data, record + 4)?);
+        let lookup_count = table_u16(data, feature + 2)?;
+        for i in 0..usize::from(lookup_count) {
+            lookup_indices.push(table_u16(data, feature + 4 + i * 2)?);
+        }
+    }
+
+    // Lookups are always applied in the order of the lookup list.
+    lookup_indices.sort_unstable();
+    lookup_indices.dedup();
+
+    let lookup_count = table_u16(data, lookup_list)?;
+    let lookups = lookup_indices
+        .into_iter()
+        .filter(|index| *index < lookup_count)
+        .filter_map(|index| {
+            let offset = table_u16(data, lookup_list + 2 + usize::from(index) * 2)?;
+            Some(lookup_list + usize::from(offset))
+        })
+        .collect();
+
+    Some(lookups)
+}
+
+/// Get the offsets of all subtables of a lookup.
+///
+/// Subtables of extension lookups are resolved to the subtable they wrap. Returns `None` if the
+/// lookup is not of the type `lookup_type`.
+pub fn lookup_subtables(
+    data: &[u8],
+    lookup: usize,
+    lookup_type: u16,
+    extension_type: u16,
+) -> Option<Vec<usize>> {
+    let actual_type = table_u16(data, lookup)?;
+    let subtable_count = usize::from(table_u16(data, lookup + 4)?);
+
+    let mut subtables = Vec::with_capacity(subtable_count);
+    for i in 0..subtable_count {
+        let mut subtable = lookup + usize::from(table_u16(data, lookup + 6 + i * 2)?);
+
+        // Resolve the actual subtable of extension lookups.
+        let subtable_type = if actual_type == extension_type {
+            let extension_type = table_u16(data, subtable + 2)?;
+            subtable += table_u32(data, subtable + 4)? as usize;
+            extension_type
+        } else {
+            actual_type
+        };
+
+        if subtable_type != lookup_type {
+            return None;
+        }
+
+        subtables.push(subtable);
+    }
+
+    Some(subtables)
+}
+
+/// Get the index of a glyph inside a coverage table.
+pub fn coverage_index(data: &[u8], coverage: usize, glyph: u16) -> Option<u16> {
+    let format = table_u16(data, coverage)?;
+    let count = usize::from(table_u16(data, coverage + 2)?);
+
+    match format {
+        1 => {
+            let glyph_index = binary_search(
+                count,
+                |i| {
+                    let record_glyph = table_u16(data, coverage + 4 + i * 2)?;
+                    Some((record_glyph, record_glyph))
+                },
+                glyph,
+            )?;
+            u16::try_from(glyph_index).ok()
+        },
+        2 => {
+            let record = range_record(data, coverage + 4, count, glyph)?;
+            let start = table_u16(data, record)?;
+            let start_index = table_u16(data, record + 4)?;
+            start_index.checked_add(glyph - start)
+        },
+        _ => None,
+    }
+}
+
+/// Find the offset of the range record containing a glyph.
+///
+/// Range records consist of a start glyph, an end glyph, and a 16 bit value.
+pub fn range_record(data: &[u8], records: usize, count: usize, glyph: u16) -> Option<usize> {
+    let index = binary_search(
+        count,
+        |i| {
+            let record = records + i * 6;
+            Some((table_u16(data, record)?, table_u16(data, record + 2)?))
+        },
+        glyph,
+    )?;
+    Some(records + index * 6)
+}
+
+/// Binary search through `count` sorted glyph ranges.
+pub fn binary_search<F>(count: usize, range: F, glyph: u16) -> Option<usize>
+where
+    F: Fn(usize) -> Option<(u16, u16)>,
+{
+    let (mut min, mut max) = (0, count);
+    while min < max {
+        let mid = min + (max - min) / 2;
+        let (start, end) = range(mid)?;
+        if glyph < start {
+            max = mid;
+        } else if glyph > end {
+            min = mid + 1;
+        } else {
+            return Some(mid);
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    pub fn u16s(values: &[u16]) -> Vec<u8> {
+        values.iter().flat_map(|value| value.to_be_bytes()).collect()
+    }
+
+    /// Build a `GSUB` or `GPOS` table with the lookups of a single feature.
+    pub fn layout_table(feature: Tag, lookups: &[(u16, &[Vec<u8>])]) -> Vec<u8> {
+        let lookup_count = lookups.len() as u16;
+        let feature_list_len = 2 + 6 + 4 + 2 * lookup_count;
+        let mut data = u16s(&[1, 0, 10, 12, 12 + feature_list_len]);
+
+        // Empty script list.
+        data.extend_from_slice(&u16s(&[0]));
+
+        // Feature list with one feature for all lookups.
+        data.extend_from_slice(&u16s(&[1]));
+        data.extend_from_slice(&feature);
+        data.extend_from_slice(&u16s(&[8, 0, lookup_count]));
+        data.extend_from_slice(&u16s(&(0..lookup_count).collect::<Vec<_>>()));
+
+        // Lookup list.
+        let mut lookup_data = Vec::new();
+        let mut lookup_offsets = Vec::new();
+        for (lookup_type, subtables) in lookups {
+            lookup_offsets.push((2 + 2 * lookup_count) as usize + lookup_data.len());
+
+            let subtable_count = subtables.len() as u16;
+            let mut offset = 6 + 2 * subtable_count;
+            lookup_data.extend_from_slice(&u16s(&[*lookup_type, 0, subtable_count]));
+            for subtable in subtables.iter() {
+                lookup_data.extend_from_slice(&u16s(&[offset]));
+                offset += subtable.len() as u16;
+            }
+            for subtable in subtables.iter() {
+                lookup_data.extend_from_slice(subtable);
+            }
+        }
+        data.extend_from_slice(&u16s(&[lookup_count]));
+        for offset in lookup_offsets {
+            data.extend_from_slice(&u16s(&[offset as u16]));
+        }
+        data.extend_from_slice(&lookup_data);
+
+        data
+    }
+
+    /// Extension subtable wrapping another subtable.
+    pub fn extension(lookup_type: u16, subtable: &[u8]) -> Vec<u8> {
+        let mut data = u16s(&[1, lookup_type]);
+        data.extend_from_slice(&8u32.to_be_bytes());
+        data.extend_from_slice(subtable);
+        data
+    }
+}
@@ -0,0 +1,65 @@
+//! Enumeration of installed fonts through Fontconfig.
+
+use super::fc::{self, ConfigRef, ObjectSet, Pattern, PatternRef, Spacing};
+use crate::{FaceInfo, FamilyInfo, FontFilter, Slant};
+
+/// Fontconfig's default weight, which is used for fonts without weight.
+const DEFAULT_WEIGHT: isize = fc::Weight::Regular as isize;
+
+/// List all font families matching the filter.
+pub fn font_families(config: &ConfigRef, filter: &FontFilter) -> Vec<FamilyInfo> {
+    let mut pattern = Pattern::new();
+    if let Some(language) = &filter.language {
+        pattern.add_lang(language);
+    }
+
+    let mut objects = ObjectSet::new();
+    objects.add_family();
+    objects.add_style();
+    objects.add_weight();
+    objects.add_slant();
+    objects.add_width();
+    objects.add_spacing();
+    objects.add_scalable();
+    objects.add_color();
+    objects.add_file();
+
+    let fonts = match fc::font_list(config, &pattern, &objects) {
+        Some(fonts) => fonts,
+        None => return Vec::new(),
+    };
+
+    // Charcell fonts are monospace too, so spacing can't be matched exactly by the pattern.
+    let faces = fonts
+        .into_iter()
+        .filter_map(|font| {
+            let family = font.family().next()?.to_owned();
+            Some((family, face_info(font)))
+        })
+        .filter(|(_, face)| !filter.monospace || face.monospace);
+
+    crate::group_families(faces)
+}
+
+/// Convert a listed Fontconfig pattern to a face.
+fn face_info(font: &PatternRef) -> FaceInfo {
+    let weight = font.weight().next().unwrap_or(DEFAULT_WEIGHT);
+    let slant = match font.slant().next() {
+        Some(slant) if slant == fc::Slant::Italic as isize => Slant::Italic,
+        Some(slant) if slant == fc::Slant::Oblique as isize => Slant::Oblique,
+        _ => Slant::Normal,
+    };
+    let width = font.get_width().unwrap_or(fc::Width::Normal);
+    let spacing = font.spacing().next().unwrap_or(Spacing::Proportional as isize);
+
+    FaceInfo {
+        style: font.style().next().unwrap_or_default().to_owned(),
+        weight: fc::weight_to_opentype(weight) as u16,
+        slant,
+        width: width.to_isize() as u16,
+        monospace: spacing >= Spacing::Mono as isize,
+        scalable: font.scalable().next().unwrap_or(true),
+        color: font.color().next().unwrap_or(false),
+        path: font.file(0),
+    }
+}
@@ -1,10 +1,13 @@
 //! Rasterization powered by FreeType and Fontconfig.
 
-use std::cmp::{min, Ordering};
+use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::fmt::{self, Formatter};
+use std::fs::File;
+use std::ops::RangeInclusive;
+#[cfg(target_os = "linux")]
+use std::path::PathBuf;
 use std::rc::Rc;
-use std::time::{Duration, Instant};
 
 use freetype::face::LoadFlag;
 use freetype::tt_os2::TrueTypeOS2Table;
@@ -15,19 +18,46 @@ use log::{debug, trace};
 
 pub mod fc;
 
+mod bitmap;
+mod colr;
+mod constraint;
+mod coverage;
+mod emoji;
+mod gpos;
+mod gsub;
+mod layout;
+mod list;
+mod sfnt;
+mod strike;
+#[cfg(feature = "svg")]
+mod svg;
+#[cfg(target_os = "linux")]
+mod watcher;
+use bitmap::Bitmap;
+use constraint::{Bounds, Placement};
+pub use constraint::{ConstraintSize, GlyphConstraint};
+pub use coverage::CoverageAdjustment;
+use coverage::CoverageTable;
+pub use strike::BitmapStrike;
+#[cfg(target_os = "linux")]
+pub use watcher::{FontChange, FontWatcher};
+
 use fc::{CharSet, FtFaceLocation, Pattern, PatternHash, PatternRef, Rgba};
 
 use super::{
-    BitmapBuffer, Error, FontDesc, FontKey, GlyphKey, Metrics, Rasterize, RasterizedGlyph, Size,
-    Slant, Style, Weight,
+    BitmapBuffer, CellBox, ClusterKey, Error, FaceLocation, FamilyInfo, FontDesc, FontFilter,
+    FontKey, GlyphKey, Metrics, Rasterize, RasterizedGlyph, Size, Slant, Style, Weight,
 };
 
 /// FreeType uses 0 for the missing glyph:
 /// https://freetype.org/freetype2/docs/reference/ft2-base_interface.html#ft_get_char_index
 const MISSING_GLYPH_INDEX: u32 = 0;
 
-/// Delay before font config reload after creating the `Rasterizer`.
-const RELOAD_DELAY: Duration = Duration::from_secs(2);
+/// Variation selector requesting the text presentation of a character.
+const TEXT_PRESENTATION: char = '\u{FE0E}';
+
+/// Variation selector requesting the emoji presentation of a character.
+const EMOJI_PRESENTATION: char = '\u{FE0F}';
 
 enum FallbackFont {
     Ref { pattern: Pattern, hash: PatternHash },
@@ -41,24 +71,219 @@ impl FontKey {
     }
 }
 
-#[derive(Default)]
 struct FallbackList {
     requested_pattern: Pattern,
+    requested_hash: PatternHash,
     list: Vec<FallbackFont>,
     coverage: CharSet,
+    render_options: RenderOptions,
+}
+
+/// Rendering settings overriding the values resolved by Fontconfig.
+///
+/// Every field which is set takes precedence over the corresponding property of the rendered
+/// Fontconfig pattern, allowing applications to diverge from the user's global configuration.
+/// Since the options are part of the face identity, the same font can be loaded with different
+/// options at the same time.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct RenderOptions {
+    pub antialias: Option<bool>,
+    pub hinting: Option<bool>,
+    pub hintstyle: Option<fc::HintStyle>,
+    pub rgba: Option<Rgba>,
+    pub lcdfilter: Option<fc::LcdFilter>,
+    pub autohint: Option<bool>,
+    pub embedded_bitmaps: Option<bool>,
+}
+
+impl RenderOptions {
+    /// Override the properties of a rendered pattern.
+    fn apply(&self, pattern: &mut PatternRef) {
+        if let Some(antialias) = self.antialias {
+            pattern.set_antialias(antialias);
+        }
+
+        if let Some(hinting) = self.hinting {
+            pattern.set_hinting(hinting);
+        }
+
+        if let Some(hintstyle) = self.hintstyle {
+            pattern.set_hintstyle(hintstyle);
+        }
+
+        if let Some(rgba) = self.rgba {
+            pattern.replace_rgba(&rgba);
+        }
+
+        if let Some(lcdfilter) = self.lcdfilter {
+            pattern.set_lcdfilter(lcdfilter);
+        }
+
+        if let Some(autohint) = self.autohint {
+            pattern.set_autohint(autohint);
+        }
+
+        if let Some(embedded_bitmaps) = self.embedded_bitmaps {
+            pattern.set_embeddedbitmap(embedded_bitmaps);
+        }
+    }
+}
+
+/// Presentation of characters which are displayed as emoji by default.
+///
+/// This only affects characters with the Unicode `Emoji_Presentation` property, like `⌚` or `😀`.
+/// Variation selectors in clusters always take precedence.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum EmojiPresentation {
+    /// Use the first font with a glyph for the character, starting with the primary font.
+    #[default]
+    Text,
+    /// Prefer fonts with colored glyphs over the primary font.
+    Emoji,
+}
+
+/// Fontconfig properties which determine how a face is loaded and rendered.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RenderProperties {
+    pub antialias: bool,
+    pub hinting: bool,
+    pub hintstyle: fc::HintStyle,
+    pub rgba: Rgba,
+    pub lcdfilter: fc::LcdFilter,
+    pub autohint: bool,
+    pub embedded_bitmaps: bool,
+    pub embolden: bool,
+    pub scalable: bool,
+    pub color: bool,
+}
+
+impl RenderProperties {
+    fn from_pattern(pattern: &PatternRef) -> Self {
+        Self {
+            antialias: pattern.antialias().next().unwrap_or(true),
+            hinting: pattern.hinting().next().unwrap_or(true),
+            hintstyle: pattern.hintstyle().next().unwrap_or(fc::HintStyle::Full),
+            rgba: pattern.rgba().next().unwrap_or(Rgba::Unknown),
+            lcdfilter: pattern.lcdfilter().next().unwrap_or(fc::LcdFilter::Default),
+            autohint: pattern.autohint().next().unwrap_or(false),
+            embedded_bitmaps: pattern.embeddedbitmap().next().unwrap_or(true),
+            embolden: pattern.embolden().next().unwrap_or(false),
+            scalable: pattern.scalable().next().unwrap_or(true),
+            color: pattern.color().next().unwrap_or(false),
+        }
+    }
+}
+
+/// Face loaded for a `FontKey`, together with the settings used to render it.
+#[derive(Clone)]
+pub struct FaceDetails {
+    pub family: String,
+    pub style: String,
+    pub location: FaceLocation,
+    pub properties: RenderProperties,
+    pub load_flags: LoadFlag,
+    pub render_mode: freetype::RenderMode,
+}
+
+impl fmt::Debug for FaceDetails {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FaceDetails")
+            .field("family", &self.family)
+            .field("style", &self.style)
+            .field("location", &self.location)
+            .field("properties", &self.properties)
+            .field("load_flags", &self.load_flags)
+            .field("render_mode", &render_mode_name(self.render_mode))
+            .finish()
+    }
+}
+
+/// Font which is searched for glyphs missing from the primary font.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FallbackFace {
+    pub family: String,
+    pub style: String,
+    pub location: FaceLocation,
+    /// Whether the face was loaded to provide a glyph already.
+    pub loaded: bool,
+}
+
+/// Name of a FreeType render mode.
+pub fn render_mode_name(render_mode: freetype::RenderMode) -> &'static str {
+    match render_mode {
+        freetype::RenderMode::Normal => "Normal",
+        freetype::RenderMode::Light => "Light",
+        freetype::RenderMode::Mono => "Mono",
+        freetype::RenderMode::Lcd => "Lcd",
+        freetype::RenderMode::LcdV => "LcdV",
+        freetype::RenderMode::Max => "Max",
+        freetype::RenderMode::Sdf => "Sdf",
+    }
 }
 
 struct FaceLoadingProperties {
     load_flags: LoadFlag,
     render_mode: freetype::RenderMode,
     lcd_filter: c_uint,
-    non_scalable: Option<f32>,
+    strikes: Vec<BitmapStrike>,
     colored_bitmap: bool,
-    embolden: bool,
+    colored_outline: bool,
     matrix: Option<Matrix>,
-    pixelsize_fixup_factor: Option<f64>,
     ft_face: Rc<FtFace>,
-    rgba: Rgba,
+    ft_face_location: FtFaceLocation,
+    /// Rendered pattern the face was loaded from.
+    pattern: fc::Pattern,
+    #[cfg(feature = "svg")]
+    svg_table: Option<Rc<svg::SvgTable>>,
+    properties: RenderProperties,
+}
+
+impl FaceLoadingProperties {
+    /// Check if the face has colored glyphs.
+    fn is_color(&self) -> bool {
+        self.colored_bitmap || self.colored_outline
+    }
+
+    /// Apply a pixel size to the face, since faces are shared between all sizes.
+    ///
+    /// Faces without outlines select one of their bitmap strikes instead. Returns the factor
+    /// which scales the selected strike to the pixel size.
+    fn set_pixelsize(&self, pixelsize: f32, integer_scaling: bool) -> Result<f64, Error> {
+        let strike = if self.ft_face.is_scalable() {
+            None
+        } else if self.colored_bitmap {
+            // Colored bitmaps are downsampled, so they're never enlarged.
+            strike::downsampling_strike(&self.strikes, pixelsize).map(|index| {
+                (index, f64::from(pixelsize) / f64::from(self.strikes[index].pixelsize))
+            })
+        } else {
+            strike::closest_strike(&self.strikes, pixelsize, integer_scaling)
+                .map(|(index, factor)| (index, f64::from(factor)))
+        };
+
+        let (index, scale) = match strike {
+            Some(strike) => strike,
+            None => {
+                self.ft_face
+                    .set_char_size(to_freetype_26_6(pixelsize), 0, 0, 0)
+                    .map_err(|err| self.error(err))?;
+                return Ok(1.);
+            },
+        };
+
+        let mut ft_face = (*self.ft_face).clone();
+        let err = unsafe { freetype_sys::FT_Select_Size(ft_face.raw_mut(), index as i32) };
+        if err != 0 {
+            return Err(self.error(err.into()));
+        }
+
+        Ok(scale)
+    }
+
+    /// Attach the location of the face to a FreeType error.
+    fn error(&self, source: freetype::Error) -> Error {
+        Error::FreeType { source, face: Some(FaceLocation::from(&self.ft_face_location)) }
+    }
 }
 
 impl fmt::Debug for FaceLoadingProperties {
@@ -66,15 +291,7 @@ impl fmt::Debug for FaceLoadingProperties {
         f.debug_struct("Face")
             .field("ft_face", &self.ft_face)
             .field("load_flags", &self.load_flags)
-            .field("render_mode", &match self.render_mode {
-                freetype::RenderMode::Normal => "Normal",
-                freetype::RenderMode::Light => "Light",
-                freetype::RenderMode::Mono => "Mono",
-                freetype::RenderMode::Lcd => "Lcd",
-                freetype::RenderMode::LcdV => "LcdV",
-                freetype::RenderMode::Max => "Max",
-                freetype::RenderMode::Sdf => "Sdf",
-            })
+            .field("render_mode", &render_mode_name(self.render_mode))
             .field("lcd_filter", &self.lcd_filter)
             .finish()
     }
@@ -84,10 +301,24 @@ impl fmt::Debug for FaceLoadingProperties {
 pub struct FreeTypeRasterizer {
     loader: FreeTypeLoader,
     fallback_lists: HashMap<FontKey, FallbackList>,
+    coverage_table: Option<CoverageTable>,
+    emoji_presentation: EmojiPresentation,
+    /// Colored fallback fonts for characters with emoji presentation, by requested font.
+    emoji_faces: HashMap<(FontKey, char), Option<FontKey>>,
+    glyph_constraints: Vec<(RangeInclusive<char>, GlyphConstraint)>,
+    integer_scaling: bool,
+    config: fc::Config,
+    config_source: ConfigSource,
+}
 
-    /// Rasterizer creation time stamp to delay lazy font config updates
-    /// in `Rasterizer::load_font`.
-    creation_timestamp: Option<Instant>,
+/// Origin of a rasterizer's Fontconfig configuration.
+enum ConfigSource {
+    /// Global configuration of the process.
+    Global,
+    /// Configuration passed by the application.
+    Isolated,
+    /// Default configuration files, extended by rules of the application.
+    Rules(fc::Rules),
 }
 
 #[inline]
@@ -129,15 +360,18 @@ impl IntoF32 for i64 {
 
 impl Rasterize for FreeTypeRasterizer {
     fn new() -> Result<FreeTypeRasterizer, Error> {
-        Ok(FreeTypeRasterizer {
-            loader: FreeTypeLoader::new()?,
-            fallback_lists: HashMap::new(),
-            creation_timestamp: Some(Instant::now()),
-        })
+        let config = fc::Config::current().ok_or_else(|| {
+            Error::PlatformError("unable to load Fontconfig configuration".into())
+        })?;
+        Self::from_config(config, ConfigSource::Global)
     }
 
-    fn metrics(&self, key: FontKey, _size: Size) -> Result<Metrics, Error> {
+    fn metrics(&self, key: FontKey, size: Size) -> Result<Metrics, Error> {
         let face = &mut self.loader.faces.get(&key).ok_or(Error::UnknownFontKey)?;
+
+        // Faces are shared between sizes, so the requested size must always be applied.
+        let scale = face.set_pixelsize(size.as_px(), self.integer_scaling)?;
+
         let full = self.full_metrics(face)?;
 
         let ascent = from_freetype_26_6(full.size_metrics.ascender);
@@ -145,6 +379,21 @@ impl Rasterize for FreeTypeRasterizer {
         let glyph_height = from_freetype_26_6(full.size_metrics.height) as f64;
         let global_glyph_height = (ascent - descent) as f64;
         let height = f64::max(glyph_height, global_glyph_height);
+        let line_gap = (height - global_glyph_height) as f32;
+        let max_advance = from_freetype_26_6(full.size_metrics.max_advance) as f64;
+
+        // Get cap height and x-height in device pixels, falling back to the glyph's height.
+        let y_scale = full.size_metrics.y_scale as f32 / 65536.0;
+        let (os2_cap_height, os2_x_height) =
+            os2_heights(&mut (*face.ft_face).clone()).unwrap_or_default();
+        let cap_height = match os2_cap_height {
+            0 => glyph_height_above_baseline(face, 'H').unwrap_or(ascent),
+            cap_height => from_freetype_26_6(cap_height as f32 * y_scale),
+        };
+        let x_height = match os2_x_height {
+            0 => glyph_height_above_baseline(face, 'x').unwrap_or(ascent / 2.),
+            x_height => from_freetype_26_6(x_height as f32 * y_scale),
+        };
 
         // Get underline position and thickness in device pixels.
         let x_scale = full.size_metrics.x_scale as f32 / 65536.0;
@@ -174,47 +423,443 @@ impl Rasterize for FreeTypeRasterizer {
                 },
             };
 
-        Ok(Metrics {
+        let metrics = Metrics {
             average_advance: full.cell_width,
+            max_advance,
             line_height: height,
+            ascent,
             descent,
+            line_gap,
+            cap_height,
+            x_height,
             underline_position,
             underline_thickness,
             strikeout_position,
             strikeout_thickness,
-        })
+        };
+
+        // Bitmap strikes are scaled to the requested size.
+        Ok(scale_metrics(metrics, scale))
     }
 
     fn load_font(&mut self, desc: &FontDesc, size: Size) -> Result<FontKey, Error> {
-        if self.creation_timestamp.map_or(true, |timestamp| timestamp.elapsed() > RELOAD_DELAY) {
-            self.creation_timestamp = None;
-            fc::update_config();
-        }
-
-        self.get_face(desc, size)
+        self.load_font_with_options(desc, size, &RenderOptions::default())
     }
 
     fn get_glyph(&mut self, glyph_key: GlyphKey) -> Result<RasterizedGlyph, Error> {
         let font_key = self.face_for_glyph(glyph_key);
         let face = &self.loader.faces[&font_key];
         let index = face.ft_face.get_char_index(glyph_key.character as usize).unwrap_or_default();
-        let pixelsize = face.non_scalable.unwrap_or_else(|| glyph_key.size.as_px());
+        self.rasterize_index(font_key, index, glyph_key, None)
+    }
+
+    fn get_cluster(&mut self, cluster_key: &ClusterKey) -> Result<RasterizedGlyph, Error> {
+        let mut chars = cluster_key.text.chars();
+        let character = match chars.next() {
+            Some(character) => character,
+            None => return Ok(RasterizedGlyph::default()),
+        };
+        let glyph_key =
+            GlyphKey { character, font_key: cluster_key.font_key, size: cluster_key.size };
+
+        match (chars.next(), chars.next()) {
+            // Single characters don't need any substitutions.
+            (None, _) => return self.get_glyph(glyph_key),
+            (Some(selector), None) if is_variation_selector(selector) => {
+                return self.get_variant(glyph_key, selector);
+            },
+            _ => (),
+        }
+
+        if let Some(index) = self.cluster_glyph(glyph_key.font_key, &cluster_key.text) {
+            return self.rasterize_index(glyph_key.font_key, index, glyph_key, None);
+        }
+
+        // Prefer fonts with colored glyphs, since they provide most emoji sequences.
+        let mut fallbacks = self.fallbacks_with_char(glyph_key);
+        fallbacks.sort_by_key(|(pattern, _)| !pattern.color().next().unwrap_or(false));
+
+        for (pattern, font_key) in fallbacks {
+            let font_key = match self.load_fallback(&pattern, font_key) {
+                Some(font_key) => font_key,
+                None => continue,
+            };
+
+            if let Some(index) = self.cluster_glyph(font_key, &cluster_key.text) {
+                return self.rasterize_index(font_key, index, glyph_key, None);
+            }
+        }
+
+        // Render the first character, if no font has a ligature for the whole cluster.
+        self.get_glyph(glyph_key)
+    }
+
+    fn get_fitted_glyph(
+        &mut self,
+        glyph_key: GlyphKey,
+        cell_box: CellBox,
+    ) -> Result<RasterizedGlyph, Error> {
+        if let Some(constraint) =
+            constraint::find_constraint(&self.glyph_constraints, glyph_key.character)
+        {
+            let font_key = self.face_for_glyph(glyph_key);
+            let face = &self.loader.faces[&font_key];
+            let index =
+                face.ft_face.get_char_index(glyph_key.character as usize).unwrap_or_default();
+            return self.rasterize_index(font_key, index, glyph_key, Some((constraint, cell_box)));
+        }
+
+        let glyph = self.get_glyph(glyph_key)?;
+        let scale = match cell_box.fit_scale(&glyph) {
+            Some(scale) => scale,
+            None => return Ok(glyph),
+        };
+
+        // Colored bitmaps have fixed sizes, so they're downsampled instead.
+        let font_key = self.face_for_glyph(glyph_key);
+        if self.loader.faces[&font_key].colored_bitmap {
+            let mut glyph = downsample_bitmap(glyph, scale);
+            glyph.advance.0 = (f64::from(glyph.advance.0) * scale).round() as i32;
+            glyph.advance.1 = (f64::from(glyph.advance.1) * scale).round() as i32;
+            return Ok(cell_box.place(glyph));
+        }
+
+        crate::fit_by_size(self, glyph_key, glyph, cell_box)
+    }
+
+    fn kerning(&mut self, left: GlyphKey, right: GlyphKey) -> (f32, f32) {
+        let font_key = self.face_for_glyph(left);
+
+        // Characters from different fonts are never kerned.
+        if self.face_for_glyph(right) != font_key {
+            return (0., 0.);
+        }
+
+        let face = &self.loader.faces[&font_key];
+        let mut ft_face = (*face.ft_face).clone();
+
+        let left_index = ft_face.get_char_index(left.character as usize).unwrap_or_default();
+        let right_index = ft_face.get_char_index(right.character as usize).unwrap_or_default();
+
+        // Prefer `GPOS` kerning, since the legacy `kern` table is missing from most fonts.
+        //
+        // Pairs without `GPOS` adjustment still use the `kern` table, if the font has both.
+        let pixelsize = left.size.as_px();
+        if ft_face.is_sfnt() && ft_face.is_scalable() {
+            let location = face.ft_face_location.clone();
+            if let Some(table) = self.loader.kerning_table(location) {
+                let kerning = match (u16::try_from(left_index), u16::try_from(right_index)) {
+                    (Ok(left), Ok(right)) => table.kerning(left, right),
+                    _ => 0,
+                };
+
+                if kerning != 0 {
+                    let units_per_em = f32::from(ft_face.em_size());
+                    return (kerning as f32 * pixelsize / units_per_em, 0.);
+                }
+            }
+        }
+
+        if !freetype_sys::FT_HAS_KERNING(ft_face.raw_mut()) {
+            return (0., 0.);
+        }
+
+        let mut kerning = freetype_sys::FT_Vector::default();
+        let mode = freetype_sys::FT_KERNING_DEFAULT;
+
+        let face = &self.loader.faces[&font_key];
+        let scale = match face.set_pixelsize(pixelsize, self.integer_scaling) {
+            Ok(scale) => scale as f32,
+            Err(_) => return (0., 0.),
+        };
+
+        unsafe {
+            freetype_sys::FT_Get_Kerning(
+                ft_face.raw_mut(),
+                left_index,
+                right_index,
+                mode,
+                &mut kerning,
+            );
+        }
+
+        (from_freetype_26_6(kerning.x) * scale, from_freetype_26_6(kerning.y) * scale)
+    }
+
+    fn font_families(&self, filter: &FontFilter) -> Result<Vec<FamilyInfo>, Error> {
+        Ok(list::font_families(&self.config, filter))
+    }
+
+    fn reload(&mut self) -> Result<bool, Error> {
+        let up_to_date = match self.config_source {
+            ConfigSource::Global => self.config.is_current() && fc::config_up_to_date(),
+            ConfigSource::Rules(_) => self.config.up_to_date(),
+            // Configurations passed by the application are never reloaded.
+            ConfigSource::Isolated => true,
+        };
+
+        if up_to_date {
+            return Ok(false);
+        }
+
+        self.reinitialize()?;
+
+        Ok(true)
+    }
+}
 
-        if !face.colored_bitmap {
-            face.ft_face.set_char_size(to_freetype_26_6(pixelsize), 0, 0, 0)?;
+impl From<Slant> for fc::Slant {
+    fn from(slant: Slant) -> Self {
+        match slant {
+            Slant::Normal => fc::Slant::Roman,
+            Slant::Italic => fc::Slant::Italic,
+            Slant::Oblique => fc::Slant::Oblique,
         }
+    }
+}
+
+impl From<Weight> for fc::Weight {
+    fn from(weight: Weight) -> Self {
+        match weight {
+            Weight::Normal => fc::Weight::Regular,
+            Weight::Bold => fc::Weight::Bold,
+        }
+    }
+}
+
+struct FullMetrics {
+    size_metrics: freetype::ffi::FT_Size_Metrics,
+    cell_width: f64,
+}
+
+impl FreeTypeRasterizer {
+    /// Create a rasterizer using an isolated Fontconfig configuration.
+    ///
+    /// Unlike the global configuration, it is not affected by [`Rasterize::reload`].
+    pub fn with_config(config: fc::Config) -> Result<FreeTypeRasterizer, Error> {
+        Self::from_config(config, ConfigSource::Isolated)
+    }
+
+    /// Create a rasterizer using the default Fontconfig configuration files, extended by rules
+    /// which only apply to this rasterizer.
+    ///
+    /// The rules are applied again whenever the configuration is reloaded.
+    pub fn with_rules(rules: fc::Rules) -> Result<FreeTypeRasterizer, Error> {
+        let config = config_with_rules(&rules)?;
+        Self::from_config(config, ConfigSource::Rules(rules))
+    }
+
+    fn from_config(
+        config: fc::Config,
+        config_source: ConfigSource,
+    ) -> Result<FreeTypeRasterizer, Error> {
+        Ok(FreeTypeRasterizer {
+            loader: FreeTypeLoader::new()?,
+            fallback_lists: HashMap::new(),
+            coverage_table: None,
+            emoji_presentation: EmojiPresentation::default(),
+            emoji_faces: HashMap::new(),
+            glyph_constraints: Vec::new(),
+            integer_scaling: false,
+            config,
+            config_source,
+        })
+    }
+
+    /// Fontconfig configuration used to resolve fonts.
+    pub fn config(&self) -> &fc::ConfigRef {
+        &self.config
+    }
+
+    /// Face loaded for a `FontKey`, with the properties which determine how it is rendered.
+    pub fn face_details(&self, font_key: FontKey) -> Option<FaceDetails> {
+        let face = self.loader.faces.get(&font_key)?;
+        Some(FaceDetails {
+            family: face.ft_face.family_name().unwrap_or_default(),
+            style: face.ft_face.style_name().unwrap_or_default(),
+            location: FaceLocation::from(&face.ft_face_location),
+            properties: face.properties,
+            load_flags: face.load_flags,
+            render_mode: face.render_mode,
+        })
+    }
+
+    /// Fonts searched for glyphs missing from a font, in order of preference.
+    pub fn fallback_faces(&self, font_key: FontKey) -> Vec<FallbackFace> {
+        let fallback_list = match self.fallback_lists.get(&font_key) {
+            Some(fallback_list) => fallback_list,
+            None => return Vec::new(),
+        };
+
+        let fallback_faces = fallback_list.list.iter().filter_map(|fallback_font| {
+            let (pattern, loaded) = match fallback_font {
+                FallbackFont::Ref { pattern, .. } => (pattern, false),
+                FallbackFont::Rendered { pattern, key } => {
+                    (pattern, self.loader.faces.contains_key(key))
+                },
+            };
+
+            Some(FallbackFace {
+                family: pattern.family().next().unwrap_or_default().into(),
+                style: pattern.style().next().unwrap_or_default().into(),
+                location: FaceLocation::from(&pattern.ft_face_location(0)?),
+                loaded,
+            })
+        });
+
+        fallback_faces.collect()
+    }
+
+    /// Load the font described by `FontDesc` and `Size` with custom rendering options.
+    ///
+    /// Fonts loaded with different `RenderOptions` will receive different keys.
+    pub fn load_font_with_options(
+        &mut self,
+        desc: &FontDesc,
+        size: Size,
+        options: &RenderOptions,
+    ) -> Result<FontKey, Error> {
+        self.get_face(desc, size, options)
+    }
+
+    /// Set the adjustment applied to the coverage of all rasterized alphamask glyphs.
+    ///
+    /// Passing `None` will disable the adjustment.
+    pub fn set_coverage_adjustment(&mut self, adjustment: Option<CoverageAdjustment>) {
+        self.coverage_table = adjustment.as_ref().map(CoverageTable::new);
+    }
+
+    /// Set how characters which are displayed as emoji by default pick their font.
+    pub fn set_emoji_presentation(&mut self, emoji_presentation: EmojiPresentation) {
+        self.emoji_presentation = emoji_presentation;
+    }
+
+    /// Set the constraints for scaling and aligning glyphs of character ranges within their cells.
+    ///
+    /// Constraints are only applied by [`Rasterize::get_fitted_glyph`], since they depend on the
+    /// size of the cells. If ranges overlap, the first matching constraint is used.
+    pub fn set_glyph_constraints<I>(&mut self, constraints: I)
+    where
+        I: IntoIterator<Item = (RangeInclusive<char>, GlyphConstraint)>,
+    {
+        self.glyph_constraints = constraints.into_iter().collect();
+    }
+
+    /// Set whether bitmap fonts without outlines are enlarged by integer factors.
+    ///
+    /// Without integer scaling, the bitmap strike closest to the requested size is used as is.
+    /// Enlarged bitmaps use nearest-neighbour scaling, so they stay crisp at high DPI.
+    pub fn set_integer_scaling(&mut self, integer_scaling: bool) {
+        self.integer_scaling = integer_scaling;
+    }
+
+    /// Bitmap strikes embedded in a loaded font.
+    pub fn bitmap_strikes(&self, font_key: FontKey) -> Vec<BitmapStrike> {
+        self.loader.faces.get(&font_key).map(|face| face.strikes.clone()).unwrap_or_default()
+    }
+
+    /// Apply a change reported by a [`FontWatcher`].
+    ///
+    /// Unlike [`Rasterize::reload`], this always reloads the global Fontconfig configuration,
+    /// since fonts modified in place don't change the timestamps Fontconfig checks. Faces loaded
+    /// from one of the changed files are opened again, while previously loaded `FontKey`s remain
+    /// valid.
+    #[cfg(target_os = "linux")]
+    pub fn apply_font_change(&mut self, change: &FontChange) -> Result<(), Error> {
+        // Forget the changed files first, so the new fallback lists don't use outdated tables.
+        self.loader.reload_files(&change.paths);
+        self.reinitialize()?;
+
+        Ok(())
+    }
+
+    /// Reload the Fontconfig configuration and rebuild all fallback lists.
+    fn reinitialize(&mut self) -> Result<(), Error> {
+        self.config = match &self.config_source {
+            ConfigSource::Global => {
+                // Another rasterizer might have reloaded the global configuration already.
+                if self.config.is_current() && !fc::reinitialize() {
+                    return Err(Error::PlatformError(
+                        "unable to reload Fontconfig configuration".into(),
+                    ));
+                }
+
+                fc::Config::current().ok_or_else(|| {
+                    Error::PlatformError("unable to load Fontconfig configuration".into())
+                })?
+            },
+            ConfigSource::Rules(rules) => config_with_rules(rules)?,
+            ConfigSource::Isolated => return Ok(()),
+        };
+
+        // Rebuild fallback lists, since newly installed fonts might provide better coverage.
+        self.emoji_faces.clear();
+        for fallback_list in self.fallback_lists.values_mut() {
+            let fonts = fc::font_sort(&self.config, &fallback_list.requested_pattern);
+            let fonts = fonts.iter().flat_map(|fonts| fonts.into_iter().skip(1));
+            let (list, coverage) =
+                fallback_fonts(&mut self.loader, fonts, fallback_list.requested_hash);
+            fallback_list.list = list;
+            fallback_list.coverage = coverage;
+        }
+
+        Ok(())
+    }
+
+    /// Rasterize the glyph at `index` of a face.
+    ///
+    /// Outlines are transformed by the `constraint` before rendering, while bitmaps are only
+    /// scaled down and moved afterwards.
+    ///
+    /// Errors for missing glyphs list the fallback fonts of `glyph_key`'s font.
+    fn rasterize_index(
+        &self,
+        font_key: FontKey,
+        index: u32,
+        glyph_key: GlyphKey,
+        constraint: Option<(GlyphConstraint, CellBox)>,
+    ) -> Result<RasterizedGlyph, Error> {
+        let face = &self.loader.faces[&font_key];
+        let scale = face.set_pixelsize(glyph_key.size.as_px(), self.integer_scaling)?;
 
         unsafe {
             let ft_lib = self.loader.library.raw();
             freetype::ffi::FT_Library_SetLcdFilter(ft_lib, face.lcd_filter);
         }
 
-        face.ft_face.load_glyph(index, face.load_flags)?;
+        face.ft_face.load_glyph(index, face.load_flags).map_err(|err| face.error(err))?;
+
+        // Render colored outlines from the `COLR` or `SVG ` table.
+        if face.colored_outline && index != MISSING_GLYPH_INDEX {
+            let advance = face.ft_face.glyph().advance();
+            let advance =
+                (from_freetype_26_6(advance.x) as i32, from_freetype_26_6(advance.y) as i32);
+
+            if let Some(glyph) = self.rasterize_color_outline(face, index) {
+                let glyph = RasterizedGlyph {
+                    character: glyph_key.character,
+                    top: glyph.top,
+                    left: glyph.left,
+                    width: glyph.width,
+                    height: glyph.height,
+                    advance,
+                    buffer: BitmapBuffer::Rgba(glyph.buffer),
+                };
+
+                return Ok(match constraint {
+                    Some((constraint, cell_box)) => constrain_bitmap(glyph, constraint, cell_box),
+                    None => glyph,
+                });
+            }
+
+            // Reload the glyph, since its slot might have been clobbered.
+            face.ft_face.load_glyph(index, face.load_flags).map_err(|err| face.error(err))?;
+        }
 
         let glyph = face.ft_face.glyph();
 
         // Generate synthetic bold.
-        if face.embolden {
+        if face.properties.embolden {
             unsafe {
                 freetype_sys::FT_GlyphSlot_Embolden(glyph.raw()
                     as *const freetype_sys::FT_GlyphSlotRec
@@ -222,117 +867,118 @@ impl Rasterize for FreeTypeRasterizer {
             }
         }
 
+        let mut outline_placement = None;
         let advance = unsafe {
-            // Transform glyphs with the matrix from Fontconfig. Primarily used to generate italics.
             let raw_glyph = face.ft_face.raw().glyph;
-            if let Some(matrix) = face.matrix.as_ref() {
-                // Check that the glyph is a vectorial outline, not a bitmap.
-                if (*raw_glyph).format == freetype_sys::FT_GLYPH_FORMAT_OUTLINE {
-                    let outline = &(*raw_glyph).outline;
 
+            // Check that the glyph is a vectorial outline, not a bitmap.
+            if (*raw_glyph).format == freetype_sys::FT_GLYPH_FORMAT_OUTLINE {
+                let outline = &(*raw_glyph).outline;
+
+                // Transform glyphs with the matrix from Fontconfig. Primarily used to generate
+                // italics.
+                if let Some(matrix) = face.matrix.as_ref() {
                     freetype_sys::FT_Outline_Transform(outline, matrix);
                 }
+
+                if let Some((constraint, cell_box)) = constraint {
+                    outline_placement = Some(constrain_outline(outline, constraint, cell_box));
+                }
             }
 
             // Don't render bitmap glyphs, it results in error with freestype 2.11.0.
             if (*raw_glyph).format != freetype_sys::FT_GLYPH_FORMAT_BITMAP {
-                glyph.render_glyph(face.render_mode)?;
+                glyph.render_glyph(face.render_mode).map_err(|err| face.error(err))?;
             }
 
             let advance = (*raw_glyph).advance;
             (from_freetype_26_6(advance.x) as i32, from_freetype_26_6(advance.y) as i32)
         };
 
-        let (pixel_height, pixel_width, buffer) =
-            Self::normalize_buffer(&glyph.bitmap(), &face.rgba)?;
+        let ft_bitmap = glyph.bitmap();
+        let pixel_mode = ft_bitmap.pixel_mode().map_err(|err| face.error(err))?;
+        let (pixel_height, pixel_width, mut buffer) = Bitmap::new(&ft_bitmap, pixel_mode)
+            .normalize(&face.properties.rgba)
+            .map_err(|reason| Error::UnsupportedFormat {
+                reason,
+                face: Some(FaceLocation::from(&face.ft_face_location)),
+            })?;
+
+        if let Some(coverage_table) = &self.coverage_table {
+            coverage_table.apply(&mut buffer);
+        }
 
         let mut rasterized_glyph = RasterizedGlyph {
             character: glyph_key.character,
             top: glyph.bitmap_top(),
             left: glyph.bitmap_left(),
-            width: pixel_width,
-            height: pixel_height,
+            width: pixel_width as i32,
+            height: pixel_height as i32,
             advance,
             buffer,
         };
 
         if index == MISSING_GLYPH_INDEX {
-            return Err(Error::MissingGlyph(rasterized_glyph));
+            let tried = self.searched_families(glyph_key.font_key);
+            return Err(Error::MissingGlyph { glyph: rasterized_glyph, tried });
         }
 
         if face.colored_bitmap {
-            let fixup_factor = match face.pixelsize_fixup_factor {
-                Some(fixup_factor) => fixup_factor,
-                None => {
-                    // Fallback if the user has bitmap scaling disabled.
-                    let metrics = face.ft_face.size_metrics().ok_or(Error::MetricsNotFound)?;
-                    f64::from(pixelsize) / f64::from(metrics.y_ppem)
-                },
-            };
-
             // Scale glyph advance.
-            rasterized_glyph.advance.0 = (advance.0 as f64 * fixup_factor).round() as i32;
-            rasterized_glyph.advance.1 = (advance.1 as f64 * fixup_factor).round() as i32;
+            rasterized_glyph.advance.0 = (advance.0 as f64 * scale).round() as i32;
+            rasterized_glyph.advance.1 = (advance.1 as f64 * scale).round() as i32;
 
-            rasterized_glyph = downsample_bitmap(rasterized_glyph, fixup_factor);
+            rasterized_glyph = downsample_bitmap(rasterized_glyph, scale);
+        } else if scale > 1. {
+            rasterized_glyph = upscale_bitmap(rasterized_glyph, scale as usize);
         }
 
-        Ok(rasterized_glyph)
-    }
-
-    fn kerning(&mut self, left: GlyphKey, right: GlyphKey) -> (f32, f32) {
-        let font_key = self.face_for_glyph(left);
-        let mut ft_face = (*self.loader.faces[&font_key].ft_face).clone();
-
-        if !freetype_sys::FT_HAS_KERNING(ft_face.raw_mut()) {
-            return (0., 0.);
-        }
-
-        let left = ft_face.get_char_index(left.character as usize).unwrap_or_default();
-        let right = ft_face.get_char_index(right.character as usize).unwrap_or_default();
-
-        let mut kerning = freetype_sys::FT_Vector::default();
-        let mode = freetype_sys::FT_KERNING_DEFAULT;
-
-        unsafe {
-            freetype_sys::FT_Get_Kerning(ft_face.raw_mut(), left, right, mode, &mut kerning);
+        match (constraint, outline_placement) {
+            (_, Some(placement)) => rasterized_glyph.advance = (placement.advance, 0),
+            (Some((constraint, cell_box)), None) => {
+                rasterized_glyph = constrain_bitmap(rasterized_glyph, constraint, cell_box);
+            },
+            (None, None) => (),
         }
 
-        (from_freetype_26_6(kerning.x), from_freetype_26_6(kerning.y))
-    }
-}
-
-impl From<Slant> for fc::Slant {
-    fn from(slant: Slant) -> Self {
-        match slant {
-            Slant::Normal => fc::Slant::Roman,
-            Slant::Italic => fc::Slant::Italic,
-            Slant::Oblique => fc::Slant::Oblique,
-        }
+        Ok(rasterized_glyph)
     }
-}
 
-impl From<Weight> for fc::Weight {
-    fn from(weight: Weight) -> Self {
-        match weight {
-            Weight::Normal => fc::Weight::Regular,
-            Weight::Bold => fc::Weight::Bold,
-        }
-    }
-}
+    /// Rasterize a glyph from the color tables of a scalable font.
+    ///
+    /// This will clobber the face's glyph slot.
+    fn rasterize_color_outline(
+        &self,
+        face: &FaceLoadingProperties,
+        index: u32,
+    ) -> Option<colr::ColorGlyph> {
+        let ft_lib = self.loader.library.raw();
+        let glyph = colr::rasterize(ft_lib, &face.ft_face, index, face.matrix.as_ref());
+
+        #[cfg(feature = "svg")]
+        let glyph = glyph.or_else(|| {
+            let svg_table = face.svg_table.as_ref()?;
+            let scale = face.ft_face.size_metrics()?.y_scale as f32 / 65536. / 64.;
+            let matrix = face.matrix.map(|matrix| {
+                [matrix.xx, matrix.xy, matrix.yx, matrix.yy].map(|value| value as f32 / 65536.)
+            });
+            svg_table.rasterize(index, scale, matrix)
+        });
 
-struct FullMetrics {
-    size_metrics: freetype::ffi::FT_Size_Metrics,
-    cell_width: f64,
-}
+        glyph
+    }
 
-impl FreeTypeRasterizer {
     /// Load a font face according to `FontDesc`.
-    fn get_face(&mut self, desc: &FontDesc, size: Size) -> Result<FontKey, Error> {
+    fn get_face(
+        &mut self,
+        desc: &FontDesc,
+        size: Size,
+        options: &RenderOptions,
+    ) -> Result<FontKey, Error> {
         // Adjust for DPR.
         let size = f64::from(size.as_px());
 
-        let config = fc::Config::get_current();
+        let config = &self.config;
         let mut pattern = Pattern::new();
         pattern.add_family(&desc.name);
         pattern.add_pixelsize(size);
@@ -350,6 +996,14 @@ impl FreeTypeRasterizer {
             },
         }
 
+        // Order fallback fonts by the requested languages.
+        for language in &desc.languages {
+            pattern.add_lang(&fc::normalize_lang(language));
+        }
+        if let Some(language) = desc.languages.first() {
+            pattern.add_namelang(&fc::normalize_lang(language));
+        }
+
         // Hash requested pattern.
         let hash = pattern.hash();
 
@@ -365,7 +1019,8 @@ impl FreeTypeRasterizer {
             matched_fonts.next().ok_or_else(|| Error::FontNotFound(desc.to_owned()))?;
 
         // We should render patterns to get values like `pixelsizefixupfactor`.
-        let primary_font = pattern.render_prepare(config, primary_font);
+        let mut primary_font = pattern.render_prepare(config, primary_font);
+        options.apply(&mut primary_font);
 
         // Hash pattern together with request pattern to include requested font size in the hash.
         let primary_font_key = FontKey::from_pattern_hashes(hash, primary_font.hash());
@@ -382,36 +1037,13 @@ impl FreeTypeRasterizer {
                 .and_then(|pattern| pattern.ok_or_else(|| Error::FontNotFound(desc.to_owned())))?;
         }
 
-        // Coverage for fallback fonts.
-        let coverage = CharSet::new();
-        let list: Vec<FallbackFont> = matched_fonts
-            .filter_map(|fallback_font| {
-                // Ignore colored outline fonts, since we can not render them.
-                let color = fallback_font.color().next().unwrap_or_default();
-                let outline = fallback_font.outline().next().unwrap_or_default();
-                if color && outline {
-                    return None;
-                }
-
-                let charset = fallback_font.get_charset()?;
-                // Exclude fonts that don't contribute to the coverage, since those won't
-                // be picked up ever.
-                //
-                // We can not do this with `font_sort` since we're manually filtering out colored
-                // outline fonts.
-                if coverage.merge(charset) {
-                    let pattern = fallback_font.upgrade();
-                    Some(FallbackFont::Ref { pattern, hash })
-                } else {
-                    None
-                }
-            })
-            .collect();
-
+        let (list, coverage) = fallback_fonts(&mut self.loader, matched_fonts, hash);
         self.fallback_lists.insert(primary_font_key, FallbackList {
             requested_pattern: pattern,
+            requested_hash: hash,
             list,
             coverage,
+            render_options: options.clone(),
         });
 
         Ok(primary_font_key)
@@ -429,170 +1061,479 @@ impl FreeTypeRasterizer {
         Ok(FullMetrics { size_metrics, cell_width: width as f64 })
     }
 
+    /// Families of a font and all of its fallback fonts.
+    fn searched_families(&self, font_key: FontKey) -> Vec<String> {
+        let primary = self.loader.faces.get(&font_key).and_then(|face| face.ft_face.family_name());
+        let fallbacks = self.fallback_lists.get(&font_key).into_iter().flat_map(|fallback_list| {
+            fallback_list.list.iter().filter_map(|fallback_font| match fallback_font {
+                FallbackFont::Ref { pattern, .. } | FallbackFont::Rendered { pattern, .. } => {
+                    pattern.family().next().map(String::from)
+                },
+            })
+        });
+
+        let mut families: Vec<_> = primary.into_iter().chain(fallbacks).collect();
+        families.dedup();
+        families
+    }
+
     fn face_for_glyph(&mut self, glyph_key: GlyphKey) -> FontKey {
-        if let Some(face) = self.loader.faces.get(&glyph_key.font_key) {
-            if face.ft_face.get_char_index(glyph_key.character as usize).is_some() {
-                return glyph_key.font_key;
+        let primary = self.loader.faces.get(&glyph_key.font_key);
+        let primary_has_glyph = primary.is_some_and(|face| {
+            face.ft_face.get_char_index(glyph_key.character as usize).is_some()
+        });
+
+        // Prefer colored glyphs for characters which are displayed as emoji by default.
+        if self.emoji_presentation == EmojiPresentation::Emoji
+            && emoji::has_emoji_presentation(glyph_key.character)
+            && !(primary_has_glyph && primary.is_some_and(FaceLoadingProperties::is_color))
+        {
+            // Searching the fallback list for colored fonts is expensive, so remember the result.
+            let cache_key = (glyph_key.font_key, glyph_key.character);
+            let font_key = match self.emoji_faces.get(&cache_key) {
+                Some(font_key) => *font_key,
+                None => match self.load_face_with_glyph(glyph_key, true) {
+                    Ok(font_key) => *self.emoji_faces.entry(cache_key).or_insert(font_key),
+                    Err(_) => None,
+                },
+            };
+
+            if let Some(font_key) = font_key {
+                return font_key;
             }
         }
 
-        self.load_face_with_glyph(glyph_key).unwrap_or(glyph_key.font_key)
+        if primary_has_glyph {
+            return glyph_key.font_key;
+        }
+
+        match self.load_face_with_glyph(glyph_key, false) {
+            Ok(font_key) => font_key.unwrap_or(glyph_key.font_key),
+            Err(_) => glyph_key.font_key,
+        }
     }
 
-    fn load_face_with_glyph(&mut self, glyph: GlyphKey) -> Result<FontKey, Error> {
-        let fallback_list = self.fallback_lists.get_mut(&glyph.font_key).unwrap();
+    /// Load the first fallback font with a glyph for the character.
+    ///
+    /// With `color_only`, fonts without colored glyphs are skipped.
+    fn load_face_with_glyph(
+        &mut self,
+        glyph: GlyphKey,
+        color_only: bool,
+    ) -> Result<Option<FontKey>, Error> {
+        let fallback_list = match self.fallback_lists.get_mut(&glyph.font_key) {
+            Some(fallback_list) => fallback_list,
+            None => return Ok(None),
+        };
 
         // Check whether glyph is presented in any fallback font.
         if !fallback_list.coverage.has_char(glyph.character) {
-            return Ok(glyph.font_key);
+            return Ok(None);
         }
 
-        for fallback_font in &mut fallback_list.list {
-            if let FallbackFont::Ref { pattern, hash } = fallback_font {
-                // Don't try to build font if it doesn't have character we need.
-                if !pattern.get_charset().unwrap().has_char(glyph.character) {
-                    continue;
-                }
+        for index in 0..fallback_list.list.len() {
+            let pattern = match &fallback_list.list[index] {
+                FallbackFont::Ref { pattern, .. } | FallbackFont::Rendered { pattern, .. } => {
+                    pattern
+                },
+            };
 
-                let config = fc::Config::get_current();
-                let pattern = fallback_list.requested_pattern.render_prepare(config, pattern);
-                let key = FontKey::from_pattern_hashes(*hash, pattern.hash());
-                *fallback_font = FallbackFont::Rendered { pattern, key };
+            if color_only && !pattern.color().next().unwrap_or(false) {
+                continue;
             }
 
-            let (font_pattern, font_key) = match fallback_font {
-                FallbackFont::Rendered { pattern, key } => (pattern, *key),
-                FallbackFont::Ref { .. } => unreachable!("loaded above"),
-            };
+            // Don't try to build font if it doesn't have character we need.
+            if !pattern.get_charset().is_some_and(|cs| cs.has_char(glyph.character)) {
+                continue;
+            }
+
+            let (font_pattern, font_key) = fallback_list.render(&self.config, index);
 
             match self.loader.faces.get(&font_key) {
                 Some(face) => {
                     // We found something in a current face, so let's use it.
                     if face.ft_face.get_char_index(glyph.character as usize).is_some() {
-                        return Ok(font_key);
+                        return Ok(Some(font_key));
                     }
                 },
                 None => {
-                    if !font_pattern.get_charset().is_some_and(|cs| cs.has_char(glyph.character)) {
-                        continue;
-                    }
-
                     let pattern = font_pattern.clone();
                     if let Some(key) = self.loader.face_from_pattern(&pattern, font_key)? {
-                        return Ok(key);
+                        return Ok(Some(key));
                     }
                 },
             }
         }
 
         // You can hit this return, if you're failing to get charset from a pattern.
-        Ok(glyph.font_key)
+        Ok(None)
     }
 
-    /// Given a FreeType `Bitmap`, returns packed buffer with 1 byte per LCD channel.
-    ///
-    /// The i32 value in the return type is the number of pixels per row.
-    fn normalize_buffer(
-        bitmap: &freetype::bitmap::Bitmap,
-        rgba: &Rgba,
-    ) -> freetype::FtResult<(i32, i32, BitmapBuffer)> {
-        use freetype::bitmap::PixelMode;
-
-        let buf = bitmap.buffer();
-        let mut packed = Vec::with_capacity((bitmap.rows() * bitmap.width()) as usize);
-        let pitch = bitmap.pitch().unsigned_abs() as usize;
-        match bitmap.pixel_mode()? {
-            PixelMode::Lcd => {
-                for i in 0..bitmap.rows() {
-                    let start = (i as usize) * pitch;
-                    let stop = start + bitmap.width() as usize;
-                    match rgba {
-                        Rgba::Bgr => {
-                            for j in (start..stop).step_by(3) {
-                                packed.push(buf[j + 2]);
-                                packed.push(buf[j + 1]);
-                                packed.push(buf[j]);
-                            }
-                        },
-                        _ => packed.extend_from_slice(&buf[start..stop]),
-                    }
-                }
-                Ok((bitmap.rows(), bitmap.width() / 3, BitmapBuffer::Rgb(packed)))
-            },
-            PixelMode::LcdV => {
-                for i in 0..bitmap.rows() / 3 {
-                    for j in 0..bitmap.width() {
-                        for k in 0..3 {
-                            let k = match rgba {
-                                Rgba::Vbgr => 2 - k,
-                                _ => k,
-                            };
-                            let offset = ((i as usize) * 3 + k) * pitch + (j as usize);
-                            packed.push(buf[offset]);
-                        }
-                    }
-                }
-                Ok((bitmap.rows() / 3, bitmap.width(), BitmapBuffer::Rgb(packed)))
-            },
-            // Mono data is stored in a packed format using 1 bit per pixel.
-            PixelMode::Mono => {
-                fn unpack_byte(res: &mut Vec<u8>, byte: u8, mut count: u8) {
-                    // Mono stores MSBit at top of byte
-                    let mut bit = 7;
-                    while count != 0 {
-                        let value = ((byte >> bit) & 1) * 255;
-                        // Push value 3x since result buffer should be 1 byte
-                        // per channel.
-                        res.push(value);
-                        res.push(value);
-                        res.push(value);
-                        count -= 1;
-                        bit -= 1;
-                    }
-                }
+    /// Get the glyph of a face's ligature for the whole cluster.
+    fn cluster_glyph(&mut self, font_key: FontKey, text: &str) -> Option<u32> {
+        let face = self.loader.faces.get(&font_key)?;
+        if !face.ft_face.is_sfnt() {
+            return None;
+        }
 
-                for i in 0..(bitmap.rows() as usize) {
-                    let mut columns = bitmap.width();
-                    let mut byte = 0;
-                    let offset = i * bitmap.pitch().unsigned_abs() as usize;
-                    while columns != 0 {
-                        let bits = min(8, columns);
-                        unpack_byte(&mut packed, buf[offset + byte], bits as u8);
+        let ft_face = Rc::clone(&face.ft_face);
+        let ligatures = self.loader.ligature_table(face.ft_face_location.clone())?;
 
-                        columns -= bits;
-                        byte += 1;
-                    }
+        // Fonts often omit variation selectors from their ligatures, so also try without them.
+        let attempts: &[bool] =
+            if text.chars().any(is_variation_selector) { &[true, false] } else { &[true] };
+        for &keep_selectors in attempts {
+            let mut glyphs = Vec::new();
+            for character in text.chars() {
+                if !keep_selectors && is_variation_selector(character) {
+                    continue;
                 }
-                Ok((bitmap.rows(), bitmap.width(), BitmapBuffer::Rgb(packed)))
-            },
-            // Gray data is stored as a value between 0 and 255 using 1 byte per pixel.
-            PixelMode::Gray => {
-                for i in 0..bitmap.rows() {
-                    let start = (i as usize) * pitch;
-                    let stop = start + bitmap.width() as usize;
-                    for byte in &buf[start..stop] {
-                        packed.push(*byte);
-                        packed.push(*byte);
-                        packed.push(*byte);
-                    }
+
+                match ft_face.get_char_index(character as usize) {
+                    Some(index) => glyphs.push(u16::try_from(index).ok()?),
+                    None if is_variation_selector(character) => (),
+                    None => return None,
                 }
-                Ok((bitmap.rows(), bitmap.width(), BitmapBuffer::Rgb(packed)))
-            },
-            PixelMode::Bgra => {
-                let buf_size = (bitmap.rows() * bitmap.width() * 4) as usize;
-                let mut i = 0;
-                while i < buf_size {
-                    packed.push(buf[i + 2]);
-                    packed.push(buf[i + 1]);
-                    packed.push(buf[i]);
-                    packed.push(buf[i + 3]);
-                    i += 4;
+            }
+
+            ligatures.substitute(&mut glyphs);
+            if let [glyph] = glyphs[..] {
+                return Some(u32::from(glyph));
+            }
+        }
+
+        None
+    }
+
+    /// Rasterize a character followed by a variation selector.
+    ///
+    /// Faces with a glyph for the variation sequence in their cmap are used first. The emoji and
+    /// text presentation selectors also accept the regular glyph of faces with and without colored
+    /// glyphs respectively, preferring these faces over all others.
+    fn get_variant(
+        &mut self,
+        glyph_key: GlyphKey,
+        selector: char,
+    ) -> Result<RasterizedGlyph, Error> {
+        let color = match selector {
+            EMOJI_PRESENTATION => Some(true),
+            TEXT_PRESENTATION => Some(false),
+            _ => None,
+        };
+
+        let mut candidates = Vec::new();
+        if let Some(face) = self.loader.faces.get(&glyph_key.font_key) {
+            candidates.push((None, glyph_key.font_key, face.is_color()));
+        }
+        for (pattern, font_key) in self.fallbacks_with_char(glyph_key) {
+            let is_color = pattern.color().next().unwrap_or(false);
+            candidates.push((Some(pattern), font_key, is_color));
+        }
+
+        if let Some(color) = color {
+            candidates.sort_by_key(|(_, _, is_color)| *is_color != color);
+        }
+
+        for (pattern, font_key, is_color) in candidates {
+            let font_key = match pattern {
+                Some(pattern) => match self.load_fallback(&pattern, font_key) {
+                    Some(font_key) => font_key,
+                    None => continue,
+                },
+                None => font_key,
+            };
+
+            let ft_face = &self.loader.faces[&font_key].ft_face;
+            let index = char_variant_index(ft_face, glyph_key.character, selector).or_else(|| {
+                if color == Some(is_color) {
+                    ft_face.get_char_index(glyph_key.character as usize)
+                } else {
+                    None
                 }
-                Ok((bitmap.rows(), bitmap.width(), BitmapBuffer::Rgba(packed)))
+            });
+
+            if let Some(index) = index {
+                return self.rasterize_index(font_key, index, glyph_key, None);
+            }
+        }
+
+        self.get_glyph(glyph_key)
+    }
+
+    /// Load the face of a fallback font.
+    ///
+    /// Returns `None` if the face could not be loaded.
+    fn load_fallback(&mut self, pattern: &PatternRef, font_key: FontKey) -> Option<FontKey> {
+        match self.loader.face_from_pattern(pattern, font_key) {
+            Ok(font_key) => font_key,
+            Err(err) => {
+                debug!("Unable to load fallback font: {}", err);
+                None
             },
-            mode => panic!("unhandled pixel mode: {mode:?}"),
         }
     }
+
+    /// Fallback fonts containing the character, in order of preference.
+    fn fallbacks_with_char(&mut self, glyph_key: GlyphKey) -> Vec<(Pattern, FontKey)> {
+        let fallback_list = match self.fallback_lists.get_mut(&glyph_key.font_key) {
+            Some(fallback_list) => fallback_list,
+            None => return Vec::new(),
+        };
+
+        let mut fallbacks = Vec::new();
+        for index in 0..fallback_list.list.len() {
+            let pattern = match &fallback_list.list[index] {
+                FallbackFont::Ref { pattern, .. } | FallbackFont::Rendered { pattern, .. } => {
+                    pattern
+                },
+            };
+            if !pattern.get_charset().is_some_and(|cs| cs.has_char(glyph_key.character)) {
+                continue;
+            }
+
+            let (pattern, font_key) = fallback_list.render(&self.config, index);
+            fallbacks.push((pattern.clone(), font_key));
+        }
+
+        fallbacks
+    }
+}
+
+impl FallbackList {
+    /// Render the pattern of a fallback font, to resolve the properties of its face.
+    fn render(&mut self, config: &fc::ConfigRef, index: usize) -> (&Pattern, FontKey) {
+        let fallback_font = &mut self.list[index];
+        if let FallbackFont::Ref { pattern, hash } = fallback_font {
+            let mut pattern = self.requested_pattern.render_prepare(config, pattern);
+            self.render_options.apply(&mut pattern);
+            let key = FontKey::from_pattern_hashes(*hash, pattern.hash());
+            *fallback_font = FallbackFont::Rendered { pattern, key };
+        }
+
+        match fallback_font {
+            FallbackFont::Rendered { pattern, key } => (pattern, *key),
+            FallbackFont::Ref { .. } => unreachable!("rendered above"),
+        }
+    }
+}
+
+/// Get the glyph for a character followed by a variation selector from the face's cmap.
+fn char_variant_index(ft_face: &FtFace, character: char, selector: char) -> Option<u32> {
+    let index = unsafe {
+        let raw_face = ft_face.raw() as *const freetype_sys::FT_FaceRec as *mut _;
+        freetype_sys::FT_Face_GetCharVariantIndex(raw_face, character as _, selector as _)
+    };
+
+    (index != MISSING_GLYPH_INDEX).then_some(index)
+}
+
+/// Check if a character is a variation selector.
+fn is_variation_selector(character: char) -> bool {
+    matches!(character, '\u{FE00}'..='\u{FE0F}' | '\u{E0100}'..='\u{E01EF}')
+}
+
+/// Load the default Fontconfig configuration with additional rules.
+fn config_with_rules(rules: &fc::Rules) -> Result<fc::Config, Error> {
+    let mut config = fc::Config::load_default()
+        .ok_or_else(|| Error::PlatformError("unable to load Fontconfig configuration".into()))?;
+
+    if !config.load_rules(&rules.to_string()) {
+        return Err(Error::PlatformError("invalid Fontconfig rules".into()));
+    }
+
+    // Build fonts after loading the rules, so rejected fonts are excluded.
+    if !config.build_fonts() {
+        return Err(Error::PlatformError("unable to load fonts".into()));
+    }
+
+    Ok(config)
+}
+
+/// Get the cap height and x-height from the OS/2 table in font units.
+fn os2_heights(ft_face: &mut FtFace) -> Option<(i16, i16)> {
+    unsafe {
+        let os2 = freetype_sys::FT_Get_Sfnt_Table(ft_face.raw_mut(), freetype_sys::ft_sfnt_os2)
+            as *const freetype_sys::TT_OS2;
+
+        // Both values were only added in version 2 of the table.
+        if os2.is_null() || (*os2).version == 0xFFFF || (*os2).version < 2 {
+            return None;
+        }
+
+        Some(((*os2).sCapHeight, (*os2).sxHeight))
+    }
+}
+
+/// Get the distance between the baseline and the top of a glyph in device pixels.
+fn glyph_height_above_baseline(face: &FaceLoadingProperties, character: char) -> Option<f32> {
+    face.ft_face.get_char_index(character as usize)?;
+    face.ft_face.load_char(character as usize, face.load_flags).ok()?;
+    Some(from_freetype_26_6(face.ft_face.glyph().metrics().horiBearingY))
+}
+
+/// Collect the fonts which can be used as fallback, together with their combined coverage.
+fn fallback_fonts<'a>(
+    loader: &mut FreeTypeLoader,
+    fonts: impl Iterator<Item = &'a PatternRef>,
+    hash: PatternHash,
+) -> (Vec<FallbackFont>, CharSet) {
+    let coverage = CharSet::new();
+    let list = fonts
+        .filter_map(|fallback_font| {
+            // Ignore colored outline fonts we can not render.
+            let color = fallback_font.color().next().unwrap_or_default();
+            let outline = fallback_font.outline().next().unwrap_or_default();
+            if color && outline && !loader.has_supported_color_table(fallback_font) {
+                return None;
+            }
+
+            let charset = fallback_font.get_charset()?;
+            // Exclude fonts that don't contribute to the coverage, since those won't
+            // be picked up ever.
+            //
+            // We can not do this with `font_sort` since we're manually filtering out
+            // unsupported colored outline fonts.
+            if coverage.merge(charset) {
+                let pattern = fallback_font.upgrade();
+                Some(FallbackFont::Ref { pattern, hash })
+            } else {
+                None
+            }
+        })
+        .collect();
+
+    (list, coverage)
+}
+
+/// Scale and move an outline according to a constraint.
+///
+/// # Safety
+///
+/// The outline must be a valid FreeType outline.
+unsafe fn constrain_outline(
+    outline: &freetype_sys::FT_Outline,
+    constraint: GlyphConstraint,
+    cell_box: CellBox,
+) -> Placement {
+    let mut cbox = freetype_sys::FT_BBox { xMin: 0, yMin: 0, xMax: 0, yMax: 0 };
+    freetype_sys::FT_Outline_Get_CBox(outline, &mut cbox);
+
+    let bounds = Bounds {
+        x_min: f64::from(from_freetype_26_6(cbox.xMin)),
+        y_min: f64::from(from_freetype_26_6(cbox.yMin)),
+        x_max: f64::from(from_freetype_26_6(cbox.xMax)),
+        y_max: f64::from(from_freetype_26_6(cbox.yMax)),
+    };
+    let placement = constraint.placement(bounds, cell_box);
+
+    let matrix = Matrix {
+        xx: (placement.scale_x * 65536.).round() as freetype_sys::FT_Fixed,
+        xy: 0,
+        yx: 0,
+        yy: (placement.scale_y * 65536.).round() as freetype_sys::FT_Fixed,
+    };
+    freetype_sys::FT_Outline_Transform(outline, &matrix);
+    freetype_sys::FT_Outline_Translate(
+        outline,
+        (placement.offset_x * 64.).round() as c_long,
+        (placement.offset_y * 64.).round() as c_long,
+    );
+
+    placement
+}
+
+/// Scale and move a rendered glyph according to a constraint.
+///
+/// Bitmaps can't be enlarged or stretched, so colored bitmaps are only scaled down to fit into
+/// their cells.
+fn constrain_bitmap(
+    glyph: RasterizedGlyph,
+    constraint: GlyphConstraint,
+    cell_box: CellBox,
+) -> RasterizedGlyph {
+    let placement = constraint.placement(glyph_bounds(&glyph), cell_box);
+    let scale = placement.scale_x.min(placement.scale_y);
+    let mut glyph = downsample_bitmap(glyph, scale);
+
+    // Align the glyph again, since downsampling rounds its size and position.
+    let alignment = GlyphConstraint { size: ConstraintSize::Keep, ..constraint };
+    let placement = alignment.placement(glyph_bounds(&glyph), cell_box);
+    glyph.left += placement.offset_x.round() as i32;
+    glyph.top += placement.offset_y.round() as i32;
+    glyph.advance = (placement.advance, 0);
+
+    glyph
+}
+
+/// Bounding box of a rendered glyph.
+fn glyph_bounds(glyph: &RasterizedGlyph) -> Bounds {
+    Bounds {
+        x_min: f64::from(glyph.left),
+        y_min: f64::from(glyph.top - glyph.height),
+        x_max: f64::from(glyph.left + glyph.width),
+        y_max: f64::from(glyph.top),
+    }
+}
+
+/// Enlarge a bitmap by an integer factor, using nearest-neighbour scaling.
+fn upscale_bitmap(mut glyph: RasterizedGlyph, factor: usize) -> RasterizedGlyph {
+    let (buffer, channels) = match &glyph.buffer {
+        BitmapBuffer::Rgb(buffer) => (buffer, 3),
+        BitmapBuffer::Rgba(buffer) => (buffer, 4),
+    };
+
+    let row_len = glyph.width as usize * channels;
+    let mut upscaled = Vec::with_capacity(buffer.len() * factor * factor);
+    for row in buffer.chunks_exact(row_len.max(1)) {
+        let start = upscaled.len();
+        for pixel in row.chunks_exact(channels) {
+            for _ in 0..factor {
+                upscaled.extend_from_slice(pixel);
+            }
+        }
+
+        // Repeat the enlarged row.
+        for _ in 1..factor {
+            upscaled.extend_from_within(start..start + row_len * factor);
+        }
+    }
+
+    glyph.buffer = match glyph.buffer {
+        BitmapBuffer::Rgb(_) => BitmapBuffer::Rgb(upscaled),
+        BitmapBuffer::Rgba(_) => BitmapBuffer::Rgba(upscaled),
+    };
+
+    let factor = factor as i32;
+    glyph.width *= factor;
+    glyph.height *= factor;
+    glyph.left *= factor;
+    glyph.top *= factor;
+    glyph.advance = (glyph.advance.0 * factor, glyph.advance.1 * factor);
+
+    glyph
+}
+
+/// Scale all metrics by a fixed factor.
+fn scale_metrics(metrics: Metrics, scale: f64) -> Metrics {
+    if scale == 1. {
+        return metrics;
+    }
+
+    let scale_f32 = scale as f32;
+    Metrics {
+        average_advance: metrics.average_advance * scale,
+        max_advance: metrics.max_advance * scale,
+        line_height: metrics.line_height * scale,
+        ascent: metrics.ascent * scale_f32,
+        descent: metrics.descent * scale_f32,
+        line_gap: metrics.line_gap * scale_f32,
+        cap_height: metrics.cap_height * scale_f32,
+        x_height: metrics.x_height * scale_f32,
+        underline_position: metrics.underline_position * scale_f32,
+        underline_thickness: metrics.underline_thickness * scale_f32,
+        strikeout_position: metrics.strikeout_position * scale_f32,
+        strikeout_thickness: metrics.strikeout_thickness * scale_f32,
+    }
 }
 
 /// Downscale a bitmap by a fixed factor.
@@ -667,8 +1608,33 @@ fn downsample_bitmap(mut bitmap_glyph: RasterizedGlyph, fixup_factor: f64) -> Ra
 }
 
 impl From<freetype::Error> for Error {
-    fn from(val: freetype::Error) -> Error {
-        Error::PlatformError(val.to_string())
+    fn from(source: freetype::Error) -> Error {
+        Error::FreeType { source, face: None }
+    }
+}
+
+impl From<&FtFaceLocation> for FaceLocation {
+    fn from(location: &FtFaceLocation) -> Self {
+        Self { path: location.path.clone(), index: location.index }
+    }
+}
+
+/// Convert an error from opening a font face.
+fn face_error(source: freetype::Error, location: &FtFaceLocation) -> Error {
+    let reason = match source {
+        freetype::Error::CannotOpenResource => match File::open(&location.path) {
+            Err(source) => return Error::Io { source, path: location.path.clone() },
+            Ok(_) => None,
+        },
+        freetype::Error::UnknownFileFormat => Some("unknown file format"),
+        freetype::Error::InvalidFileFormat => Some("invalid file format"),
+        _ => None,
+    };
+
+    let face = Some(FaceLocation::from(location));
+    match reason {
+        Some(reason) => Error::UnsupportedFormat { reason: reason.into(), face },
+        None => Error::FreeType { source, face },
     }
 }
 
@@ -678,6 +1644,11 @@ struct FreeTypeLoader {
     library: Library,
     faces: HashMap<FontKey, FaceLoadingProperties>,
     ft_faces: HashMap<FtFaceLocation, Rc<FtFace>>,
+    kerning_tables: HashMap<FtFaceLocation, Option<Rc<gpos::PairKerning>>>,
+    ligature_tables: HashMap<FtFaceLocation, Option<Rc<gsub::Ligatures>>>,
+    color_tables: HashMap<FtFaceLocation, bool>,
+    #[cfg(feature = "svg")]
+    svg_tables: HashMap<FtFaceLocation, Option<Rc<svg::SvgTable>>>,
 }
 
 impl FreeTypeLoader {
@@ -687,15 +1658,95 @@ impl FreeTypeLoader {
         // Initialize default properties, like user preferred interpreter.
         unsafe { freetype_sys::FT_Set_Default_Properties(library.raw()) };
 
-        Ok(FreeTypeLoader { library, faces: HashMap::new(), ft_faces: HashMap::new() })
+        Ok(FreeTypeLoader {
+            library,
+            faces: HashMap::new(),
+            ft_faces: HashMap::new(),
+            kerning_tables: HashMap::new(),
+            ligature_tables: HashMap::new(),
+            color_tables: HashMap::new(),
+            #[cfg(feature = "svg")]
+            svg_tables: HashMap::new(),
+        })
+    }
+
+    /// Check if a font has a table for colored outlines which can be rendered.
+    fn has_supported_color_table(&mut self, pattern: &PatternRef) -> bool {
+        let location = match pattern.ft_face_location(0) {
+            Some(location) => location,
+            None => return false,
+        };
+
+        *self.color_tables.entry(location).or_insert_with_key(|location| {
+            match sfnt::table_tags(&location.path, location.index) {
+                Ok(tags) => tags
+                    .iter()
+                    .any(|tag| tag == b"COLR" || (cfg!(feature = "svg") && tag == b"SVG ")),
+                Err(err) => {
+                    debug!("Unable to read tables of {:?}: {}", location.path, err);
+                    false
+                },
+            }
+        })
+    }
+
+    /// Get the `GPOS` pair kerning of a font face.
+    fn kerning_table(&mut self, ft_face_location: FtFaceLocation) -> Option<Rc<gpos::PairKerning>> {
+        self.kerning_tables
+            .entry(ft_face_location)
+            .or_insert_with_key(|location| {
+                let table = sfnt::load_table(&location.path, location.index, *b"GPOS");
+                match table {
+                    Ok(table) => table.and_then(gpos::PairKerning::new).map(Rc::new),
+                    Err(err) => {
+                        debug!("Unable to load GPOS table of {:?}: {}", location.path, err);
+                        None
+                    },
+                }
+            })
+            .clone()
+    }
+
+    /// Get the `GSUB` ligature substitutions of a font face.
+    fn ligature_table(&mut self, ft_face_location: FtFaceLocation) -> Option<Rc<gsub::Ligatures>> {
+        self.ligature_tables
+            .entry(ft_face_location)
+            .or_insert_with_key(|location| {
+                let table = sfnt::load_table(&location.path, location.index, *b"GSUB");
+                match table {
+                    Ok(table) => table.and_then(gsub::Ligatures::new).map(Rc::new),
+                    Err(err) => {
+                        debug!("Unable to load GSUB table of {:?}: {}", location.path, err);
+                        None
+                    },
+                }
+            })
+            .clone()
+    }
+
+    /// Get the `SVG ` table of a font face.
+    #[cfg(feature = "svg")]
+    fn svg_table(&mut self, ft_face_location: FtFaceLocation) -> Option<Rc<svg::SvgTable>> {
+        self.svg_tables
+            .entry(ft_face_location)
+            .or_insert_with_key(|location| {
+                let table = sfnt::load_table(&location.path, location.index, *b"SVG ");
+                match table {
+                    Ok(table) => table.and_then(svg::SvgTable::new).map(Rc::new),
+                    Err(err) => {
+                        debug!("Unable to load SVG table of {:?}: {}", location.path, err);
+                        None
+                    },
+                }
+            })
+            .clone()
     }
 
     fn load_ft_face(&mut self, ft_face_location: FtFaceLocation) -> Result<Rc<FtFace>, Error> {
-        let mut ft_face = self.library.new_face(&ft_face_location.path, ft_face_location.index)?;
-        if ft_face.has_color() && !ft_face.is_scalable() {
-            // Select the colored bitmap size to use from the array of available sizes.
-            unsafe { freetype_sys::FT_Select_Size(ft_face.raw_mut(), 0) };
-        }
+        let ft_face = self
+            .library
+            .new_face(&ft_face_location.path, ft_face_location.index)
+            .map_err(|err| face_error(err, &ft_face_location))?;
 
         let ft_face = Rc::new(ft_face);
         self.ft_faces.insert(ft_face_location, Rc::clone(&ft_face));
@@ -703,6 +1754,48 @@ impl FreeTypeLoader {
         Ok(ft_face)
     }
 
+    /// Open all faces loaded from one of the files again.
+    ///
+    /// Faces whose file can no longer be opened are kept as they are.
+    #[cfg(target_os = "linux")]
+    fn reload_files(&mut self, paths: &[PathBuf]) {
+        self.color_tables.retain(|location, _| !paths.contains(&location.path));
+
+        let locations: Vec<_> = self
+            .ft_faces
+            .keys()
+            .filter(|location| paths.contains(&location.path))
+            .cloned()
+            .collect();
+
+        for location in locations {
+            if let Err(err) = self.load_ft_face(location.clone()) {
+                debug!("Unable to reload {:?}: {}", location.path, err);
+                continue;
+            }
+
+            self.kerning_tables.remove(&location);
+            self.ligature_tables.remove(&location);
+            #[cfg(feature = "svg")]
+            self.svg_tables.remove(&location);
+
+            // Load the faces again, since their properties depend on the file's contents.
+            let font_keys: Vec<_> = self
+                .faces
+                .iter()
+                .filter(|(_, face)| face.ft_face_location == location)
+                .map(|(font_key, _)| *font_key)
+                .collect();
+            for font_key in font_keys {
+                let face = self.faces.remove(&font_key).unwrap();
+                if let Err(err) = self.face_from_pattern(&face.pattern, font_key) {
+                    debug!("Unable to reload {:?}: {}", location.path, err);
+                    self.faces.insert(font_key, face);
+                }
+            }
+        }
+    }
+
     fn face_from_pattern(
         &mut self,
         pattern: &PatternRef,
@@ -717,16 +1810,15 @@ impl FreeTypeLoader {
 
             let ft_face = match self.ft_faces.get(&ft_face_location) {
                 Some(ft_face) => Rc::clone(ft_face),
-                None => self.load_ft_face(ft_face_location)?,
+                None => self.load_ft_face(ft_face_location.clone())?,
             };
 
-            let non_scalable = if pattern.scalable().next().unwrap_or(true) {
-                None
-            } else {
-                Some(pattern.pixelsize().next().expect("has 1+ pixelsize") as f32)
-            };
+            let color = pattern.color().next().unwrap_or(false);
+            let colored_outline = (ft_face.has_color() || color) && ft_face.is_scalable();
 
-            let embolden = pattern.embolden().next().unwrap_or(false);
+            #[cfg(feature = "svg")]
+            let svg_table =
+                if colored_outline { self.svg_table(ft_face_location.clone()) } else { None };
 
             let matrix = pattern.get_matrix().map(|matrix| {
                 // Convert Fontconfig matrix to FreeType matrix.
@@ -738,21 +1830,22 @@ impl FreeTypeLoader {
                 Matrix { xx, xy, yx, yy }
             });
 
-            let pixelsize_fixup_factor = pattern.pixelsizefixupfactor().next();
-
-            let rgba = pattern.rgba().next().unwrap_or(Rgba::Unknown);
+            let properties = RenderProperties::from_pattern(pattern);
 
             let face = FaceLoadingProperties {
-                load_flags: Self::ft_load_flags(pattern),
-                render_mode: Self::ft_render_mode(pattern),
-                lcd_filter: Self::ft_lcd_filter(pattern),
-                non_scalable,
+                load_flags: Self::ft_load_flags(&properties),
+                render_mode: Self::ft_render_mode(&properties),
+                lcd_filter: Self::ft_lcd_filter(&properties),
+                strikes: strike::bitmap_strikes(&ft_face),
                 colored_bitmap: ft_face.has_color() && !ft_face.is_scalable(),
-                embolden,
+                colored_outline,
                 matrix,
-                pixelsize_fixup_factor,
                 ft_face,
-                rgba,
+                ft_face_location,
+                pattern: pattern.to_owned(),
+                properties,
+                #[cfg(feature = "svg")]
+                svg_table,
             };
 
             debug!("Loaded Face {face:?}");
@@ -765,21 +1858,13 @@ impl FreeTypeLoader {
         }
     }
 
-    fn ft_load_flags(pattern: &PatternRef) -> LoadFlag {
-        let antialias = pattern.antialias().next().unwrap_or(true);
-        let autohint = pattern.autohint().next().unwrap_or(false);
-        let hinting = pattern.hinting().next().unwrap_or(true);
-        let rgba = pattern.rgba().next().unwrap_or(Rgba::Unknown);
-        let embedded_bitmaps = pattern.embeddedbitmap().next().unwrap_or(true);
-        let scalable = pattern.scalable().next().unwrap_or(true);
-        let color = pattern.color().next().unwrap_or(false);
+    fn ft_load_flags(properties: &RenderProperties) -> LoadFlag {
+        let RenderProperties {
+            antialias, autohint, rgba, embedded_bitmaps, scalable, color, ..
+        } = *properties;
 
         // Disable hinting if so was requested.
-        let hintstyle = if hinting {
-            pattern.hintstyle().next().unwrap_or(fc::HintStyle::Full)
-        } else {
-            fc::HintStyle::None
-        };
+        let hintstyle = if properties.hinting { properties.hintstyle } else { fc::HintStyle::None };
 
         let mut flags = match (antialias, hintstyle, rgba) {
             (false, fc::HintStyle::None, _) => LoadFlag::NO_HINTING | LoadFlag::MONOCHROME,
@@ -833,11 +1918,8 @@ impl FreeTypeLoader {
         flags
     }
 
-    fn ft_render_mode(pat: &PatternRef) -> freetype::RenderMode {
-        let antialias = pat.antialias().next().unwrap_or(true);
-        let rgba = pat.rgba().next().unwrap_or(Rgba::Unknown);
-
-        match (antialias, rgba) {
+    fn ft_render_mode(properties: &RenderProperties) -> freetype::RenderMode {
+        match (properties.antialias, properties.rgba) {
             (false, _) => freetype::RenderMode::Mono,
             (_, Rgba::Rgb) | (_, Rgba::Bgr) => freetype::RenderMode::Lcd,
             (_, Rgba::Vrgb) | (_, Rgba::Vbgr) => freetype::RenderMode::LcdV,
@@ -845,8 +1927,8 @@ impl FreeTypeLoader {
         }
     }
 
-    fn ft_lcd_filter(pat: &PatternRef) -> c_uint {
-        match pat.lcdfilter().next().unwrap_or(fc::LcdFilter::Default) {
+    fn ft_lcd_filter(properties: &RenderProperties) -> c_uint {
+        match properties.lcdfilter {
             fc::LcdFilter::None => freetype::ffi::FT_LCD_FILTER_NONE,
             fc::LcdFilter::Default => freetype::ffi::FT_LCD_FILTER_DEFAULT,
             fc::LcdFilter::Light => freetype::ffi::FT_LCD_FILTER_LIGHT,
@@ -854,3 +1936,243 @@ impl FreeTypeLoader {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+
+    use super::*;
+
+    #[test]
+    fn metrics_for_requested_size() {
+        let mut rasterizer = FreeTypeRasterizer::new().unwrap();
+        let style = Style::Description { slant: Slant::Normal, weight: Weight::Normal };
+        let desc = FontDesc::new("monospace", style);
+        let (small_size, large_size) = (Size::new(12.), Size::new(24.));
+        let font_key = rasterizer.load_font(&desc, small_size).unwrap();
+
+        let small = rasterizer.metrics(font_key, small_size).unwrap();
+        let large = rasterizer.metrics(font_key, large_size).unwrap();
+        assert!(large.line_height > small.line_height);
+        assert!(large.average_advance > small.average_advance);
+
+        // Rasterizing glyphs at another size must not change the metrics.
+        let glyph_key = GlyphKey { character: 'a', font_key, size: large_size };
+        rasterizer.get_glyph(glyph_key).unwrap();
+        let metrics = rasterizer.metrics(font_key, small_size).unwrap();
+        assert_eq!(metrics.line_height, small.line_height);
+        assert_eq!(metrics.average_advance, small.average_advance);
+        assert_eq!(metrics.cap_height, small.cap_height);
+    }
+
+    #[test]
+    fn missing_glyph_error() {
+        let mut rasterizer = FreeTypeRasterizer::new().unwrap();
+        let style = Style::Description { slant: Slant::Normal, weight: Weight::Normal };
+        let size = Size::new(12.);
+        let font_key = rasterizer.load_font(&FontDesc::new("monospace", style), size).unwrap();
+        let family = rasterizer.loader.faces[&font_key].ft_face.family_name().unwrap();
+
+        let glyph_key = GlyphKey { character: '\u{10FFFD}', font_key, size };
+        match rasterizer.get_glyph(glyph_key) {
+            Err(Error::MissingGlyph { glyph, tried }) => {
+                assert_eq!(glyph.character, '\u{10FFFD}');
+                assert_eq!(tried.first(), Some(&family));
+            },
+            result => panic!("expected missing glyph, got {result:?}"),
+        }
+    }
+
+    #[test]
+    fn face_loading_errors() {
+        use std::error::Error as _;
+
+        let mut loader = FreeTypeLoader::new().unwrap();
+
+        let location = FtFaceLocation::new("/nonexistent/font.ttf".into(), 0);
+        let err = loader.load_ft_face(location).unwrap_err();
+        assert!(matches!(err, Error::Io { .. }));
+        assert!(err.source().is_some());
+
+        let path =
+            std::env::temp_dir().join(format!("crossfont-invalid-{}.ttf", std::process::id()));
+        std::fs::write(&path, b"not a font").unwrap();
+        let err = loader.load_ft_face(FtFaceLocation::new(path.clone(), 0)).unwrap_err();
+        std::fs::remove_file(&path).unwrap();
+        match err {
+            Error::UnsupportedFormat { face: Some(face), .. } => assert_eq!(face.path, path),
+            err => panic!("expected unsupported format, got {err:?}"),
+        }
+    }
+
+    #[test]
+    fn monospace_families() {
+        let rasterizer = FreeTypeRasterizer::new().unwrap();
+        let filter = FontFilter { monospace: true, ..Default::default() };
+        let families = rasterizer.font_families(&filter).unwrap();
+
+        assert!(!families.is_empty());
+        for family in families {
+            assert!(!family.faces.is_empty());
+            assert!(family.faces.iter().all(|face| face.monospace));
+        }
+    }
+
+    #[test]
+    fn reload_unchanged_config() {
+        let mut rasterizer = FreeTypeRasterizer::new().unwrap();
+        let style = Style::Description { slant: Slant::Normal, weight: Weight::Normal };
+        let size = Size::new(12.);
+        let font_key = rasterizer.load_font(&FontDesc::new("monospace", style), size).unwrap();
+
+        assert!(!rasterizer.reload().unwrap());
+
+        // Existing fonts are still usable after reloading.
+        rasterizer.get_glyph(GlyphKey { character: 'a', font_key, size }).unwrap();
+    }
+
+    #[test]
+    fn isolated_config() {
+        let style = Style::Description { slant: Slant::Normal, weight: Weight::Normal };
+        let desc = FontDesc::new("monospace", style);
+        let size = Size::new(12.);
+
+        let mut rasterizer = FreeTypeRasterizer::new().unwrap();
+        let font_key = rasterizer.load_font(&desc, size).unwrap();
+        let path = rasterizer.loader.faces[&font_key].ft_face_location.path.clone();
+
+        // Without aliases, the only available font is used for every family.
+        let mut config = fc::Config::empty().unwrap();
+        assert!(config.add_font_file(&path));
+        let mut rasterizer = FreeTypeRasterizer::with_config(config).unwrap();
+        let font_key = rasterizer.load_font(&desc, size).unwrap();
+        assert_eq!(rasterizer.loader.faces[&font_key].ft_face_location.path, path);
+
+        let families = rasterizer.font_families(&FontFilter::default()).unwrap();
+        assert_eq!(families.len(), 1);
+        assert!(!rasterizer.reload().unwrap());
+    }
+
+    #[test]
+    fn rules() {
+        let style = Style::Description { slant: Slant::Normal, weight: Weight::Normal };
+        let size = Size::new(12.);
+
+        let mut rasterizer = FreeTypeRasterizer::new().unwrap();
+        let font_key =
+            rasterizer.load_font(&FontDesc::new("monospace", style.clone()), size).unwrap();
+        let face = &rasterizer.loader.faces[&font_key];
+        let path = face.ft_face_location.path.clone();
+        let family = face.ft_face.family_name().unwrap();
+        assert!(!face.properties.embolden);
+
+        let rules = fc::Rules::new()
+            .alias("crossfont-test-alias", [&family])
+            .set(&family, fc::Property::Embolden(true));
+        let mut rasterizer = FreeTypeRasterizer::with_rules(rules).unwrap();
+        let desc = FontDesc::new("crossfont-test-alias", style.clone());
+        let font_key = rasterizer.load_font(&desc, size).unwrap();
+        let face = &rasterizer.loader.faces[&font_key];
+        assert_eq!(face.ft_face_location.path, path);
+        assert!(face.properties.embolden);
+        assert!(!rasterizer.reload().unwrap());
+
+        let rules = fc::Rules::new().reject_family(&family);
+        let mut rasterizer = FreeTypeRasterizer::with_rules(rules).unwrap();
+        let font_key = rasterizer.load_font(&FontDesc::new("monospace", style), size).unwrap();
+        assert_ne!(rasterizer.loader.faces[&font_key].ft_face.family_name(), Some(family));
+    }
+
+    #[test]
+    fn face_details() {
+        let style = Style::Description { slant: Slant::Normal, weight: Weight::Normal };
+        let options = RenderOptions {
+            antialias: Some(false),
+            hinting: Some(false),
+            ..RenderOptions::default()
+        };
+
+        let mut rasterizer = FreeTypeRasterizer::new().unwrap();
+        let desc = FontDesc::new("monospace", style);
+        let font_key = rasterizer.load_font_with_options(&desc, Size::new(12.), &options).unwrap();
+
+        let details = rasterizer.face_details(font_key).unwrap();
+        let face = &rasterizer.loader.faces[&font_key];
+        assert_eq!(details.location.path, face.ft_face_location.path);
+        assert!(!details.properties.antialias && !details.properties.hinting);
+        assert_eq!(details.load_flags, LoadFlag::NO_HINTING | LoadFlag::MONOCHROME);
+        assert_eq!(render_mode_name(details.render_mode), "Mono");
+
+        for fallback in rasterizer.fallback_faces(font_key) {
+            assert!(!fallback.loaded);
+        }
+    }
+
+    #[test]
+    fn languages() {
+        let style = Style::Description { slant: Slant::Normal, weight: Weight::Normal };
+        let size = Size::new(12.);
+
+        let mut rasterizer = FreeTypeRasterizer::new().unwrap();
+        let desc = FontDesc::new("monospace", style);
+        let font_key = rasterizer.load_font(&desc, size).unwrap();
+        let ja_font_key =
+            rasterizer.load_font(&desc.with_languages(["ja_JP.UTF-8"]), size).unwrap();
+
+        assert_ne!(font_key, ja_font_key);
+        let pattern = &rasterizer.fallback_lists[&ja_font_key].requested_pattern;
+        assert_eq!(pattern.namelang().next(), Some("ja"));
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn reload_changed_files() {
+        let mut rasterizer = FreeTypeRasterizer::new().unwrap();
+        let style = Style::Description { slant: Slant::Normal, weight: Weight::Normal };
+        let size = Size::new(12.);
+        let font_key = rasterizer.load_font(&FontDesc::new("monospace", style), size).unwrap();
+
+        let old_face = Rc::clone(&rasterizer.loader.faces[&font_key].ft_face);
+        let path = rasterizer.loader.faces[&font_key].ft_face_location.path.clone();
+        rasterizer.loader.reload_files(&[path]);
+
+        assert!(!Rc::ptr_eq(&old_face, &rasterizer.loader.faces[&font_key].ft_face));
+        rasterizer.get_glyph(GlyphKey { character: 'a', font_key, size }).unwrap();
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn replaced_font_file() {
+        let dir = std::env::temp_dir().join(format!("crossfont-replaced-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let fonts = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fonts");
+        let font = dir.join("font.ttf");
+        fs::copy(fonts.join("CrossfontTest-Regular.ttf"), &font).unwrap();
+
+        let mut config = fc::Config::empty().unwrap();
+        assert!(config.add_font_dir(&dir));
+        let mut watcher = FontWatcher::for_config(&config).unwrap();
+        watcher.watch(&dir).unwrap();
+        let mut rasterizer = FreeTypeRasterizer::with_config(config).unwrap();
+
+        let style = Style::Description { slant: Slant::Normal, weight: Weight::Normal };
+        let desc = FontDesc::new("Crossfont Test", style);
+        let font_key = rasterizer.load_font(&desc, Size::new(12.)).unwrap();
+        assert!(!rasterizer.loader.faces[&font_key].colored_bitmap);
+
+        // Replace the outlines with color bitmaps in place.
+        fs::copy(fonts.join("CrossfontTestBitmap-Regular.ttf"), &font).unwrap();
+        let change = watcher.wait().unwrap();
+        assert!(change.paths.contains(&font));
+        rasterizer.apply_font_change(&change).unwrap();
+
+        let face = &rasterizer.loader.faces[&font_key];
+        assert!(face.colored_bitmap && !face.colored_outline);
+        let strikes = rasterizer.bitmap_strikes(font_key);
+        assert_eq!(strikes.iter().map(|strike| strike.pixelsize).collect::<Vec<_>>(), [32.]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,219 @@
+//! Minimal reader for the table directory of OpenType font files.
+
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// Four byte OpenType table tag.
+pub type Tag = [u8; 4];
+
+/// Tag of the font collection header.
+const COLLECTION_TAG: Tag = *b"ttcf";
+
+/// Versions of single font files with a table directory.
+const FONT_TAGS: [Tag; 4] = [[0, 1, 0, 0], *b"OTTO", *b"true", *b"typ1"];
+
+/// Location of a table inside the font file.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct TableRecord {
+    tag: Tag,
+    offset: u32,
+    length: u32,
+}
+
+/// Get the tags of all tables of the face at `index` in the font file at `path`.
+pub fn table_tags(path: &Path, index: isize) -> io::Result<Vec<Tag>> {
+    let mut file = File::open(path)?;
+    let records = read_table_records(&mut file, index)?;
+    Ok(records.into_iter().map(|record| record.tag).collect())
+}
+
+/// Load the raw data of the table with the specified `tag`.
+///
+/// Returns `None` if the face has no such table.
+pub fn load_table(path: &Path, index: isize, tag: Tag) -> io::Result<Option<Vec<u8>>> {
+    let mut file = File::open(path)?;
+    read_table(&mut file, index, tag)
+}
+
+fn read_table<R: Read + Seek>(
+    reader: &mut R,
+    index: isize,
+    tag: Tag,
+) -> io::Result<Option<Vec<u8>>> {
+    let records = read_table_records(reader, index)?;
+    let record = match records.into_iter().find(|record| record.tag == tag) {
+        Some(record) => record,
+        None => return Ok(None),
+    };
+
+    // Validate the length before allocating, since it's read from the untrusted font file.
+    let stream_len = reader.seek(SeekFrom::End(0))?;
+    let end = u64::from(record.offset) + u64::from(record.length);
+    if end > stream_len {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "table exceeds font file"));
+    }
+
+    reader.seek(SeekFrom::Start(u64::from(record.offset)))?;
+    let mut data = vec![0; record.length as usize];
+    reader.read_exact(&mut data)?;
+
+    Ok(Some(data))
+}
+
+fn read_table_records<R: Read + Seek>(
+    reader: &mut R,
+    index: isize,
+) -> io::Result<Vec<TableRecord>> {
+    let mut tag = [0; 4];
+    reader.read_exact(&mut tag)?;
+
+    // Resolve the offset of the face's table directory inside of collections.
+    if tag == COLLECTION_TAG {
+        // Skip the collection's version.
+        reader.seek(SeekFrom::Current(4))?;
+
+        let num_fonts = read_u32(reader)?;
+        // Only the lower 16 bits contain the face index, the rest are named instances.
+        let index = (index & 0xFFFF) as u32;
+        if index >= num_fonts {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "invalid face index"));
+        }
+
+        reader.seek(SeekFrom::Current(i64::from(index) * 4))?;
+        let offset = read_u32(reader)?;
+        reader.seek(SeekFrom::Start(u64::from(offset) + 4))?;
+    } else if !FONT_TAGS.contains(&tag) {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "not an OpenType font"));
+    }
+
+    let num_tables = read_u16(reader)?;
+
+    // Skip the binary search parameters.
+    reader.seek(SeekFrom::Current(6))?;
+
+    let mut records = Vec::with_capacity(num_tables as usize);
+    for _ in 0..num_tables {
+        let mut tag = [0; 4];
+        reader.read_exact(&mut tag)?;
+
+        // Skip the table's checksum.
+        reader.seek(SeekFrom::Current(4))?;
+
+        let offset = read_u32(reader)?;
+        let length = read_u32(reader)?;
+        records.push(TableRecord { tag, offset, length });
+    }
+
+    Ok(records)
+}
+
+/// Read a big endian `u16` at `offset` of the table `data`.
+pub fn table_u16(data: &[u8], offset: usize) -> Option<u16> {
+    let bytes = data.get(offset..offset.checked_add(2)?)?;
+    Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+/// Read a big endian `u32` at `offset` of the table `data`.
+pub fn table_u32(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset.checked_add(4)?)?;
+    Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+fn read_u16<R: Read>(reader: &mut R) -> io::Result<u16> {
+    let mut bytes = [0; 2];
+    reader.read_exact(&mut bytes)?;
+    Ok(u16::from_be_bytes(bytes))
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_be_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    fn table_directory(tags: &[&Tag]) -> Vec<u8> {
+        let mut data = vec![0, 1, 0, 0];
+        data.extend_from_slice(&(tags.len() as u16).to_be_bytes());
+        data.extend_from_slice(&[0; 6]);
+        for tag in tags {
+            data.extend_from_slice(*tag);
+            data.extend_from_slice(&[0; 12]);
+        }
+        data
+    }
+
+    fn read_table_tags(data: &[u8], index: isize) -> io::Result<Vec<Tag>> {
+        let records = read_table_records(&mut Cursor::new(data), index)?;
+        Ok(records.into_iter().map(|record| record.tag).collect())
+    }
+
+    #[test]
+    fn single_font() {
+        let data = table_directory(&[b"COLR", b"CPAL", b"glyf"]);
+        let tags = read_table_tags(&data, 0).unwrap();
+        assert_eq!(tags, [*b"COLR", *b"CPAL", *b"glyf"]);
+    }
+
+    #[test]
+    fn table_data() {
+        let mut data = table_directory(&[b"SVG "]);
+        let offset = data.len() as u32;
+        data[20..24].copy_from_slice(&offset.to_be_bytes());
+        data[24..28].copy_from_slice(&3u32.to_be_bytes());
+        data.extend_from_slice(b"svg");
+
+        let table = read_table(&mut Cursor::new(&data), 0, *b"SVG ").unwrap();
+        assert_eq!(table.as_deref(), Some(&b"svg"[..]));
+
+        assert_eq!(read_table(&mut Cursor::new(&data), 0, *b"COLR").unwrap(), None);
+    }
+
+    #[test]
+    fn oversized_table() {
+        let mut data = table_directory(&[b"GPOS"]);
+        let offset = data.len() as u32;
+        data[20..24].copy_from_slice(&offset.to_be_bytes());
+        data[24..28].copy_from_slice(&u32::MAX.to_be_bytes());
+        data.extend_from_slice(b"gpos");
+
+        let err = read_table(&mut Cursor::new(&data), 0, *b"GPOS").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        // Tables cut short by a truncated file are rejected as well.
+        data[24..28].copy_from_slice(&5u32.to_be_bytes());
+        assert!(read_table(&mut Cursor::new(&data), 0, *b"GPOS").is_err());
+    }
+
+    #[test]
+    fn collection() {
+        let first = table_directory(&[b"glyf"]);
+        let second = table_directory(&[b"SVG ", b"CFF "]);
+
+        let header_len = 12 + 2 * 4;
+        let mut data = b"ttcf\0\x01\0\0".to_vec();
+        data.extend_from_slice(&2u32.to_be_bytes());
+        data.extend_from_slice(&(header_len as u32).to_be_bytes());
+        data.extend_from_slice(&((header_len + first.len()) as u32).to_be_bytes());
+        data.extend_from_slice(&first);
+        data.extend_from_slice(&second);
+
+        let tags = read_table_tags(&data, 1).unwrap();
+        assert_eq!(tags, [*b"SVG ", *b"CFF "]);
+
+        assert!(read_table_tags(&data, 2).is_err());
+    }
+
+    #[test]
+    fn invalid_font() {
+        let mut data = table_directory(&[b"glyf"]);
+        data[..4].copy_from_slice(b"wOFF");
+        assert!(read_table_tags(&data, 0).is_err());
+    }
+}
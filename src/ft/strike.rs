@@ -0,0 +1,148 @@
+//! Selection of embedded bitmap sizes.
+
+use std::slice;
+
+use freetype::Face as FtFace;
+
+/// Largest factor strikes are enlarged by with integer scaling.
+const MAX_INTEGER_SCALE: f32 = 16.;
+
+/// Fixed size bitmaps embedded in a font face.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct BitmapStrike {
+    /// Nominal width of the glyphs in pixels.
+    pub width: i32,
+    /// Nominal height of the glyphs in pixels.
+    pub height: i32,
+    /// Pixel size the bitmaps were drawn for.
+    pub pixelsize: f32,
+}
+
+/// Get all bitmap strikes of a face.
+pub fn bitmap_strikes(ft_face: &FtFace) -> Vec<BitmapStrike> {
+    let raw = ft_face.raw();
+    if raw.available_sizes.is_null() || raw.num_fixed_sizes <= 0 {
+        return Vec::new();
+    }
+
+    let sizes = unsafe { slice::from_raw_parts(raw.available_sizes, raw.num_fixed_sizes as usize) };
+    sizes
+        .iter()
+        .map(|size| {
+            // Some bitmap formats don't report their pixels per em.
+            let pixelsize = match size.y_ppem {
+                0 => f32::from(size.height),
+                y_ppem => y_ppem as f32 / 64.,
+            };
+
+            BitmapStrike { width: i32::from(size.width), height: i32::from(size.height), pixelsize }
+        })
+        .collect()
+}
+
+/// Find the strike closest to a pixel size.
+///
+/// With `integer_scaling`, strikes can be enlarged by an integer factor to get closer to the pixel
+/// size. Returns the index of the strike together with the factor it's enlarged by.
+pub fn closest_strike(
+    strikes: &[BitmapStrike],
+    pixelsize: f32,
+    integer_scaling: bool,
+) -> Option<(usize, u32)> {
+    let candidates = usable_strikes(strikes).flat_map(|(index, strike)| {
+        let factor = if integer_scaling {
+            (pixelsize / strike.pixelsize).clamp(1., MAX_INTEGER_SCALE)
+        } else {
+            1.
+        };
+        let factors = [factor.floor() as u32, factor.ceil() as u32];
+        factors.map(|factor| (index, factor, (strike.pixelsize * factor as f32 - pixelsize).abs()))
+    });
+
+    // Prefer smaller factors and sizes if strikes are equally close.
+    candidates
+        .min_by(|(index_a, factor_a, distance_a), (index_b, factor_b, distance_b)| {
+            distance_a
+                .total_cmp(distance_b)
+                .then(factor_a.cmp(factor_b))
+                .then(strikes[*index_a].pixelsize.total_cmp(&strikes[*index_b].pixelsize))
+        })
+        .map(|(index, factor, _)| (index, factor))
+}
+
+/// Find the strike which loses the least detail when scaled down to a pixel size.
+///
+/// This is the smallest strike at least as big as the pixel size, or the biggest strike if all of
+/// them are smaller.
+pub fn downsampling_strike(strikes: &[BitmapStrike], pixelsize: f32) -> Option<usize> {
+    let bigger = usable_strikes(strikes)
+        .filter(|(_, strike)| strike.pixelsize >= pixelsize)
+        .min_by(|(_, a), (_, b)| a.pixelsize.total_cmp(&b.pixelsize));
+
+    bigger
+        .or_else(|| {
+            usable_strikes(strikes).max_by(|(_, a), (_, b)| a.pixelsize.total_cmp(&b.pixelsize))
+        })
+        .map(|(index, _)| index)
+}
+
+/// Strikes with a size they can be scaled from, together with their index.
+///
+/// Broken fonts can report strikes without any size.
+fn usable_strikes(strikes: &[BitmapStrike]) -> impl Iterator<Item = (usize, &BitmapStrike)> {
+    strikes
+        .iter()
+        .enumerate()
+        .filter(|(_, strike)| strike.pixelsize.is_finite() && strike.pixelsize > 0.)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strikes(pixelsizes: &[f32]) -> Vec<BitmapStrike> {
+        let strike = |&pixelsize| BitmapStrike { width: 0, height: 0, pixelsize };
+        pixelsizes.iter().map(strike).collect()
+    }
+
+    #[test]
+    fn strike_selection() {
+        let strikes = strikes(&[12., 16., 32.]);
+
+        assert_eq!(closest_strike(&strikes, 16., false), Some((1, 1)));
+        assert_eq!(closest_strike(&strikes, 14., false), Some((0, 1)));
+        assert_eq!(closest_strike(&strikes, 26., false), Some((2, 1)));
+        assert_eq!(closest_strike(&strikes, 64., false), Some((2, 1)));
+        assert_eq!(closest_strike(&strikes, 8., true), Some((0, 1)));
+
+        // Enlarged strikes are only used if they're closer than all other strikes.
+        assert_eq!(closest_strike(&strikes, 24., true), Some((0, 2)));
+        assert_eq!(closest_strike(&strikes, 32., true), Some((2, 1)));
+        assert_eq!(closest_strike(&strikes, 66., true), Some((2, 2)));
+        assert_eq!(closest_strike(&strikes, 48., true), Some((1, 3)));
+
+        assert_eq!(downsampling_strike(&strikes, 14.), Some(1));
+        assert_eq!(downsampling_strike(&strikes, 16.), Some(1));
+        assert_eq!(downsampling_strike(&strikes, 64.), Some(2));
+
+        assert_eq!(closest_strike(&[], 16., true), None);
+        assert_eq!(downsampling_strike(&[], 16.), None);
+    }
+
+    #[test]
+    fn invalid_strikes() {
+        // Strikes without a size are never selected.
+        let strikes = strikes(&[0., 8., f32::NAN]);
+        assert_eq!(closest_strike(&strikes, 16., true), Some((1, 2)));
+        assert_eq!(downsampling_strike(&strikes, 4.), Some(1));
+        assert_eq!(downsampling_strike(&strikes, 16.), Some(1));
+
+        let empty = self::strikes(&[0.]);
+        assert_eq!(closest_strike(&empty, 16., true), None);
+        assert_eq!(downsampling_strike(&empty, 16.), None);
+
+        // Tiny strikes are only enlarged up to the maximum factor.
+        let tiny = self::strikes(&[0.001]);
+        assert_eq!(closest_strike(&tiny, 1000., true), Some((0, 16)));
+    }
+}
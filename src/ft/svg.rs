@@ -0,0 +1,220 @@
+//! Rendering of colored glyphs from the OpenType `SVG ` table.
+
+use log::debug;
+use resvg::tiny_skia::{Pixmap, Transform};
+use resvg::usvg::{self, Node, Tree};
+
+use super::colr::ColorGlyph;
+use super::sfnt::{table_u16, table_u32};
+
+/// Size of the `SVG ` table header.
+const HEADER_SIZE: usize = 10;
+
+/// Size of a single SVG document record.
+const DOCUMENT_RECORD_SIZE: usize = 12;
+
+/// Maximum number of pixels of an SVG glyph.
+const MAX_PIXELS: u64 = 4096 * 4096;
+
+/// Raw `SVG ` table of a font face.
+#[derive(Debug)]
+pub struct SvgTable {
+    data: Vec<u8>,
+    document_list: usize,
+    num_documents: usize,
+}
+
+impl SvgTable {
+    /// Parse the `SVG ` table header.
+    pub fn new(data: Vec<u8>) -> Option<Self> {
+        if data.len() < HEADER_SIZE {
+            return None;
+        }
+
+        let document_list = table_u32(&data, 2)? as usize;
+        let num_documents = table_u16(&data, document_list)? as usize;
+
+        let records_end = document_list + 2 + num_documents * DOCUMENT_RECORD_SIZE;
+        if records_end > data.len() {
+            return None;
+        }
+
+        Some(Self { data, document_list, num_documents })
+    }
+
+    /// Get the SVG document containing the glyph at `glyph_index`.
+    fn document(&self, glyph_index: u32) -> Option<&[u8]> {
+        let (mut min, mut max) = (0, self.num_documents);
+        while min < max {
+            let mid = min + (max - min) / 2;
+            let record = self.document_list + 2 + mid * DOCUMENT_RECORD_SIZE;
+
+            let start_glyph = u32::from(table_u16(&self.data, record)?);
+            let end_glyph = u32::from(table_u16(&self.data, record + 2)?);
+
+            if glyph_index < start_glyph {
+                max = mid;
+            } else if glyph_index > end_glyph {
+                min = mid + 1;
+            } else {
+                let offset = self.document_list + table_u32(&self.data, record + 4)? as usize;
+                let length = table_u32(&self.data, record + 8)? as usize;
+                return self.data.get(offset..offset.checked_add(length)?);
+            }
+        }
+
+        None
+    }
+
+    /// Rasterize the glyph at `glyph_index`.
+    ///
+    /// The `scale` converts font units to pixels, while the optional `matrix` is applied in
+    /// pixel space with the Y axis pointing up.
+    ///
+    /// Returns `None` if the table has no document for this glyph.
+    pub fn rasterize(
+        &self,
+        glyph_index: u32,
+        scale: f32,
+        matrix: Option<[f32; 4]>,
+    ) -> Option<ColorGlyph> {
+        let document = self.document(glyph_index)?;
+
+        let tree = match Tree::from_data(document, &usvg::Options::default()) {
+            Ok(tree) => tree,
+            Err(err) => {
+                debug!("Unable to parse SVG document for glyph {glyph_index}: {err}");
+                return None;
+            },
+        };
+
+        // Documents might contain multiple glyphs, so only the glyph's element is rendered.
+        let node = tree.node_by_id(&format!("glyph{glyph_index}"))?;
+
+        // Transformation from the SVG document into pixels, with the Y axis pointing down.
+        let mut pixel_transform = Transform::from_scale(scale, scale);
+        if let Some([xx, xy, yx, yy]) = matrix {
+            let matrix = Transform::from_row(xx, -yx, -xy, yy, 0., 0.);
+            pixel_transform = matrix.pre_concat(pixel_transform);
+        }
+
+        let empty = ColorGlyph { left: 0, top: 0, width: 0, height: 0, buffer: Vec::new() };
+        let bounds = match node.abs_layer_bounding_box() {
+            Some(bounds) => bounds,
+            None => return Some(empty),
+        };
+        let pixel_bounds = match bounds.transform(pixel_transform) {
+            Some(pixel_bounds) => pixel_bounds,
+            None => return Some(empty),
+        };
+
+        let left = pixel_bounds.left().floor();
+        let top = pixel_bounds.top().floor();
+        let width = (pixel_bounds.right().ceil() - left) as u32;
+        let height = (pixel_bounds.bottom().ceil() - top) as u32;
+
+        if u64::from(width) * u64::from(height) > MAX_PIXELS {
+            return None;
+        }
+
+        let mut pixmap = match Pixmap::new(width, height) {
+            Some(pixmap) => pixmap,
+            None => return Some(empty),
+        };
+
+        // The renderer positions the node's bounding box at the origin before applying our
+        // transform, so the transform must undo that and apply all ancestor transforms.
+        let transform = Transform::from_translate(-left, -top)
+            .pre_concat(pixel_transform)
+            .pre_concat(parent_transform(node))
+            .pre_translate(bounds.x(), bounds.y());
+        resvg::render_node(node, transform, &mut pixmap.as_mut());
+
+        Some(ColorGlyph {
+            left: left as i32,
+            top: -top as i32,
+            width: width as i32,
+            height: height as i32,
+            buffer: pixmap.take(),
+        })
+    }
+}
+
+/// Get the absolute transform of a node's parent.
+fn parent_transform(node: &Node) -> Transform {
+    match node {
+        Node::Group(group) => {
+            let relative = group.transform().invert().unwrap_or_default();
+            group.abs_transform().pre_concat(relative)
+        },
+        node => node.abs_transform(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build an `SVG ` table with one document per glyph range.
+    fn svg_table(documents: &[(u16, u16, &str)]) -> Vec<u8> {
+        let mut data = vec![0, 0];
+        data.extend_from_slice(&(HEADER_SIZE as u32).to_be_bytes());
+        data.extend_from_slice(&[0; 4]);
+
+        data.extend_from_slice(&(documents.len() as u16).to_be_bytes());
+        let mut offset = 2 + documents.len() * DOCUMENT_RECORD_SIZE;
+        for (start, end, document) in documents {
+            data.extend_from_slice(&start.to_be_bytes());
+            data.extend_from_slice(&end.to_be_bytes());
+            data.extend_from_slice(&(offset as u32).to_be_bytes());
+            data.extend_from_slice(&(document.len() as u32).to_be_bytes());
+            offset += document.len();
+        }
+
+        for (.., document) in documents {
+            data.extend_from_slice(document.as_bytes());
+        }
+
+        data
+    }
+
+    const SQUARES: &str = r##"<svg xmlns="http://www.w3.org/2000/svg">
+        <rect id="glyph1" x="0" y="-800" width="1000" height="800" fill="#ff0000"/>
+        <g id="glyph2" transform="translate(500, 0)">
+            <rect x="0" y="-500" width="500" height="500" fill="#0000ff" fill-opacity="0.5"/>
+        </g>
+    </svg>"##;
+
+    #[test]
+    fn document_lookup() {
+        let table = SvgTable::new(svg_table(&[(1, 2, "a"), (5, 5, "b"), (7, 9, "c")])).unwrap();
+        assert_eq!(table.document(0), None);
+        assert_eq!(table.document(2), Some(&b"a"[..]));
+        assert_eq!(table.document(5), Some(&b"b"[..]));
+        assert_eq!(table.document(6), None);
+        assert_eq!(table.document(9), Some(&b"c"[..]));
+    }
+
+    #[test]
+    fn truncated_table() {
+        let mut data = svg_table(&[(1, 2, "a")]);
+        data.truncate(HEADER_SIZE + 4);
+        assert!(SvgTable::new(data).is_none());
+    }
+
+    #[test]
+    fn rasterize_glyph() {
+        let table = SvgTable::new(svg_table(&[(1, 2, SQUARES)])).unwrap();
+
+        let glyph = table.rasterize(1, 0.01, None).unwrap();
+        assert_eq!((glyph.left, glyph.top, glyph.width, glyph.height), (0, 8, 10, 8));
+        assert!(glyph.buffer.chunks(4).all(|pixel| pixel == [255, 0, 0, 255]));
+
+        // Only the requested glyph is rendered, with its premultiplied color.
+        let glyph = table.rasterize(2, 0.01, None).unwrap();
+        assert_eq!((glyph.left, glyph.top, glyph.width, glyph.height), (5, 5, 5, 5));
+        assert!(glyph.buffer.chunks(4).all(|pixel| pixel == [0, 0, 128, 128]));
+
+        assert!(table.rasterize(3, 0.01, None).is_none());
+    }
+}
@@ -0,0 +1,360 @@
+//! Notifications for changes to the Fontconfig configuration and font directories.
+
+use std::collections::{BTreeSet, HashMap};
+use std::ffi::OsStr;
+use std::io;
+use std::mem;
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd, FromRawFd, OwnedFd, RawFd};
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::ptr;
+use std::thread::{self, JoinHandle};
+
+use libc::c_int;
+use log::debug;
+
+use super::fc::{self, ConfigRef};
+
+/// Events which indicate that a font or configuration file was added, removed or modified.
+const WATCH_MASK: u32 = libc::IN_CLOSE_WRITE
+    | libc::IN_CREATE
+    | libc::IN_DELETE
+    | libc::IN_MOVED_FROM
+    | libc::IN_MOVED_TO
+    | libc::IN_ONLYDIR;
+
+/// Events of a parent directory which indicate that a missing directory might have been created.
+///
+/// The events are added to existing watches of the parent, instead of replacing them.
+const PARENT_MASK: u32 = libc::IN_CREATE | libc::IN_MOVED_TO | libc::IN_ONLYDIR | libc::IN_MASK_ADD;
+
+/// Files and directories which changed since the last notification.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct FontChange {
+    /// Changed paths, in the order they were reported.
+    ///
+    /// When the kernel dropped events, this contains all watched directories instead.
+    pub paths: Vec<PathBuf>,
+}
+
+/// Watcher for the Fontconfig configuration files and font directories, using inotify.
+///
+/// Changes can either be polled, optionally after waiting for the watcher's file descriptor to
+/// become readable, or received through a callback on a separate thread. The notification should
+/// then be passed to [`FreeTypeRasterizer::apply_font_change`].
+///
+/// [`FreeTypeRasterizer::apply_font_change`]: super::FreeTypeRasterizer::apply_font_change
+#[derive(Debug)]
+pub struct FontWatcher {
+    fd: OwnedFd,
+    watches: HashMap<c_int, PathBuf>,
+
+    /// Directories which don't exist yet.
+    missing: BTreeSet<PathBuf>,
+
+    /// Nearest existing parents of missing directories, only watched for their creation.
+    parents: HashMap<c_int, PathBuf>,
+}
+
+impl FontWatcher {
+    /// Watch the current Fontconfig configuration.
+    pub fn new() -> io::Result<Self> {
+        Self::for_config(fc::Config::get_current())
+    }
+
+    /// Watch the configuration files and font directories of a Fontconfig configuration.
+    ///
+    /// Directories which don't exist yet are watched once they're created.
+    pub fn for_config(config: &ConfigRef) -> io::Result<Self> {
+        let fd = unsafe { libc::inotify_init1(libc::IN_NONBLOCK | libc::IN_CLOEXEC) };
+        if fd == -1 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+        let mut watcher =
+            Self { fd, watches: HashMap::new(), missing: BTreeSet::new(), parents: HashMap::new() };
+
+        let config_files = config.config_files();
+        let config_file_dirs = config_files.iter().filter_map(|file| file.parent());
+        let dirs: BTreeSet<_> =
+            config.config_dirs().into_iter().chain(config.font_dirs()).collect();
+
+        for dir in config_file_dirs.map(Path::to_path_buf).chain(dirs) {
+            if let Err(err) = watcher.watch(&dir) {
+                debug!("Unable to watch {dir:?}: {err}");
+            }
+        }
+
+        Ok(watcher)
+    }
+
+    /// Watch an additional directory.
+    ///
+    /// Subdirectories created later on are watched automatically. If the directory doesn't exist
+    /// yet, its nearest existing parent is watched until the directory is created.
+    pub fn watch(&mut self, dir: &Path) -> io::Result<()> {
+        match self.add_watch(dir, WATCH_MASK) {
+            Ok(wd) => {
+                self.watches.insert(wd, dir.to_path_buf());
+                return Ok(());
+            },
+            Err(err) if err.kind() == io::ErrorKind::NotFound => (),
+            Err(err) => return Err(err),
+        }
+
+        let parent = match dir.ancestors().skip(1).find(|parent| parent.is_dir()) {
+            Some(parent) => parent,
+            None => return Err(io::ErrorKind::NotFound.into()),
+        };
+
+        let wd = self.add_watch(parent, PARENT_MASK)?;
+        self.parents.insert(wd, parent.to_path_buf());
+        self.missing.insert(dir.to_path_buf());
+
+        Ok(())
+    }
+
+    /// Add an inotify watch for a directory.
+    fn add_watch(&self, dir: &Path, mask: u32) -> io::Result<c_int> {
+        let path = dir.as_os_str().as_bytes();
+        let mut c_path = Vec::with_capacity(path.len() + 1);
+        c_path.extend_from_slice(path);
+        c_path.push(0);
+
+        let wd =
+            unsafe { libc::inotify_add_watch(self.fd.as_raw_fd(), c_path.as_ptr().cast(), mask) };
+        if wd == -1 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(wd)
+    }
+
+    /// Watch missing directories inside a newly created directory.
+    fn watch_created(&mut self, created: &Path, change: &mut FontChange) {
+        let created_dirs: Vec<_> =
+            self.missing.iter().filter(|dir| dir.starts_with(created)).cloned().collect();
+        for dir in created_dirs {
+            self.missing.remove(&dir);
+            if let Err(err) = self.watch(&dir) {
+                debug!("Unable to watch {dir:?}: {err}");
+            }
+
+            // Fonts might have been added before the directory was watched.
+            if self.watches.values().any(|watched| *watched == dir) {
+                push_unique(&mut change.paths, dir);
+            }
+        }
+
+        // Stop watching parents which are no longer needed.
+        let unused: Vec<_> = self
+            .parents
+            .iter()
+            .filter(|(_, parent)| !self.missing.iter().any(|dir| dir.starts_with(parent)))
+            .map(|(wd, _)| *wd)
+            .collect();
+        for wd in unused {
+            self.parents.remove(&wd);
+            if !self.watches.contains_key(&wd) {
+                unsafe { libc::inotify_rm_watch(self.fd.as_raw_fd(), wd) };
+            }
+        }
+    }
+
+    /// Get all changes since the last call, without blocking.
+    pub fn poll(&mut self) -> io::Result<Option<FontChange>> {
+        // Buffer aligned for `inotify_event`, large enough for multiple events.
+        #[repr(C, align(4))]
+        struct Buffer([u8; 4096]);
+
+        let mut buffer = Buffer([0; 4096]);
+        let mut change = FontChange::default();
+
+        loop {
+            let len = unsafe {
+                libc::read(self.fd.as_raw_fd(), buffer.0.as_mut_ptr().cast(), buffer.0.len())
+            };
+
+            if len == -1 {
+                let err = io::Error::last_os_error();
+                match err.kind() {
+                    io::ErrorKind::WouldBlock => break,
+                    io::ErrorKind::Interrupted => continue,
+                    _ => return Err(err),
+                }
+            }
+
+            let mut offset = 0;
+            while offset + mem::size_of::<libc::inotify_event>() <= len as usize {
+                let event = unsafe {
+                    ptr::read_unaligned(buffer.0.as_ptr().add(offset).cast::<libc::inotify_event>())
+                };
+                let name_start = offset + mem::size_of::<libc::inotify_event>();
+                let name = &buffer.0[name_start..name_start + event.len as usize];
+                offset = name_start + event.len as usize;
+
+                // Names are padded with NUL bytes.
+                let name_len = name.iter().position(|&byte| byte == 0).unwrap_or(name.len());
+                self.handle_event(&event, OsStr::from_bytes(&name[..name_len]), &mut change);
+            }
+        }
+
+        if change.paths.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(change))
+    }
+
+    /// Block until the next change.
+    pub fn wait(&mut self) -> io::Result<FontChange> {
+        loop {
+            if let Some(change) = self.poll()? {
+                return Ok(change);
+            }
+
+            let mut pollfd =
+                libc::pollfd { fd: self.fd.as_raw_fd(), events: libc::POLLIN, revents: 0 };
+            if unsafe { libc::poll(&mut pollfd, 1, -1) } == -1 {
+                let err = io::Error::last_os_error();
+                if err.kind() != io::ErrorKind::Interrupted {
+                    return Err(err);
+                }
+            }
+        }
+    }
+
+    /// Call `callback` for every change on a separate thread.
+    ///
+    /// Watching stops once the callback returns `false`.
+    pub fn spawn<F>(mut self, mut callback: F) -> JoinHandle<io::Result<()>>
+    where
+        F: FnMut(FontChange) -> bool + Send + 'static,
+    {
+        thread::spawn(move || {
+            while callback(self.wait()?) {}
+            Ok(())
+        })
+    }
+
+    /// Record the path affected by an event.
+    fn handle_event(&mut self, event: &libc::inotify_event, name: &OsStr, change: &mut FontChange) {
+        // Events were dropped, so anything might have changed.
+        if event.mask & libc::IN_Q_OVERFLOW != 0 {
+            change.paths.extend(self.watches.values().cloned());
+            change.paths.sort_unstable();
+            change.paths.dedup();
+            return;
+        }
+
+        if let Some(parent) = self.parents.get(&event.wd).cloned() {
+            let created = event.mask & (libc::IN_CREATE | libc::IN_MOVED_TO) != 0;
+            if event.mask & libc::IN_IGNORED != 0 {
+                self.parents.remove(&event.wd);
+            } else if created && event.mask & libc::IN_ISDIR != 0 {
+                self.watch_created(&parent.join(name), change);
+            }
+        }
+
+        let dir = match self.watches.get(&event.wd) {
+            Some(dir) => dir.clone(),
+            None => return,
+        };
+
+        // The watched directory itself was removed.
+        if event.mask & libc::IN_IGNORED != 0 {
+            self.watches.remove(&event.wd);
+            push_unique(&mut change.paths, dir);
+            return;
+        }
+
+        let path = if name.is_empty() { dir } else { dir.join(name) };
+
+        let new_dir = event.mask & (libc::IN_CREATE | libc::IN_MOVED_TO) != 0;
+        if new_dir && event.mask & libc::IN_ISDIR != 0 {
+            if let Err(err) = self.watch(&path) {
+                debug!("Unable to watch {path:?}: {err}");
+            }
+        }
+
+        push_unique(&mut change.paths, path);
+    }
+}
+
+impl AsFd for FontWatcher {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.fd.as_fd()
+    }
+}
+
+impl AsRawFd for FontWatcher {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd.as_raw_fd()
+    }
+}
+
+fn push_unique(paths: &mut Vec<PathBuf>, path: PathBuf) {
+    if !paths.contains(&path) {
+        paths.push(path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn file_changes() {
+        let dir = std::env::temp_dir().join(format!("crossfont-watcher-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let config = fc::Config::empty().unwrap();
+        let mut watcher = FontWatcher::for_config(&config).unwrap();
+        watcher.watch(&dir).unwrap();
+        assert_eq!(watcher.poll().unwrap(), None);
+
+        let font = dir.join("font.ttf");
+        fs::write(&font, b"font").unwrap();
+        let subdir = dir.join("subdir");
+        fs::create_dir(&subdir).unwrap();
+
+        let change = watcher.poll().unwrap().unwrap();
+        assert_eq!(change.paths, vec![font.clone(), subdir.clone()]);
+
+        // New subdirectories are watched too.
+        let nested = subdir.join("nested.otf");
+        fs::write(&nested, b"font").unwrap();
+        fs::remove_file(&font).unwrap();
+        assert_eq!(watcher.wait().unwrap().paths, vec![nested, font]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn missing_directory() {
+        let dir = std::env::temp_dir().join(format!("crossfont-missing-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let config = fc::Config::empty().unwrap();
+        let mut watcher = FontWatcher::for_config(&config).unwrap();
+        let fonts = dir.join("share").join("fonts");
+        watcher.watch(&fonts).unwrap();
+
+        // Other changes to the parent are ignored.
+        fs::write(dir.join("unrelated"), b"file").unwrap();
+        assert_eq!(watcher.poll().unwrap(), None);
+
+        fs::create_dir_all(&fonts).unwrap();
+        assert_eq!(watcher.wait().unwrap().paths, vec![fonts.clone()]);
+
+        // The directory is watched once it exists.
+        let font = fonts.join("font.ttf");
+        fs::write(&font, b"font").unwrap();
+        assert_eq!(watcher.wait().unwrap().paths, vec![font]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -6,7 +6,10 @@
 
 #![deny(clippy::all, clippy::if_not_else, clippy::enum_glob_use)]
 
+use std::collections::BTreeMap;
 use std::fmt::{self, Display, Formatter};
+use std::io;
+use std::path::PathBuf;
 use std::sync::atomic::{AtomicUsize, Ordering};
 
 #[cfg(not(any(target_os = "macos", windows)))]
@@ -24,35 +27,54 @@ pub mod darwin;
 #[cfg(target_os = "macos")]
 pub use darwin::CoreTextRasterizer as Rasterizer;
 
+mod parse;
+pub use parse::{CssFont, FontconfigName, PangoDescription, ParseFontError};
+
 /// Max font size in pt.
 ///
 /// The value is picked based on `u32` max, since we use 6 digits for fract.
 const MAX_FONT_PT_SIZE: f32 = 3999.;
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FontDesc {
     name: String,
     style: Style,
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Vec::is_empty"))]
+    languages: Vec<String>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Slant {
+    #[default]
     Normal,
     Italic,
     Oblique,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Weight {
+    #[default]
     Normal,
     Bold,
 }
 
 /// Style of font.
+///
+/// With the `serde` feature, a specific style is represented as a string, while a description is
+/// a map where both fields are optional.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(untagged))]
 pub enum Style {
     Specific(String),
-    Description { slant: Slant, weight: Weight },
+    Description {
+        #[cfg_attr(feature = "serde", serde(default))]
+        slant: Slant,
+        #[cfg_attr(feature = "serde", serde(default))]
+        weight: Weight,
+    },
 }
 
 impl fmt::Display for Style {
@@ -71,7 +93,20 @@ impl FontDesc {
     where
         S: Into<String>,
     {
-        FontDesc { name: name.into(), style }
+        FontDesc { name: name.into(), style, languages: Vec::new() }
+    }
+
+    /// Prefer fallback fonts supporting the languages, in order of preference.
+    ///
+    /// Languages are BCP 47 tags or POSIX locales, like `ja`, `zh-TW` or `zh_CN.UTF-8`. Han
+    /// characters missing from the font will use the glyph forms of the first supported language.
+    pub fn with_languages<I, S>(mut self, languages: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.languages = languages.into_iter().map(Into::into).collect();
+        self
     }
 }
 
@@ -105,10 +140,111 @@ pub struct GlyphKey {
     pub size: Size,
 }
 
+/// Grapheme cluster rasterized as a single glyph, like an emoji ZWJ sequence, a flag, or a
+/// character followed by a variation selector.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct ClusterKey {
+    pub text: String,
+    pub font_key: FontKey,
+    pub size: Size,
+}
+
+/// Space available to a glyph in a grid of cells.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct CellBox {
+    /// Number of cells the glyph may cover.
+    pub cells: u32,
+    /// Width of a single cell in pixels.
+    pub cell_width: i32,
+    /// Height of a cell in pixels.
+    pub cell_height: i32,
+    /// Distance from the bottom of the cell to the baseline in pixels.
+    pub baseline: i32,
+}
+
+impl CellBox {
+    /// Width of all cells in pixels.
+    fn width(&self) -> i32 {
+        self.cells as i32 * self.cell_width
+    }
+
+    /// Get the factor which scales a glyph down to fit into the box.
+    ///
+    /// Returns `None` if the glyph fits already.
+    fn fit_scale(&self, glyph: &RasterizedGlyph) -> Option<f64> {
+        let width = self.width();
+        if width <= 0 || self.cell_height <= 0 {
+            return None;
+        }
+
+        if glyph.width <= width && glyph.height <= self.cell_height {
+            return None;
+        }
+
+        let width_scale = f64::from(width) / f64::from(glyph.width);
+        let height_scale = f64::from(self.cell_height) / f64::from(glyph.height);
+        Some(width_scale.min(height_scale))
+    }
+
+    /// Center a glyph horizontally inside the box.
+    ///
+    /// The glyph keeps its position relative to the baseline, unless it would extend beyond the
+    /// top or bottom of the box.
+    fn place(&self, mut glyph: RasterizedGlyph) -> RasterizedGlyph {
+        glyph.left = (self.width() - glyph.width) / 2;
+
+        let top = self.cell_height - self.baseline;
+        let bottom = -self.baseline;
+        glyph.top = glyph.top.min(top).max(bottom + glyph.height);
+
+        glyph.advance.0 = glyph.advance.0.min(self.width());
+
+        glyph
+    }
+}
+
+/// Scale a glyph down to fit into the box, by rasterizing it at smaller sizes.
+fn fit_by_size<R: Rasterize + ?Sized>(
+    rasterizer: &mut R,
+    glyph_key: GlyphKey,
+    mut glyph: RasterizedGlyph,
+    cell_box: CellBox,
+) -> Result<RasterizedGlyph, Error> {
+    // Glyphs don't scale exactly with the font size, so try again if it's still too big.
+    let mut size = glyph_key.size;
+    for _ in 0..3 {
+        let scale = match cell_box.fit_scale(&glyph) {
+            Some(scale) => scale,
+            None => break,
+        };
+
+        size = size.scale(scale as f32);
+        glyph = rasterizer.get_glyph(GlyphKey { size, ..glyph_key })?;
+    }
+
+    Ok(cell_box.place(glyph))
+}
+
 /// Font size stored as base and fraction.
+///
+/// With the `serde` feature, the size is represented in points.
 #[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Size(u32);
 
+#[cfg(feature = "serde")]
+impl serde::Serialize for Size {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f32(self.as_pt())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Size {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        f32::deserialize(deserializer).map(Size::new)
+    }
+}
+
 impl Size {
     /// Create a new `Size` from a f32 size in points.
     ///
@@ -149,6 +285,7 @@ impl Size {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct RasterizedGlyph {
     pub character: char,
     pub width: i32,
@@ -160,6 +297,7 @@ pub struct RasterizedGlyph {
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum BitmapBuffer {
     /// RGB alphamask.
     Rgb(Vec<u8>),
@@ -183,18 +321,130 @@ impl Default for RasterizedGlyph {
 }
 
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Metrics {
     pub average_advance: f64,
+    /// Largest horizontal advance of all glyphs in the font.
+    pub max_advance: f64,
     pub line_height: f64,
+    /// Distance from the baseline to the top of the line, excluding the line gap.
+    pub ascent: f32,
     pub descent: f32,
+    /// Additional spacing between lines, which is part of `line_height`.
+    pub line_gap: f32,
+    /// Height of uppercase letters above the baseline.
+    ///
+    /// Falls back to the height of the `H` glyph, or the ascent, when the font doesn't specify it.
+    pub cap_height: f32,
+    /// Height of lowercase letters above the baseline.
+    ///
+    /// Falls back to the height of the `x` glyph, or half the ascent, when the font doesn't
+    /// specify it.
+    pub x_height: f32,
     pub underline_position: f32,
     pub underline_thickness: f32,
     pub strikeout_position: f32,
     pub strikeout_thickness: f32,
 }
 
+/// Installed font family.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FamilyInfo {
+    pub name: String,
+    pub faces: Vec<FaceInfo>,
+}
+
+/// Installed font face of a family.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FaceInfo {
+    /// Name of the face's style, like `Bold Italic`.
+    pub style: String,
+    /// Weight on the OpenType scale, where 400 is regular and 700 is bold.
+    pub weight: u16,
+    pub slant: Slant,
+    /// Width as a percentage of the normal width.
+    pub width: u16,
+    pub monospace: bool,
+    pub scalable: bool,
+    pub color: bool,
+    pub path: Option<PathBuf>,
+}
+
+/// Filter for font enumeration.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct FontFilter {
+    /// Only list monospace fonts.
+    pub monospace: bool,
+    /// Only list fonts supporting the language, as an RFC 3066 tag like `en` or `zh-tw`.
+    pub language: Option<String>,
+}
+
+/// Group faces by their family, sorted by name.
+fn group_families(faces: impl IntoIterator<Item = (String, FaceInfo)>) -> Vec<FamilyInfo> {
+    let mut families = BTreeMap::<_, Vec<_>>::new();
+    for (family, face) in faces {
+        families.entry(family).or_default().push(face);
+    }
+
+    let mut families: Vec<_> = families
+        .into_iter()
+        .map(|(name, mut faces)| {
+            faces.sort_by(|a, b| {
+                let slant = |face: &FaceInfo| face.slant as u8;
+                (a.width, a.weight, slant(a), &a.style).cmp(&(
+                    b.width,
+                    b.weight,
+                    slant(b),
+                    &b.style,
+                ))
+            });
+            faces.dedup();
+            FamilyInfo { name, faces }
+        })
+        .collect();
+
+    families.sort_by_cached_key(|family| family.name.to_lowercase());
+
+    families
+}
+
+/// Convert a POSIX locale like `zh_TW.UTF-8` to a BCP 47 language tag like `zh-TW`.
+#[cfg(any(target_os = "macos", windows))]
+fn bcp47_language(locale: &str) -> String {
+    let language = locale.split(['.', '@']).next().unwrap_or_default();
+    language.replace('_', "-")
+}
+
+/// Check if a supported language tag covers the requested language.
+///
+/// Both tags are compared case-insensitively, with `zh` covering `zh-Hant` and `zh_TW`.
+#[cfg(any(target_os = "macos", windows))]
+fn language_matches(supported: &str, requested: &str) -> bool {
+    let normalize = |tag: &str| tag.replace('_', "-").to_lowercase();
+    let (supported, requested) = (normalize(supported), normalize(requested));
+    supported == requested || supported.starts_with(&format!("{requested}-"))
+}
+
+/// Font face within a font file.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FaceLocation {
+    pub path: PathBuf,
+    /// Index of the face within a font collection.
+    pub index: isize,
+}
+
+impl Display for FaceLocation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.index {
+            0 => write!(f, "{:?}", self.path),
+            index => write!(f, "{:?} (face {index})", self.path),
+        }
+    }
+}
+
 /// Errors occuring when using the rasterizer.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum Error {
     /// Unable to find a font matching the description.
     FontNotFound(FontDesc),
@@ -203,18 +453,38 @@ pub enum Error {
     MetricsNotFound,
 
     /// The glyph could not be found in any font.
-    MissingGlyph(RasterizedGlyph),
+    MissingGlyph {
+        /// Glyph of the primary font used to represent the missing character.
+        glyph: RasterizedGlyph,
+        /// Families of all fonts which were searched for the character.
+        tried: Vec<String>,
+    },
 
     /// Requested an operation with a FontKey that isn't known to the rasterizer.
     UnknownFontKey,
 
+    /// Error from FreeType, with the face it occurred for.
+    #[cfg(not(any(target_os = "macos", windows)))]
+    FreeType { source: freetype::Error, face: Option<FaceLocation> },
+
+    /// Unable to read a font file.
+    Io { source: io::Error, path: PathBuf },
+
+    /// The font or one of its glyphs is stored in a format which can't be rendered.
+    UnsupportedFormat { reason: String, face: Option<FaceLocation> },
+
     /// Error from platfrom's font system.
     PlatformError(String),
 }
 
 impl std::error::Error for Error {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
-        None
+        match self {
+            #[cfg(not(any(target_os = "macos", windows)))]
+            Error::FreeType { source, .. } => Some(source),
+            Error::Io { source, .. } => Some(source),
+            _ => None,
+        }
     }
 }
 
@@ -222,11 +492,22 @@ impl Display for Error {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
             Error::FontNotFound(font) => write!(f, "font {font:?} not found"),
-            Error::MissingGlyph(glyph) => {
-                write!(f, "glyph for character {:?} not found", glyph.character)
+            Error::MissingGlyph { glyph, tried } => {
+                write!(f, "glyph for character {:?} not found in {tried:?}", glyph.character)
             },
             Error::UnknownFontKey => f.write_str("invalid font key"),
             Error::MetricsNotFound => f.write_str("metrics not found"),
+            #[cfg(not(any(target_os = "macos", windows)))]
+            Error::FreeType { face: Some(face), .. } => write!(f, "FreeType error for font {face}"),
+            #[cfg(not(any(target_os = "macos", windows)))]
+            Error::FreeType { face: None, .. } => f.write_str("FreeType error"),
+            Error::Io { path, .. } => write!(f, "unable to read font {path:?}"),
+            Error::UnsupportedFormat { reason, face: Some(face) } => {
+                write!(f, "unsupported format in font {face}: {reason}")
+            },
+            Error::UnsupportedFormat { reason, face: None } => {
+                write!(f, "unsupported format: {reason}")
+            },
             Error::PlatformError(err) => write!(f, "{err}"),
         }
     }
@@ -247,6 +528,104 @@ pub trait Rasterize {
     /// Rasterize the glyph described by `GlyphKey`..
     fn get_glyph(&mut self, _: GlyphKey) -> Result<RasterizedGlyph, Error>;
 
+    /// Rasterize the grapheme cluster described by `ClusterKey` as a single glyph.
+    ///
+    /// The glyph's `character` is the first character of the cluster. Rasterizers without support
+    /// for ligatures only render the first character, which is also used when no font has a
+    /// ligature for the whole cluster.
+    fn get_cluster(&mut self, cluster_key: &ClusterKey) -> Result<RasterizedGlyph, Error> {
+        match cluster_key.text.chars().next() {
+            Some(character) => self.get_glyph(GlyphKey {
+                character,
+                font_key: cluster_key.font_key,
+                size: cluster_key.size,
+            }),
+            None => Ok(RasterizedGlyph::default()),
+        }
+    }
+
+    /// Rasterize the glyph described by `GlyphKey`, scaled down to fit into the cell box.
+    ///
+    /// Glyphs which overflow the box keep their aspect ratio and are centered horizontally, while
+    /// all other glyphs are returned unchanged.
+    fn get_fitted_glyph(
+        &mut self,
+        glyph_key: GlyphKey,
+        cell_box: CellBox,
+    ) -> Result<RasterizedGlyph, Error> {
+        let glyph = self.get_glyph(glyph_key)?;
+        if cell_box.fit_scale(&glyph).is_none() {
+            return Ok(glyph);
+        }
+
+        fit_by_size(self, glyph_key, glyph, cell_box)
+    }
+
     /// Kerning between two characters.
     fn kerning(&mut self, left: GlyphKey, right: GlyphKey) -> (f32, f32);
+
+    /// Reload the system's font configuration.
+    ///
+    /// Returns `true` if the configuration changed, in which case fonts should be loaded again to
+    /// pick up newly installed fonts. Previously loaded `FontKey`s remain valid.
+    ///
+    /// Rasterizers which can't detect changes never report any.
+    fn reload(&mut self) -> Result<bool, Error> {
+        Ok(false)
+    }
+
+    /// List all installed font families matching the filter.
+    ///
+    /// Rasterizers which can't enumerate fonts return no families.
+    fn font_families(&self, _filter: &FontFilter) -> Result<Vec<FamilyInfo>, Error> {
+        Ok(Vec::new())
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serde_size_in_points() {
+        assert_eq!(serde_json::to_string(&Size::new(12.5)).unwrap(), "12.5");
+        assert_eq!(serde_json::from_str::<Size>("13").unwrap(), Size::new(13.));
+    }
+
+    #[test]
+    fn serde_style() {
+        let style: Style = serde_json::from_str("\"Bold Italic\"").unwrap();
+        assert_eq!(style, Style::Specific("Bold Italic".into()));
+
+        let style: Style = serde_json::from_str("{\"weight\": \"Bold\"}").unwrap();
+        assert_eq!(style, Style::Description { slant: Slant::Normal, weight: Weight::Bold });
+
+        let desc = FontDesc::new("monospace", Style::Description {
+            slant: Slant::Italic,
+            weight: Weight::Normal,
+        });
+        let json = serde_json::to_string(&desc).unwrap();
+        assert_eq!(
+            json,
+            "{\"name\":\"monospace\",\"style\":{\"slant\":\"Italic\",\"weight\":\"Normal\"}}"
+        );
+        assert_eq!(serde_json::from_str::<FontDesc>(&json).unwrap(), desc);
+    }
+
+    #[test]
+    fn serde_glyph() {
+        let glyph = RasterizedGlyph {
+            character: 'a',
+            width: 1,
+            height: 1,
+            buffer: BitmapBuffer::Rgba(vec![1, 2, 3, 4]),
+            ..Default::default()
+        };
+        let json = serde_json::to_string(&glyph).unwrap();
+        let deserialized: RasterizedGlyph = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized.character, 'a');
+        assert!(
+            matches!(deserialized.buffer, BitmapBuffer::Rgba(buffer) if buffer == [1, 2, 3, 4])
+        );
+    }
 }
@@ -0,0 +1,780 @@
+//! Parsers for font descriptions typed by users.
+
+use std::error;
+use std::fmt::{self, Display, Formatter, Write};
+use std::str::FromStr;
+
+use crate::{FontDesc, Size, Slant, Style, Weight};
+
+/// Style keywords understood by all formats, with their canonical name.
+///
+/// Keywords are matched case-insensitively, ignoring `-` and `_`.
+const KEYWORDS: &[(&str, Keyword)] = &[
+    ("thin", Keyword { name: "Thin", kind: KeywordKind::Weight(100) }),
+    ("extralight", Keyword { name: "ExtraLight", kind: KeywordKind::Weight(200) }),
+    ("ultralight", Keyword { name: "ExtraLight", kind: KeywordKind::Weight(200) }),
+    ("light", Keyword { name: "Light", kind: KeywordKind::Weight(300) }),
+    ("semilight", Keyword { name: "SemiLight", kind: KeywordKind::Weight(350) }),
+    ("demilight", Keyword { name: "SemiLight", kind: KeywordKind::Weight(350) }),
+    ("book", Keyword { name: "Book", kind: KeywordKind::Weight(380) }),
+    ("regular", Keyword { name: "Regular", kind: KeywordKind::Weight(400) }),
+    ("normal", Keyword { name: "Regular", kind: KeywordKind::Weight(400) }),
+    ("medium", Keyword { name: "Medium", kind: KeywordKind::Weight(500) }),
+    ("semibold", Keyword { name: "SemiBold", kind: KeywordKind::Weight(600) }),
+    ("demibold", Keyword { name: "SemiBold", kind: KeywordKind::Weight(600) }),
+    ("bold", Keyword { name: "Bold", kind: KeywordKind::Weight(700) }),
+    ("extrabold", Keyword { name: "ExtraBold", kind: KeywordKind::Weight(800) }),
+    ("ultrabold", Keyword { name: "ExtraBold", kind: KeywordKind::Weight(800) }),
+    ("black", Keyword { name: "Black", kind: KeywordKind::Weight(900) }),
+    ("heavy", Keyword { name: "Black", kind: KeywordKind::Weight(900) }),
+    ("extrablack", Keyword { name: "ExtraBlack", kind: KeywordKind::Weight(1000) }),
+    ("ultrablack", Keyword { name: "ExtraBlack", kind: KeywordKind::Weight(1000) }),
+    ("roman", Keyword { name: "Roman", kind: KeywordKind::Slant(Slant::Normal) }),
+    ("italic", Keyword { name: "Italic", kind: KeywordKind::Slant(Slant::Italic) }),
+    ("oblique", Keyword { name: "Oblique", kind: KeywordKind::Slant(Slant::Oblique) }),
+    ("ultracondensed", Keyword { name: "UltraCondensed", kind: KeywordKind::Stretch(50) }),
+    ("extracondensed", Keyword { name: "ExtraCondensed", kind: KeywordKind::Stretch(63) }),
+    ("condensed", Keyword { name: "Condensed", kind: KeywordKind::Stretch(75) }),
+    ("semicondensed", Keyword { name: "SemiCondensed", kind: KeywordKind::Stretch(87) }),
+    ("semiexpanded", Keyword { name: "SemiExpanded", kind: KeywordKind::Stretch(113) }),
+    ("expanded", Keyword { name: "Expanded", kind: KeywordKind::Stretch(125) }),
+    ("extraexpanded", Keyword { name: "ExtraExpanded", kind: KeywordKind::Stretch(150) }),
+    ("ultraexpanded", Keyword { name: "UltraExpanded", kind: KeywordKind::Stretch(200) }),
+];
+
+/// Fontconfig weights, mapped to the weight on the CSS scale.
+const FONTCONFIG_WEIGHTS: &[(u16, u16)] = &[
+    (0, 100),
+    (40, 200),
+    (50, 300),
+    (55, 350),
+    (75, 380),
+    (80, 400),
+    (100, 500),
+    (180, 600),
+    (200, 700),
+    (205, 800),
+    (210, 900),
+    (215, 1000),
+];
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct Keyword {
+    name: &'static str,
+    kind: KeywordKind,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum KeywordKind {
+    /// Weight on the CSS scale.
+    Weight(u16),
+    Slant(Slant),
+    /// Width as a percentage of the normal width.
+    Stretch(u16),
+}
+
+impl Keyword {
+    fn find(word: &str) -> Option<Self> {
+        let word: String =
+            word.chars().filter(|c| *c != '-' && *c != '_').collect::<String>().to_lowercase();
+        KEYWORDS.iter().find(|(key, _)| *key == word).map(|(_, keyword)| *keyword)
+    }
+
+    fn find_kind(kind: KeywordKind) -> Option<Self> {
+        KEYWORDS.iter().map(|(_, keyword)| *keyword).find(|keyword| keyword.kind == kind)
+    }
+
+    /// Check if the keyword doesn't change the style.
+    fn is_default(self) -> bool {
+        matches!(self.kind, KeywordKind::Weight(400) | KeywordKind::Slant(Slant::Normal))
+    }
+}
+
+/// Convert style keywords to a `Style`.
+///
+/// Keywords which can't be represented by `Style::Description` produce a `Style::Specific`.
+fn style_from_keywords(keywords: &[Keyword]) -> Style {
+    let mut slant = Slant::Normal;
+    let mut weight = Weight::Normal;
+    let mut described = true;
+
+    for keyword in keywords {
+        match keyword.kind {
+            KeywordKind::Slant(keyword_slant) => slant = keyword_slant,
+            KeywordKind::Weight(400) => weight = Weight::Normal,
+            KeywordKind::Weight(700) => weight = Weight::Bold,
+            _ => described = false,
+        }
+    }
+
+    if described {
+        return Style::Description { slant, weight };
+    }
+
+    let names: Vec<_> = keywords
+        .iter()
+        .filter(|keyword| !keyword.is_default())
+        .map(|keyword| keyword.name)
+        .collect();
+    Style::Specific(names.join(" "))
+}
+
+/// Style keywords describing a style.
+///
+/// Words of specific styles which aren't keywords are dropped, since formats without style names
+/// can't tell them apart from the family.
+fn style_words(style: &Style) -> Vec<&str> {
+    match style {
+        Style::Specific(style) => {
+            style.split_whitespace().filter(|word| Keyword::find(word).is_some()).collect()
+        },
+        Style::Description { slant, weight } => {
+            let weight = match weight {
+                Weight::Bold => Some("Bold"),
+                Weight::Normal => None,
+            };
+            let slant = match slant {
+                Slant::Italic => Some("Italic"),
+                Slant::Oblique => Some("Oblique"),
+                Slant::Normal => None,
+            };
+            weight.into_iter().chain(slant).collect()
+        },
+    }
+}
+
+/// Parse a positive size.
+fn parse_size(size: &str) -> Result<f32, ParseFontError> {
+    match size.trim().parse::<f32>() {
+        Ok(value) if value.is_finite() && value > 0. => Ok(value),
+        _ => Err(ParseFontError::InvalidSize(size.into())),
+    }
+}
+
+/// Split a number from its unit.
+fn split_unit(value: &str) -> (&str, &str) {
+    let end = value.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(value.len());
+    value.split_at(end)
+}
+
+/// Error when parsing a font description.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ParseFontError {
+    /// The description doesn't contain a font family.
+    MissingFamily,
+
+    /// The CSS shorthand doesn't contain a font size.
+    MissingSize,
+
+    /// A size which is not a positive number.
+    InvalidSize(String),
+
+    /// A size with a unit other than `pt` or `px`.
+    UnsupportedUnit(String),
+
+    /// Unknown property of a Fontconfig name.
+    UnknownProperty(String),
+
+    /// Property value which can't be used for a font description.
+    InvalidValue { property: String, value: String },
+
+    /// Word which is not a known style keyword.
+    UnknownKeyword(String),
+
+    /// Quoted family without a closing quote.
+    UnterminatedQuote,
+
+    /// A style name was combined with a weight, slant, or width.
+    ConflictingStyle,
+}
+
+impl error::Error for ParseFontError {}
+
+impl Display for ParseFontError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseFontError::MissingFamily => f.write_str("missing font family"),
+            ParseFontError::MissingSize => f.write_str("missing font size"),
+            ParseFontError::InvalidSize(size) => write!(f, "invalid font size {size:?}"),
+            ParseFontError::UnsupportedUnit(unit) => {
+                write!(f, "unsupported font size unit {unit:?}, expected pt or px")
+            },
+            ParseFontError::UnknownProperty(property) => {
+                write!(f, "unknown font property {property:?}")
+            },
+            ParseFontError::InvalidValue { property, value } => {
+                write!(f, "invalid value {value:?} for {property}")
+            },
+            ParseFontError::UnknownKeyword(keyword) => {
+                write!(f, "unknown font style keyword {keyword:?}")
+            },
+            ParseFontError::UnterminatedQuote => f.write_str("unterminated quote in font family"),
+            ParseFontError::ConflictingStyle => {
+                f.write_str("style name can't be combined with weight, slant, or width")
+            },
+        }
+    }
+}
+
+/// Font in Fontconfig's name syntax, like `Fira Code-12:style=Bold`.
+///
+/// Supported properties are `family`, `style`, `size`, `pixelsize`, `weight`, `slant`, `width`,
+/// and `lang`, together with style constants like `:bold`. Only the first family is used.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FontconfigName {
+    pub desc: FontDesc,
+    pub size: Option<Size>,
+}
+
+impl FromStr for FontconfigName {
+    type Err = ParseFontError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        let mut sections = split_unescaped(name, ':').into_iter();
+        let head = split_unescaped(sections.next().unwrap_or_default(), '-');
+
+        let mut family = split_unescaped(head[0], ',')
+            .into_iter()
+            .map(|family| unescape(family).trim().to_owned())
+            .find(|family| !family.is_empty());
+
+        let mut size = match head.get(1) {
+            Some(sizes) => Some(Size::new(parse_size(split_unescaped(sizes, ',')[0])?)),
+            None => None,
+        };
+
+        let mut style = None;
+        let mut keywords = Vec::new();
+        let mut languages = Vec::new();
+        for section in sections {
+            let (property, value) = match section.split_once('=') {
+                Some((property, value)) => (property.trim(), unescape(value)),
+                None => {
+                    let keyword = Keyword::find(section.trim())
+                        .ok_or_else(|| ParseFontError::UnknownKeyword(section.into()))?;
+                    keywords.push(keyword);
+                    continue;
+                },
+            };
+
+            let invalid_value =
+                || ParseFontError::InvalidValue { property: property.into(), value: value.clone() };
+
+            match property {
+                "family" => {
+                    family = family.or_else(|| Some(value.trim().to_owned()));
+                },
+                "style" => style = Some(value.clone()),
+                "lang" => languages.push(value.trim().to_owned()),
+                "size" => size = Some(Size::new(parse_size(&value)?)),
+                "pixelsize" => size = Some(Size::from_px(parse_size(&value)?)),
+                "weight" => {
+                    let keyword = match value.trim().parse::<u16>() {
+                        Ok(weight) => FONTCONFIG_WEIGHTS
+                            .iter()
+                            .find(|(fc_weight, _)| *fc_weight == weight)
+                            .and_then(|(_, weight)| {
+                                Keyword::find_kind(KeywordKind::Weight(*weight))
+                            }),
+                        Err(_) => Keyword::find(&value)
+                            .filter(|keyword| matches!(keyword.kind, KeywordKind::Weight(_))),
+                    };
+                    keywords.push(keyword.ok_or_else(invalid_value)?);
+                },
+                "slant" => {
+                    let slant = match value.trim() {
+                        "0" => Some(Slant::Normal),
+                        "100" => Some(Slant::Italic),
+                        "110" => Some(Slant::Oblique),
+                        value => Keyword::find(value).and_then(|keyword| match keyword.kind {
+                            KeywordKind::Slant(slant) => Some(slant),
+                            _ => None,
+                        }),
+                    };
+                    let keyword =
+                        slant.and_then(|slant| Keyword::find_kind(KeywordKind::Slant(slant)));
+                    keywords.push(keyword.ok_or_else(invalid_value)?);
+                },
+                "width" => {
+                    let keyword = match value.trim().parse::<u16>() {
+                        Ok(100) => Keyword::find("normal"),
+                        Ok(width) => Keyword::find_kind(KeywordKind::Stretch(width)),
+                        Err(_) => Keyword::find(&value)
+                            .filter(|keyword| matches!(keyword.kind, KeywordKind::Stretch(_))),
+                    };
+                    keywords.push(keyword.ok_or_else(invalid_value)?);
+                },
+                _ => return Err(ParseFontError::UnknownProperty(property.into())),
+            }
+        }
+
+        let style = match style {
+            Some(_) if !keywords.is_empty() => return Err(ParseFontError::ConflictingStyle),
+            Some(style) => Style::Specific(style),
+            None => style_from_keywords(&keywords),
+        };
+
+        let family = family.ok_or(ParseFontError::MissingFamily)?;
+        let desc = FontDesc::new(family, style).with_languages(languages);
+        Ok(Self { desc, size })
+    }
+}
+
+impl Display for FontconfigName {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&escape(&self.desc.name, "\\-:,"))?;
+
+        if let Some(size) = self.size {
+            write!(f, "-{}", size.as_pt())?;
+        }
+
+        match &self.desc.style {
+            Style::Specific(style) => write!(f, ":style={}", escape(style, "\\:"))?,
+            Style::Description { slant, weight } => {
+                if *weight == Weight::Bold {
+                    f.write_str(":weight=bold")?;
+                }
+
+                match slant {
+                    Slant::Italic => f.write_str(":slant=italic")?,
+                    Slant::Oblique => f.write_str(":slant=oblique")?,
+                    Slant::Normal => (),
+                }
+            },
+        }
+
+        for language in &self.desc.languages {
+            write!(f, ":lang={}", escape(language, "\\:"))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Split at unescaped occurrences of `delimiter`, keeping all escapes.
+fn split_unescaped(text: &str, delimiter: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut escaped = false;
+    let mut start = 0;
+
+    for (i, c) in text.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == delimiter {
+            parts.push(&text[start..i]);
+            start = i + c.len_utf8();
+        }
+    }
+    parts.push(&text[start..]);
+
+    parts
+}
+
+/// Remove backslash escapes.
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unescaped.extend(chars.next()),
+            c => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+/// Escape all `special` characters with a backslash.
+fn escape(text: &str, special: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if special.contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Font in Pango's description syntax, like `JetBrains Mono Italic 13`.
+///
+/// Style keywords and the size are read from the end of the description, everything before them
+/// is the family. Families ending in a style keyword or a number can be terminated with a comma,
+/// like `Fira Sans Book, 12` or `3270,`. Sizes are in points, unless they are suffixed with `px`.
+///
+/// Only styles made of keywords can be represented. Other words of [`Style::Specific`] are left
+/// out when formatting, and styles described by slant and regular or bold weight are parsed as
+/// [`Style::Description`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PangoDescription {
+    pub desc: FontDesc,
+    pub size: Option<Size>,
+}
+
+impl FromStr for PangoDescription {
+    type Err = ParseFontError;
+
+    fn from_str(description: &str) -> Result<Self, Self::Err> {
+        let mut words: Vec<_> = description.split_whitespace().collect();
+
+        let mut size = None;
+        if let Some(word) = words
+            .last()
+            .filter(|word| word.starts_with(|c: char| c.is_ascii_digit()) && !word.ends_with(','))
+        {
+            size = Some(match split_unit(word) {
+                (number, "") => Size::new(parse_size(number)?),
+                (number, "px") => Size::from_px(parse_size(number)?),
+                (_, unit) => return Err(ParseFontError::UnsupportedUnit(unit.into())),
+            });
+            words.pop();
+        }
+
+        let mut keywords = Vec::new();
+        while let Some(keyword) = words.last().and_then(|word| Keyword::find(word)) {
+            keywords.insert(0, keyword);
+            words.pop();
+        }
+
+        let families = words.join(" ");
+        let family = families.split(',').map(str::trim).find(|family| !family.is_empty());
+        let family = family.ok_or(ParseFontError::MissingFamily)?;
+
+        Ok(Self { desc: FontDesc::new(family, style_from_keywords(&keywords)), size })
+    }
+}
+
+impl Display for PangoDescription {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.desc.name)?;
+
+        // Separate families ending in a keyword or number from the style and size.
+        let last_word = self.desc.name.split_whitespace().last();
+        if last_word.is_some_and(|word| {
+            Keyword::find(word).is_some() || word.starts_with(|c: char| c.is_ascii_digit())
+        }) {
+            f.write_char(',')?;
+        }
+
+        for word in style_words(&self.desc.style) {
+            write!(f, " {word}")?;
+        }
+
+        if let Some(size) = self.size {
+            write!(f, " {}", size.as_pt())?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Font in CSS shorthand syntax, like `italic bold 12pt "Fira Code", monospace`.
+///
+/// Sizes must be in `pt` or `px`, an optional line height is ignored. Only the first family is
+/// used.
+///
+/// Like with [`PangoDescription`], words of [`Style::Specific`] which aren't keywords are left
+/// out when formatting.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CssFont {
+    pub desc: FontDesc,
+    pub size: Size,
+}
+
+impl FromStr for CssFont {
+    type Err = ParseFontError;
+
+    fn from_str(shorthand: &str) -> Result<Self, Self::Err> {
+        let mut rest = shorthand.trim_start();
+        let mut keywords = Vec::new();
+
+        let size = loop {
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            let token = &rest[..end];
+            if token.is_empty() {
+                return Err(ParseFontError::MissingSize);
+            }
+            rest = rest[end..].trim_start();
+
+            if token.starts_with(|c: char| c.is_ascii_digit() || c == '.') {
+                // Ignore the line height.
+                let size = token.split('/').next().unwrap_or_default();
+                match split_unit(size) {
+                    (number, "pt") => break Size::new(parse_size(number)?),
+                    (number, "px") => break Size::from_px(parse_size(number)?),
+                    (weight, "") => {
+                        let keyword = weight
+                            .parse()
+                            .ok()
+                            .and_then(|weight| Keyword::find_kind(KeywordKind::Weight(weight)));
+                        keywords.push(keyword.ok_or_else(|| ParseFontError::InvalidValue {
+                            property: "font-weight".into(),
+                            value: weight.into(),
+                        })?);
+                    },
+                    (_, unit) => return Err(ParseFontError::UnsupportedUnit(unit.into())),
+                }
+            } else if let Some(keyword) = Keyword::find(token) {
+                keywords.push(keyword);
+            } else if rest
+                .split_whitespace()
+                .any(|token| token.starts_with(|c: char| c.is_ascii_digit()))
+            {
+                return Err(ParseFontError::UnknownKeyword(token.into()));
+            } else {
+                return Err(ParseFontError::MissingSize);
+            }
+        };
+
+        let family = css_family(rest)?.ok_or(ParseFontError::MissingFamily)?;
+
+        Ok(Self { desc: FontDesc::new(family, style_from_keywords(&keywords)), size })
+    }
+}
+
+impl Display for CssFont {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for word in style_words(&self.desc.style) {
+            match Keyword::find(word).map(|keyword| keyword.kind) {
+                Some(KeywordKind::Slant(_)) => write!(f, "{} ", word.to_lowercase())?,
+                Some(KeywordKind::Weight(700)) => f.write_str("bold ")?,
+                Some(KeywordKind::Weight(weight)) => write!(f, "{weight} ")?,
+                Some(KeywordKind::Stretch(_)) => write!(f, "{} ", css_stretch(word))?,
+                None => (),
+            }
+        }
+
+        write!(f, "{}pt ", self.size.as_pt())?;
+
+        let name = &self.desc.name;
+        if name.split_whitespace().all(is_css_identifier) {
+            f.write_str(name)
+        } else {
+            write!(f, "\"{}\"", escape(name, "\\\""))
+        }
+    }
+}
+
+/// Parse the first family of a CSS family list.
+fn css_family(families: &str) -> Result<Option<String>, ParseFontError> {
+    let families = families.trim_start();
+
+    let quote = match families.chars().next() {
+        Some(quote @ ('"' | '\'')) => quote,
+        _ => {
+            let family = families.split(',').next().unwrap_or_default();
+            let family = family.split_whitespace().collect::<Vec<_>>().join(" ");
+            return Ok(Some(family).filter(|family| !family.is_empty()));
+        },
+    };
+
+    let mut family = String::new();
+    let mut chars = families[1..].chars();
+    loop {
+        match chars.next() {
+            Some('\\') => family.extend(chars.next()),
+            Some(c) if c == quote => break,
+            Some(c) => family.push(c),
+            None => return Err(ParseFontError::UnterminatedQuote),
+        }
+    }
+
+    Ok(Some(family).filter(|family| !family.is_empty()))
+}
+
+/// Format a stretch keyword name like `UltraCondensed` as CSS keyword `ultra-condensed`.
+fn css_stretch(name: &str) -> String {
+    let mut keyword = String::with_capacity(name.len() + 1);
+    for (i, c) in name.char_indices() {
+        if i > 0 && c.is_uppercase() {
+            keyword.push('-');
+        }
+        keyword.extend(c.to_lowercase());
+    }
+    keyword
+}
+
+/// Check if a word can be used in an unquoted CSS family name.
+fn is_css_identifier(word: &str) -> bool {
+    let mut chars = word.chars();
+    let first = chars.next();
+    first.is_some_and(|c| c.is_alphabetic() || c == '_' || c == '-')
+        && word != "-"
+        && !word.starts_with("--")
+        && !(first == Some('-') && word[1..].starts_with(|c: char| c.is_ascii_digit()))
+        && chars.all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+        && Keyword::find(word).is_none()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn desc(name: &str, style: Style) -> FontDesc {
+        FontDesc::new(name, style)
+    }
+
+    fn described(slant: Slant, weight: Weight) -> Style {
+        Style::Description { slant, weight }
+    }
+
+    #[test]
+    fn fontconfig_name() {
+        let name: FontconfigName = "Fira Code:style=Bold:size=12".parse().unwrap();
+        assert_eq!(name.desc, desc("Fira Code", Style::Specific("Bold".into())));
+        assert_eq!(name.size, Some(Size::new(12.)));
+
+        let name: FontconfigName = "Noto\\-Sans,monospace-10.5:bold:slant=italic".parse().unwrap();
+        assert_eq!(name.desc, desc("Noto-Sans", described(Slant::Italic, Weight::Bold)));
+        assert_eq!(name.size, Some(Size::new(10.5)));
+
+        let name: FontconfigName = "Iosevka:weight=light:pixelsize=16".parse().unwrap();
+        assert_eq!(name.desc, desc("Iosevka", Style::Specific("Light".into())));
+        assert_eq!(name.size, Some(Size::from_px(16.)));
+
+        let name: FontconfigName = ":family=Hack:weight=200".parse().unwrap();
+        assert_eq!(name.desc, desc("Hack", described(Slant::Normal, Weight::Bold)));
+        assert_eq!(name.size, None);
+
+        let name: FontconfigName = "Sarasa Mono:lang=ja:lang=zh-tw".parse().unwrap();
+        let style = described(Slant::Normal, Weight::Normal);
+        assert_eq!(name.desc, desc("Sarasa Mono", style).with_languages(["ja", "zh-tw"]));
+    }
+
+    #[test]
+    fn fontconfig_name_errors() {
+        let parse = |name: &str| name.parse::<FontconfigName>().unwrap_err();
+        assert_eq!(parse(":size=12"), ParseFontError::MissingFamily);
+        assert_eq!(parse("Hack-big"), ParseFontError::InvalidSize("big".into()));
+        assert_eq!(
+            parse("Hack:antialias=true"),
+            ParseFontError::UnknownProperty("antialias".into())
+        );
+        assert_eq!(parse("Hack:shiny"), ParseFontError::UnknownKeyword("shiny".into()));
+        assert_eq!(parse("Hack:style=Bold:italic"), ParseFontError::ConflictingStyle);
+        assert_eq!(parse("Hack:weight=italic"), ParseFontError::InvalidValue {
+            property: "weight".into(),
+            value: "italic".into()
+        });
+    }
+
+    #[test]
+    fn pango_description() {
+        let font: PangoDescription = "JetBrains Mono Italic 13".parse().unwrap();
+        assert_eq!(font.desc, desc("JetBrains Mono", described(Slant::Italic, Weight::Normal)));
+        assert_eq!(font.size, Some(Size::new(13.)));
+
+        let font: PangoDescription = "Fira Sans Book, Semi-Bold Condensed 16px".parse().unwrap();
+        assert_eq!(font.desc, desc("Fira Sans Book", Style::Specific("SemiBold Condensed".into())));
+        assert_eq!(font.size, Some(Size::from_px(16.)));
+
+        let font: PangoDescription = "Hack".parse().unwrap();
+        assert_eq!(font.desc, desc("Hack", described(Slant::Normal, Weight::Normal)));
+        assert_eq!(font.size, None);
+
+        // Families ending in a number are separated from the size by a comma.
+        let font: PangoDescription = "Font 3270,".parse().unwrap();
+        assert_eq!(font.desc, desc("Font 3270", described(Slant::Normal, Weight::Normal)));
+        assert_eq!(font.size, None);
+
+        let font: PangoDescription = "3270, 12".parse().unwrap();
+        assert_eq!(font.desc, desc("3270", described(Slant::Normal, Weight::Normal)));
+        assert_eq!(font.size, Some(Size::new(12.)));
+    }
+
+    #[test]
+    fn pango_description_errors() {
+        let parse = |description: &str| description.parse::<PangoDescription>().unwrap_err();
+        assert_eq!(parse("Bold 12"), ParseFontError::MissingFamily);
+        assert_eq!(parse("Hack 12pt"), ParseFontError::UnsupportedUnit("pt".into()));
+        assert_eq!(parse("Hack 1.2.3"), ParseFontError::InvalidSize("1.2.3".into()));
+    }
+
+    #[test]
+    fn css_font() {
+        let font: CssFont = "bold 12pt Iosevka".parse().unwrap();
+        assert_eq!(font.desc, desc("Iosevka", described(Slant::Normal, Weight::Bold)));
+        assert_eq!(font.size, Size::new(12.));
+
+        let font: CssFont = "italic 300 16px/1.2 'Fira Code', monospace".parse().unwrap();
+        assert_eq!(font.desc, desc("Fira Code", Style::Specific("Italic Light".into())));
+        assert_eq!(font.size, Size::from_px(16.));
+
+        let font: CssFont = "normal ultra-condensed 9.5pt DejaVu   Sans Mono".parse().unwrap();
+        assert_eq!(font.desc, desc("DejaVu Sans Mono", Style::Specific("UltraCondensed".into())));
+    }
+
+    #[test]
+    fn css_font_errors() {
+        let parse = |shorthand: &str| shorthand.parse::<CssFont>().unwrap_err();
+        assert_eq!(parse("bold Iosevka"), ParseFontError::MissingSize);
+        assert_eq!(parse("bold 12pt"), ParseFontError::MissingFamily);
+        assert_eq!(parse("1.2em Iosevka"), ParseFontError::UnsupportedUnit("em".into()));
+        assert_eq!(parse("shiny 12pt Iosevka"), ParseFontError::UnknownKeyword("shiny".into()));
+        assert_eq!(parse("12pt \"Iosevka"), ParseFontError::UnterminatedQuote);
+        assert_eq!(parse("450 12pt Iosevka"), ParseFontError::InvalidValue {
+            property: "font-weight".into(),
+            value: "450".into()
+        });
+    }
+
+    #[test]
+    fn display_round_trip() {
+        let styles = [
+            described(Slant::Normal, Weight::Normal),
+            described(Slant::Italic, Weight::Bold),
+            described(Slant::Oblique, Weight::Normal),
+            Style::Specific("Light Italic".into()),
+            Style::Specific("SemiBold Condensed".into()),
+        ];
+
+        for style in styles {
+            for name in ["Fira Code", "Fira Sans Book", "Noto-Sans", "3270", "Font 3270"] {
+                let font_desc = desc(name, style.clone());
+
+                let fontconfig =
+                    FontconfigName { desc: font_desc.clone(), size: Some(Size::new(12.5)) };
+                assert_eq!(fontconfig.to_string().parse(), Ok(fontconfig));
+
+                let desc = font_desc.clone().with_languages(["ja", "zh_TW.UTF-8"]);
+                let fontconfig = FontconfigName { desc, size: None };
+                assert_eq!(fontconfig.to_string().parse(), Ok(fontconfig));
+
+                for size in [Some(Size::new(13.)), None] {
+                    let pango = PangoDescription { desc: font_desc.clone(), size };
+                    assert_eq!(pango.to_string().parse(), Ok(pango));
+                }
+
+                let css = CssFont { desc: font_desc, size: Size::new(9.) };
+                assert_eq!(css.to_string().parse(), Ok(css));
+            }
+        }
+
+        // Style names which aren't keywords are only kept by Fontconfig.
+        let lossy = [
+            ("Retina", described(Slant::Normal, Weight::Normal)),
+            ("Regular", described(Slant::Normal, Weight::Normal)),
+            ("Retina Bold Italic", described(Slant::Italic, Weight::Bold)),
+            ("Text Light", Style::Specific("Light".into())),
+        ];
+
+        for (style, parsed) in lossy {
+            let font_desc = desc("Hack", Style::Specific(style.into()));
+
+            let fontconfig = FontconfigName { desc: font_desc.clone(), size: None };
+            assert_eq!(fontconfig.to_string().parse(), Ok(fontconfig));
+
+            for size in [Some(Size::new(13.)), None] {
+                let pango = PangoDescription { desc: font_desc.clone(), size };
+                let expected = PangoDescription { desc: desc("Hack", parsed.clone()), size };
+                assert_eq!(pango.to_string().parse(), Ok(expected));
+            }
+
+            let css = CssFont { desc: font_desc, size: Size::new(9.) };
+            let expected = CssFont { desc: desc("Hack", parsed), size: Size::new(9.) };
+            assert_eq!(css.to_string().parse(), Ok(expected));
+        }
+    }
+}
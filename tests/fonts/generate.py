@@ -0,0 +1,235 @@
+"""Generate the fonts used by the golden image tests.
+
+Run `python3 tests/fonts/generate.py` to write the fonts next to this script.
+"""
+
+import os
+import sys
+
+sys.path.insert(0, os.path.dirname(os.path.abspath(__file__)))
+from ttf import (  # noqa: E402
+    Font,
+    Glyph,
+    cbdt_tables,
+    color_line,
+    colr_table,
+    cpal_table,
+    gpos_table,
+    gsub_table,
+    kern_table,
+    paint_colr_layers,
+    paint_glyph,
+    paint_linear_gradient,
+    paint_radial_gradient,
+    paint_rotate,
+    paint_scale,
+    paint_solid,
+    paint_sweep_gradient,
+    paint_transform,
+    paint_translate,
+    rect,
+    svg_table,
+)
+
+
+def outline_font():
+    font = Font("Crossfont Test")
+    font.monospace = True
+
+    triangle = [(50, 0), (350, 700), (650, 0)]
+    counter = [(250, 150), (350, 400), (450, 150)][::-1]
+    letter_a = font.add_glyph(Glyph("A", 700, [triangle, counter], [ord("A")]))
+
+    stems = [rect(100, 0, 220, 700), rect(480, 0, 600, 700), rect(220, 300, 480, 400)]
+    letter_h = font.add_glyph(Glyph("H", 700, stems, [ord("H")]))
+
+    outer = [(250, 0), (100, 150), (100, 350), (250, 500), (450, 500), (600, 350), (600, 150), (450, 0)]
+    inner = [(280, 100), (200, 180), (200, 320), (280, 400), (420, 400), (500, 320), (500, 180), (420, 100)]
+    letter_o = font.add_glyph(Glyph("o", 700, [outer, inner[::-1]], [ord("o")]))
+
+    font.add_glyph(Glyph("slash", 700, [[(100, -100), (480, 750), (600, 750), (220, -100)]], [ord("/")]))
+
+    heart = [(350, 0), (50, 350), (200, 600), (350, 450), (500, 600), (650, 350)]
+    font.add_glyph(Glyph("heart", 700, [heart], [0x2764]))
+
+    star = [(350, 700), (450, 450), (700, 450), (500, 300), (600, 0)]
+    star += [(350, 200), (100, 0), (200, 300), (0, 450), (250, 450)]
+    font.add_glyph(Glyph("star", 700, [star], [0x2B50]))
+
+    # Private use icons, one spilling into the next cell and a tiny one above the baseline.
+    font.add_glyph(Glyph("arrow", 700, [[(0, -200), (0, 800), (900, 300)]], [0xE0B0]))
+    font.add_glyph(Glyph("dot", 700, [rect(300, 500, 400, 600)], [0xF101]))
+
+    # Variant of `H` without its bar, selected by U+FE00.
+    alternate = font.add_glyph(Glyph("H.alt", 700, stems[:2]))
+    font.variations = {0xFE00: {ord("H"): alternate}}
+
+    # `GPOS` kerns `Ao`, which the legacy `kern` table kerns differently, together with `AH`.
+    font.extra_tables["GPOS"] = gpos_table({(letter_a, letter_o): -125})
+    font.extra_tables["kern"] = kern_table({(letter_a, letter_h): -250, (letter_a, letter_o): -500})
+
+    return font.build()
+
+
+def bitmap_font():
+    font = Font("Crossfont Test Bitmap", ascent=875, descent=-125)
+    font.outlines = False
+    glyph = font.add_glyph(Glyph("quadrants", 1000, [], [0xE200]))
+    letter_u = font.add_glyph(Glyph("u1F1FA", 1000, [], [0x1F1FA]))
+    letter_s = font.add_glyph(Glyph("u1F1F8", 1000, [], [0x1F1F8]))
+    flag = font.add_glyph(Glyph("u1F1FA_u1F1F8", 1000, []))
+    heart = font.add_glyph(Glyph("heart", 1000, [], [0x2764]))
+    star = font.add_glyph(Glyph("star", 1000, [], [0x2B50]))
+
+    red, green, blue, white = (255, 0, 0, 255), (0, 255, 0, 255), (0, 0, 255, 255), (128, 128, 128, 128)
+    rows = [[red] * 16 + [green] * 16 for _ in range(16)]
+    rows += [[blue] * 16 + [white] * 16 for _ in range(16)]
+
+    # Regional indicators are solid squares, while their flag ligature has horizontal stripes.
+    solid_red = [[red] * 32 for _ in range(32)]
+    solid_blue = [[blue] * 32 for _ in range(32)]
+    stripes = [[red if row // 8 % 2 == 0 else blue] * 32 for row in range(32)]
+
+    cblc, cbdt = cbdt_tables(32, 28, -4, [
+        (glyph, rows, 0, 28, 32),
+        (letter_u, solid_red, 0, 28, 32),
+        (letter_s, solid_blue, 0, 28, 32),
+        (flag, stripes, 0, 28, 32),
+        (heart, [[green] * 32 for _ in range(32)], 0, 28, 32),
+        (star, [[white] * 32 for _ in range(32)], 0, 28, 32),
+    ])
+    font.extra_tables["CBLC"] = cblc
+    font.extra_tables["CBDT"] = cbdt
+    font.extra_tables["GSUB"] = gsub_table("ccmp", [([letter_u, letter_s], flag)])
+
+    return font.build()
+
+
+def colr_font():
+    font = Font("Crossfont Test Color")
+    square = font.add_glyph(Glyph("square", 1000, [rect(100, 0, 900, 800)]))
+    inner = font.add_glyph(Glyph("inner", 1000, [rect(300, 200, 700, 600)]))
+    bar = font.add_glyph(Glyph("bar", 1000, [rect(400, 0, 600, 400)]))
+
+    names = ["layers", "solid", "linear", "radial", "sweep", "transform", "clip"]
+    base = {}
+    for i, name in enumerate(names):
+        base[name] = font.add_glyph(Glyph(name, 1000, [rect(100, 0, 900, 800)], [0xE300 + i]))
+
+    red, blue, green, white = 0, 1, 2, 3
+    font.extra_tables["CPAL"] = cpal_table([
+        (255, 0, 0, 255),
+        (0, 0, 255, 255),
+        (0, 255, 0, 255),
+        (255, 255, 255, 255),
+    ])
+
+    # Rotation by 45 degrees around the center of the square.
+    cos = sin = 0.5 ** 0.5
+    rotation = (cos, sin, -sin, cos, 500 - cos * 500 + sin * 400, 400 - sin * 500 - cos * 400)
+
+    layer_paints = [
+        # Translucent square with an opaque center.
+        paint_glyph(square, paint_solid(green, 0.5)),
+        paint_glyph(inner, paint_solid(red)),
+        # Shrunk square in the top right, a diamond and a bar tilted around the origin.
+        paint_translate(paint_scale(paint_glyph(square, paint_solid(red)), 0.5, 0.5), 450, 400),
+        paint_transform(paint_glyph(inner, paint_solid(blue)), *rotation),
+        paint_rotate(paint_glyph(bar, paint_solid(green)), 15),
+    ]
+
+    gradient = color_line([(0, red, 1), (1, blue, 1)])
+    paints = {
+        base["solid"]: paint_colr_layers(0, 2),
+        base["linear"]: paint_glyph(
+            square, paint_linear_gradient(gradient, (100, 0), (900, 0), (100, 800))
+        ),
+        base["radial"]: paint_glyph(
+            square,
+            paint_radial_gradient(
+                color_line([(0, white, 1), (1, green, 0.5)]), (500, 400), 0, (500, 400), 400
+            ),
+        ),
+        base["sweep"]: paint_glyph(square, paint_sweep_gradient(gradient, (500, 400), 0, 360)),
+        base["transform"]: paint_colr_layers(2, 3),
+        base["clip"]: paint_glyph(square, paint_solid(blue)),
+    }
+
+    font.extra_tables["COLR"] = colr_table(
+        {base["layers"]: [(square, red), (inner, blue)]},
+        paints,
+        layer_paints,
+        {base["clip"]: (100, 0, 500, 800)},
+    )
+
+    return font.build()
+
+
+def svg_font():
+    font = Font("Crossfont Test SVG")
+    square = font.add_glyph(Glyph("square", 1000, [rect(100, 0, 900, 800)], [0xE400]))
+    gradient = font.add_glyph(Glyph("gradient", 1000, [rect(100, 0, 900, 800)], [0xE401]))
+
+    # Both glyphs share a document, with the Y axis pointing down.
+    document = f"""<svg xmlns="http://www.w3.org/2000/svg">
+<defs>
+<linearGradient id="fade" x1="0" x2="1" y1="0" y2="0">
+<stop offset="0" stop-color="#ff0000"/>
+<stop offset="1" stop-color="#0000ff" stop-opacity="0"/>
+</linearGradient>
+</defs>
+<g id="glyph{square}">
+<rect x="100" y="-800" width="800" height="800" fill="#ff0000"/>
+<rect x="300" y="-600" width="400" height="400" fill="#0000ff" fill-opacity="0.5"/>
+</g>
+<rect id="glyph{gradient}" x="100" y="-800" width="800" height="800" fill="url(#fade)"/>
+</svg>
+"""
+    font.extra_tables["SVG "] = svg_table([(square, gradient, document)])
+
+    return font.build()
+
+
+def pixel_font():
+    """BDF font with a single 8px strike, for bitmap text fonts without outlines."""
+    letter_a = ["0110", "1001", "1001", "1111", "1001", "1001"]
+    lines = [
+        "STARTFONT 2.1",
+        "FONT -crossfont-Crossfont Test Pixel-Medium-R-Normal--8-80-75-75-C-60-ISO10646-1",
+        "SIZE 8 75 75",
+        "FONTBOUNDINGBOX 6 8 0 -2",
+        "STARTPROPERTIES 9",
+        'FAMILY_NAME "Crossfont Test Pixel"',
+        'WEIGHT_NAME "Medium"',
+        'SLANT "R"',
+        "PIXEL_SIZE 8",
+        "AVERAGE_WIDTH 60",
+        "FONT_ASCENT 6",
+        "FONT_DESCENT 2",
+        'CHARSET_REGISTRY "ISO10646"',
+        'CHARSET_ENCODING "1"',
+        "ENDPROPERTIES",
+        "CHARS 1",
+        "STARTCHAR A",
+        "ENCODING 65",
+        "SWIDTH 750 0",
+        "DWIDTH 6 0",
+        f"BBX 4 {len(letter_a)} 1 0",
+        "BITMAP",
+    ]
+    lines += ["%02X" % (int(row, 2) << 4) for row in letter_a]
+    lines += ["ENDCHAR", "ENDFONT"]
+    return ("\n".join(lines) + "\n").encode()
+
+
+if __name__ == "__main__":
+    directory = os.path.dirname(os.path.abspath(__file__))
+    for name, data in [
+        ("CrossfontTest-Regular.ttf", outline_font()),
+        ("CrossfontTestBitmap-Regular.ttf", bitmap_font()),
+        ("CrossfontTestColor-Regular.ttf", colr_font()),
+        ("CrossfontTestSVG-Regular.ttf", svg_font()),
+        ("CrossfontTestPixel-Regular.bdf", pixel_font()),
+    ]:
+        with open(os.path.join(directory, name), "wb") as file:
+            file.write(data)
@@ -0,0 +1,567 @@
+"""Minimal TrueType font builder used to generate the test fonts."""
+
+import struct
+import zlib
+
+
+def pad4(data):
+    return data + b"\0" * ((4 - len(data) % 4) % 4)
+
+
+def checksum(data):
+    data = pad4(data)
+    return sum(struct.unpack(">%dI" % (len(data) // 4), data)) & 0xFFFFFFFF
+
+
+class Glyph:
+    def __init__(self, name, advance, contours=(), codepoints=()):
+        # Each contour is a list of (x, y) on-curve points in clockwise order.
+        self.name = name
+        self.advance = advance
+        self.contours = [list(c) for c in contours]
+        self.codepoints = list(codepoints)
+
+    def bbox(self):
+        points = [p for c in self.contours for p in c]
+        if not points:
+            return (0, 0, 0, 0)
+        xs = [p[0] for p in points]
+        ys = [p[1] for p in points]
+        return (min(xs), min(ys), max(xs), max(ys))
+
+    def encode(self):
+        if not self.contours:
+            return b""
+        xmin, ymin, xmax, ymax = self.bbox()
+        data = struct.pack(">hhhhh", len(self.contours), xmin, ymin, xmax, ymax)
+        end = -1
+        for contour in self.contours:
+            end += len(contour)
+            data += struct.pack(">H", end)
+        data += struct.pack(">H", 0)
+        points = [p for c in self.contours for p in c]
+        data += bytes([0x01] * len(points))
+        last = 0
+        for x, _ in points:
+            data += struct.pack(">h", x - last)
+            last = x
+        last = 0
+        for _, y in points:
+            data += struct.pack(">h", y - last)
+            last = y
+        return data
+
+
+def rect(x0, y0, x1, y1):
+    """Clockwise rectangle contour."""
+    return [(x0, y0), (x0, y1), (x1, y1), (x1, y0)]
+
+
+def name_table(names):
+    records = []
+    strings = b""
+    for name_id, value in sorted(names.items()):
+        encoded = value.encode("utf-16-be")
+        records.append(struct.pack(">HHHHHH", 3, 1, 0x409, name_id, len(encoded), len(strings)))
+        strings += encoded
+    header = struct.pack(">HHH", 0, len(records), 6 + 12 * len(records))
+    return header + b"".join(records) + strings
+
+
+def cmap_table(mapping, variations=None):
+    """Build a cmap with format 4, format 12 and optional format 14 subtables."""
+    bmp = sorted((c, g) for c, g in mapping.items() if c <= 0xFFFF)
+
+    # Format 4 with one segment per codepoint plus the final 0xFFFF segment.
+    segments = [(c, c, g) for c, g in bmp] + [(0xFFFF, 0xFFFF, 0)]
+    seg_count = len(segments)
+    search_range = 2 * (1 << (seg_count.bit_length() - 1))
+    entry_selector = seg_count.bit_length() - 1
+    range_shift = 2 * seg_count - search_range
+    end_codes = b"".join(struct.pack(">H", s[1]) for s in segments)
+    start_codes = b"".join(struct.pack(">H", s[0]) for s in segments)
+    deltas = b"".join(struct.pack(">H", (s[2] - s[0]) & 0xFFFF if s[2] else 1) for s in segments)
+    offsets = b"\0\0" * seg_count
+    body = (
+        struct.pack(">HHHH", seg_count * 2, search_range, entry_selector, range_shift)
+        + end_codes
+        + b"\0\0"
+        + start_codes
+        + deltas
+        + offsets
+    )
+    format4 = struct.pack(">HHH", 4, 6 + len(body), 0) + body
+
+    groups = sorted(mapping.items())
+    format12 = struct.pack(">HHIII", 12, 0, 16 + 12 * len(groups), 0, len(groups))
+    for c, g in groups:
+        format12 += struct.pack(">III", c, c, g)
+
+    subtables = [(3, 1, format4), (3, 10, format12)]
+
+    if variations:
+        # Only non-default UVS mappings are supported.
+        records = b""
+        data = b""
+        selectors = sorted(variations.items())
+        offset = 10 + 11 * len(selectors)
+        for selector, uvs in selectors:
+            uvs = sorted(uvs.items())
+            table = struct.pack(">I", len(uvs))
+            for c, g in uvs:
+                table += struct.pack(">I", c)[1:] + struct.pack(">H", g)
+            records += struct.pack(">I", selector)[1:] + struct.pack(">II", 0, offset + len(data))
+            data += table
+        format14 = struct.pack(">HII", 14, offset + len(data), len(selectors)) + records + data
+        subtables.insert(0, (0, 5, format14))
+
+    header = struct.pack(">HH", 0, len(subtables))
+    offset = 4 + 8 * len(subtables)
+    records = b""
+    data = b""
+    for platform, encoding, table in subtables:
+        records += struct.pack(">HHI", platform, encoding, offset + len(data))
+        data += table
+    return header + records + data
+
+
+class Font:
+    def __init__(self, family, style="Regular", units_per_em=1000, ascent=800, descent=-200):
+        self.family = family
+        self.style = style
+        self.units_per_em = units_per_em
+        self.ascent = ascent
+        self.descent = descent
+        self.line_gap = 0
+        self.glyphs = [Glyph(".notdef", 500, [rect(50, 0, 450, 700), rect(100, 50, 400, 650)[::-1]])]
+        self.extra_tables = {}
+        self.variations = {}
+        self.x_height = 500
+        self.cap_height = 700
+        self.monospace = False
+        self.weight_class = 400
+        # Fonts without outlines only contain the bitmaps in `extra_tables`.
+        self.outlines = True
+
+    def add_glyph(self, glyph):
+        self.glyphs.append(glyph)
+        return len(self.glyphs) - 1
+
+    def glyph_id(self, name):
+        return next(i for i, g in enumerate(self.glyphs) if g.name == name)
+
+    def build(self):
+        tables = dict(self.extra_tables)
+        num_glyphs = len(self.glyphs)
+
+        if self.outlines:
+            glyf = b""
+            loca = b""
+            for glyph in self.glyphs:
+                loca += struct.pack(">I", len(glyf))
+                glyf += pad4(glyph.encode())
+            loca += struct.pack(">I", len(glyf))
+            tables["glyf"] = glyf
+            tables["loca"] = loca
+
+        boxes = [g.bbox() for g in self.glyphs if g.contours] or [(0, 0, 0, 0)]
+        xmin = min(b[0] for b in boxes)
+        ymin = min(b[1] for b in boxes)
+        xmax = max(b[2] for b in boxes)
+        ymax = max(b[3] for b in boxes)
+        max_advance = max(g.advance for g in self.glyphs)
+
+        tables["head"] = struct.pack(
+            ">HHIIIHHqqhhhhHHhhh",
+            1, 0, 0x10000, 0, 0x5F0F3CF5, 0x000B, self.units_per_em, 0, 0,
+            xmin, ymin, xmax, ymax, 0, 8, 2, 1, 0,
+        )
+
+        tables["hhea"] = struct.pack(
+            ">HHhhhHhhhhhhhhhhhH",
+            1, 0, self.ascent, self.descent, self.line_gap, max_advance,
+            0, 0, xmax, 1, 0, 0, 0, 0, 0, 0, 0, num_glyphs,
+        )
+
+        max_points = max([sum(len(c) for c in g.contours) for g in self.glyphs] + [0])
+        max_contours = max([len(g.contours) for g in self.glyphs] + [0])
+        if self.outlines:
+            tables["maxp"] = struct.pack(
+                ">IHHHHHHHHHHHHHH",
+                0x10000, num_glyphs, max_points, max_contours, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0,
+            )
+        else:
+            tables["maxp"] = struct.pack(">IH", 0x5000, num_glyphs)
+
+        hmtx = b""
+        for glyph in self.glyphs:
+            hmtx += struct.pack(">Hh", glyph.advance, glyph.bbox()[0])
+        tables["hmtx"] = hmtx
+
+        mapping = {}
+        for gid, glyph in enumerate(self.glyphs):
+            for codepoint in glyph.codepoints:
+                mapping[codepoint] = gid
+        tables["cmap"] = cmap_table(mapping, self.variations)
+
+        codepoints = sorted(mapping) or [0x20]
+        avg_width = sum(g.advance for g in self.glyphs) // num_glyphs
+        panose = bytes([2, 0, 5, 9 if self.monospace else 3, 0, 0, 0, 0, 0, 0])
+        fs_selection = 0x40 if self.style == "Regular" else 0x20 if self.style == "Bold" else 0
+        tables["OS/2"] = (
+            struct.pack(
+                ">HhHHHhhhhhhhhhhh",
+                4, avg_width, self.weight_class, 5, 0,
+                self.units_per_em // 2, self.units_per_em // 2, 0, 0,
+                self.units_per_em // 2, self.units_per_em // 2, 0, 0,
+                50, 300, 0,
+            )
+            + panose
+            + struct.pack(">IIII", 1, 0, 0, 0)
+            + b"NONE"
+            + struct.pack(
+                ">HHHhhhHH",
+                fs_selection, min(codepoints[0], 0xFFFF), min(codepoints[-1], 0xFFFF),
+                self.ascent, self.descent, self.line_gap, self.ascent, -self.descent,
+            )
+            + struct.pack(">II", 1, 0)
+            + struct.pack(">hhHHH", self.x_height, self.cap_height, 0, 0x20, 0)
+        )
+
+        tables["post"] = struct.pack(
+            ">IIhhIIIII", 0x30000, 0, -100, 50, 1 if self.monospace else 0, 0, 0, 0, 0
+        )
+
+        full = self.family if self.style == "Regular" else "%s %s" % (self.family, self.style)
+        tables["name"] = name_table({
+            1: self.family,
+            2: self.style,
+            3: full,
+            4: full,
+            6: full.replace(" ", ""),
+        })
+
+        return self._assemble(tables)
+
+    def _assemble(self, tables):
+        tags = sorted(tables)
+        num_tables = len(tags)
+        entry_selector = num_tables.bit_length() - 1
+        search_range = 16 * (1 << entry_selector)
+        range_shift = num_tables * 16 - search_range
+        header = struct.pack(">IHHHH", 0x10000, num_tables, search_range, entry_selector, range_shift)
+
+        offset = 12 + 16 * num_tables
+        directory = b""
+        data = b""
+        head_offset = None
+        for tag in tags:
+            table = tables[tag]
+            if tag == "head":
+                head_offset = offset + len(data)
+            directory += tag.encode().ljust(4) + struct.pack(
+                ">III", checksum(table), offset + len(data), len(table)
+            )
+            data += pad4(table)
+
+        font = bytearray(header + directory + data)
+        adjustment = (0xB1B0AFBA - checksum(bytes(font))) & 0xFFFFFFFF
+        font[head_offset + 8:head_offset + 12] = struct.pack(">I", adjustment)
+        return bytes(font)
+
+
+def png(rows):
+    """Encode rows of (r, g, b, a) pixels as PNG."""
+
+    def chunk(tag, data):
+        return struct.pack(">I", len(data)) + tag + data + struct.pack(">I", zlib.crc32(tag + data))
+
+    height = len(rows)
+    width = len(rows[0])
+    raw = b"".join(b"\0" + b"".join(bytes(pixel) for pixel in row) for row in rows)
+    return (
+        b"\x89PNG\r\n\x1a\n"
+        + chunk(b"IHDR", struct.pack(">IIBBBBB", width, height, 8, 6, 0, 0, 0))
+        + chunk(b"IDAT", zlib.compress(raw))
+        + chunk(b"IEND", b"")
+    )
+
+
+def cbdt_tables(ppem, ascent, descent, glyphs):
+    """Build CBLC and CBDT tables for a single strike.
+
+    `glyphs` is a list of consecutive (glyph, rows, bearing_x, bearing_y, advance) tuples, with
+    rows of (r, g, b, a) pixels.
+    """
+    cbdt = struct.pack(">HH", 3, 0)
+    offsets = b""
+    for _, rows, bearing_x, bearing_y, advance in glyphs:
+        offsets += struct.pack(">I", len(cbdt) - 4)
+        image = png(rows)
+        metrics = struct.pack(">BBbbB", len(rows), len(rows[0]), bearing_x, bearing_y, advance)
+        cbdt += metrics + struct.pack(">I", len(image)) + image
+    offsets += struct.pack(">I", len(cbdt) - 4)
+
+    first = glyphs[0][0]
+    last = glyphs[-1][0]
+    subtable = struct.pack(">HHI", 1, 17, 4) + offsets
+    array = struct.pack(">HHI", first, last, 8)
+    width = max(len(rows[0]) for _, rows, _, _, _ in glyphs)
+    line_metrics = struct.pack(">bbBbbbbbbbbb", ascent, descent, width, 1, 0, 0, 0, 0, 0, 0, 0, 0)
+    size = (
+        struct.pack(">IIII", 8 + 48, len(array) + len(subtable), 1, 0)
+        + line_metrics
+        + line_metrics
+        + struct.pack(">HHBBBb", first, last, ppem, ppem, 32, 1)
+    )
+    cblc = struct.pack(">HHI", 3, 0, 1) + size + array + subtable
+    return cblc, cbdt
+
+
+def layout_table(feature, lookup_type, subtable):
+    """Build a GSUB or GPOS table with a single lookup for `feature`."""
+    # Script list with the feature in the default language system of the default script.
+    script_list = struct.pack(">H4sH", 1, b"DFLT", 8) + struct.pack(">HH", 4, 0)
+    script_list += struct.pack(">HHHH", 0, 0xFFFF, 1, 0)
+
+    feature_list = struct.pack(">H4sH", 1, feature.encode(), 8) + struct.pack(">HHH", 0, 1, 0)
+
+    lookup_list = struct.pack(">HH", 1, 4) + struct.pack(">HHHH", lookup_type, 0, 1, 8) + subtable
+
+    feature_offset = 10 + len(script_list)
+    lookup_offset = feature_offset + len(feature_list)
+    header = struct.pack(">HHHHH", 1, 0, 10, feature_offset, lookup_offset)
+    return header + script_list + feature_list + lookup_list
+
+
+def coverage_table(glyphs):
+    return struct.pack(">HH", 1, len(glyphs)) + b"".join(struct.pack(">H", glyph) for glyph in glyphs)
+
+
+def gsub_table(feature, ligatures):
+    """Build a GSUB table with one ligature substitution lookup for `feature`.
+
+    `ligatures` is a list of (component glyphs, ligature glyph) tuples.
+    """
+    # Ligature sets are grouped by their first glyph, in the order of the coverage table.
+    sets = {}
+    for components, ligature in ligatures:
+        sets.setdefault(components[0], []).append((components, ligature))
+    first_glyphs = sorted(sets)
+
+    coverage = coverage_table(first_glyphs)
+
+    set_data = b""
+    set_offsets = []
+    header_len = 6 + 2 * len(first_glyphs)
+    for glyph in first_glyphs:
+        set_offsets.append(header_len + len(coverage) + len(set_data))
+        entries = sets[glyph]
+        ligature_set = struct.pack(">H", len(entries))
+        ligature_data = b""
+        for components, ligature in entries:
+            ligature_set += struct.pack(">H", 2 + 2 * len(entries) + len(ligature_data))
+            ligature_data += struct.pack(">HH", ligature, len(components))
+            ligature_data += b"".join(struct.pack(">H", c) for c in components[1:])
+        set_data += ligature_set + ligature_data
+
+    subtable = struct.pack(">HHH", 1, header_len, len(first_glyphs))
+    subtable += b"".join(struct.pack(">H", offset) for offset in set_offsets)
+    subtable += coverage + set_data
+
+    return layout_table(feature, 4, subtable)
+
+
+def gpos_table(pairs):
+    """Build a GPOS table with one pair adjustment lookup for the `kern` feature.
+
+    `pairs` maps (left glyph, right glyph) to the advance adjustment of the left glyph.
+    """
+    # Pair sets are grouped by their first glyph, in the order of the coverage table.
+    sets = {}
+    for (left, right), value in sorted(pairs.items()):
+        sets.setdefault(left, []).append((right, value))
+    first_glyphs = sorted(sets)
+
+    coverage = coverage_table(first_glyphs)
+
+    set_data = b""
+    set_offsets = []
+    header_len = 10 + 2 * len(first_glyphs)
+    for glyph in first_glyphs:
+        set_offsets.append(header_len + len(coverage) + len(set_data))
+        entries = sets[glyph]
+        set_data += struct.pack(">H", len(entries))
+        set_data += b"".join(struct.pack(">Hh", right, value) for right, value in entries)
+
+    # Format 1 with only the horizontal advance of the first glyph.
+    subtable = struct.pack(">HHHHH", 1, header_len, 0x0004, 0, len(first_glyphs))
+    subtable += b"".join(struct.pack(">H", offset) for offset in set_offsets)
+    subtable += coverage + set_data
+
+    return layout_table("kern", 2, subtable)
+
+
+def kern_table(pairs):
+    """Build a legacy kern table with one horizontal format 0 subtable.
+
+    `pairs` maps (left glyph, right glyph) to their kerning.
+    """
+    entry_selector = max(len(pairs).bit_length() - 1, 0)
+    search_range = 6 * (1 << entry_selector)
+    range_shift = 6 * len(pairs) - search_range
+    data = struct.pack(">HHHH", len(pairs), search_range, entry_selector, range_shift)
+    data += b"".join(struct.pack(">HHh", left, right, value) for (left, right), value in sorted(pairs.items()))
+
+    subtable = struct.pack(">HHH", 0, 6 + len(data), 0x0001) + data
+    return struct.pack(">HH", 0, 1) + subtable
+
+
+def cpal_table(colors):
+    """Build a CPAL table with a single palette of (r, g, b, a) colors."""
+    header = struct.pack(">HHHHIH", 0, len(colors), 1, len(colors), 14, 0)
+    return header + b"".join(struct.pack(">BBBB", b, g, r, a) for r, g, b, a in colors)
+
+
+def offset24(offset):
+    return struct.pack(">I", offset)[1:]
+
+
+def f2dot14(value):
+    return struct.pack(">h", round(value * 16384))
+
+
+def color_line(stops, extend=0):
+    """Build a color line from (offset, palette index, alpha) stops."""
+    data = struct.pack(">BH", extend, len(stops))
+    for offset, palette_index, alpha in stops:
+        data += f2dot14(offset) + struct.pack(">H", palette_index) + f2dot14(alpha)
+    return data
+
+
+def with_children(header, children):
+    """Append child tables to a paint, with their offsets following its 24-bit placeholders.
+
+    `header` is a list of byte strings, where `None` entries are replaced by the offset of the
+    next child.
+    """
+    length = sum(3 if part is None else len(part) for part in header)
+    data = b""
+    offsets = []
+    for child in children:
+        offsets.append(length + len(data))
+        data += child
+    offsets = iter(offsets)
+    return b"".join(offset24(next(offsets)) if part is None else part for part in header) + data
+
+
+def paint_colr_layers(first_layer, num_layers):
+    return struct.pack(">BBI", 1, num_layers, first_layer)
+
+
+def paint_solid(palette_index, alpha=1.0):
+    return struct.pack(">BH", 2, palette_index) + f2dot14(alpha)
+
+
+def paint_linear_gradient(line, p0, p1, p2):
+    points = struct.pack(">hhhhhh", *p0, *p1, *p2)
+    return with_children([b"\x04", None, points], [line])
+
+
+def paint_radial_gradient(line, c0, r0, c1, r1):
+    circles = struct.pack(">hhHhhH", *c0, r0, *c1, r1)
+    return with_children([b"\x06", None, circles], [line])
+
+
+def paint_sweep_gradient(line, center, start_angle, end_angle):
+    """Sweep gradient with angles in degrees, which are stored with a bias of 180 degrees."""
+    angles = f2dot14(start_angle / 180 - 1) + f2dot14(end_angle / 180 - 1)
+    return with_children([b"\x08", None, struct.pack(">hh", *center), angles], [line])
+
+
+def paint_glyph(glyph, paint):
+    return with_children([b"\x0a", None, struct.pack(">H", glyph)], [paint])
+
+
+def paint_transform(paint, xx, yx, xy, yy, dx, dy):
+    affine = b"".join(struct.pack(">i", round(value * 65536)) for value in (xx, yx, xy, yy, dx, dy))
+    return with_children([b"\x0c", None, None], [paint, affine])
+
+
+def paint_translate(paint, dx, dy):
+    return with_children([b"\x0e", None, struct.pack(">hh", dx, dy)], [paint])
+
+
+def paint_scale(paint, scale_x, scale_y):
+    return with_children([b"\x10", None, f2dot14(scale_x) + f2dot14(scale_y)], [paint])
+
+
+def paint_rotate(paint, degrees):
+    return with_children([b"\x18", None, f2dot14(degrees / 180)], [paint])
+
+
+def colr_table(layers, paints, layer_paints, clips):
+    """Build a COLR version 1 table.
+
+    `layers` maps base glyphs to the (glyph, palette index) layers of version 0, `paints` maps
+    base glyphs to the root of their version 1 paint graph, `layer_paints` is the layer list
+    referenced by `paint_colr_layers` and `clips` maps glyphs to their (x_min, y_min, x_max,
+    y_max) clip box.
+    """
+    header_len = 34
+
+    base_records = b""
+    layer_records = b""
+    num_layers = 0
+    for glyph, glyph_layers in sorted(layers.items()):
+        base_records += struct.pack(">HHH", glyph, num_layers, len(glyph_layers))
+        layer_records += b"".join(struct.pack(">HH", *layer) for layer in glyph_layers)
+        num_layers += len(glyph_layers)
+
+    base_glyph_list = struct.pack(">I", len(paints))
+    paint_data = b""
+    for glyph, paint in sorted(paints.items()):
+        offset = 4 + 6 * len(paints) + len(paint_data)
+        base_glyph_list += struct.pack(">HI", glyph, offset)
+        paint_data += paint
+    base_glyph_list += paint_data
+
+    layer_list = struct.pack(">I", len(layer_paints))
+    paint_data = b""
+    for paint in layer_paints:
+        layer_list += struct.pack(">I", 4 + 4 * len(layer_paints) + len(paint_data))
+        paint_data += paint
+    layer_list += paint_data
+
+    clip_list = struct.pack(">BI", 1, len(clips))
+    box_data = b""
+    for glyph, box in sorted(clips.items()):
+        offset = 5 + 7 * len(clips) + len(box_data)
+        clip_list += struct.pack(">HH", glyph, glyph) + offset24(offset)
+        box_data += struct.pack(">Bhhhh", 1, *box)
+    clip_list += box_data
+
+    base_records_offset = header_len
+    layer_records_offset = base_records_offset + len(base_records)
+    base_glyph_list_offset = layer_records_offset + len(layer_records)
+    layer_list_offset = base_glyph_list_offset + len(base_glyph_list)
+    clip_list_offset = layer_list_offset + len(layer_list)
+
+    header = struct.pack(
+        ">HHIIHIIIII",
+        1, len(layers), base_records_offset, layer_records_offset, num_layers,
+        base_glyph_list_offset, layer_list_offset, clip_list_offset, 0, 0,
+    )
+    return header + base_records + layer_records + base_glyph_list + layer_list + clip_list
+
+
+def svg_table(documents):
+    """Build an SVG table from (start glyph, end glyph, document) tuples."""
+    records = b""
+    data = b""
+    offset = 2 + 12 * len(documents)
+    for start, end, document in documents:
+        document = document.encode()
+        records += struct.pack(">HHII", start, end, offset + len(data), len(document))
+        data += document
+    return struct.pack(">HII", 0, 10, 0) + struct.pack(">H", len(documents)) + records + data
@@ -0,0 +1,461 @@
+//! Golden image tests for glyph rasterization.
+//!
+//! Fonts are loaded from `tests/fonts` through an isolated Fontconfig configuration, so the
+//! results don't depend on the fonts and settings of the machine running the tests.
+//!
+//! Run the tests with `CROSSFONT_BLESS=1` to write the current output to `tests/golden`, instead
+//! of comparing against it.
+
+#![cfg(not(any(target_os = "macos", windows)))]
+
+use std::env;
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crossfont::ft::fc::{self, LcdFilter, Rgba};
+use crossfont::ft::{
+    BitmapStrike, ConstraintSize, EmojiPresentation, FreeTypeRasterizer, GlyphConstraint,
+    RenderOptions,
+};
+use crossfont::{
+    BitmapBuffer, CellBox, ClusterKey, FontDesc, FontKey, GlyphKey, Rasterize, RasterizedGlyph,
+    Size, Slant, Style, Weight,
+};
+
+const OUTLINE_FAMILY: &str = "Crossfont Test";
+const OUTLINE_CHARS: [char; 4] = ['A', 'H', 'o', '/'];
+
+const BITMAP_FAMILY: &str = "Crossfont Test Bitmap";
+const BITMAP_CHARS: [char; 1] = ['\u{E200}'];
+
+const PIXEL_FAMILY: &str = "Crossfont Test Pixel";
+
+const COLOR_FAMILY: &str = "Crossfont Test Color";
+#[cfg(feature = "svg")]
+const SVG_FAMILY: &str = "Crossfont Test SVG";
+#[cfg(feature = "svg")]
+const SVG_CHARS: [char; 2] = ['\u{E400}', '\u{E401}'];
+const COLR_LAYER_CHARS: [char; 1] = ['\u{E300}'];
+const COLR_PAINT_CHARS: [char; 6] =
+    ['\u{E301}', '\u{E302}', '\u{E303}', '\u{E304}', '\u{E305}', '\u{E306}'];
+
+fn rasterizer(rules: fc::Rules) -> FreeTypeRasterizer {
+    let fonts = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fonts");
+
+    let mut config = fc::Config::empty().unwrap();
+    assert!(config.add_font_dir(&fonts));
+    assert!(config.load_rules(&rules.to_string()));
+
+    FreeTypeRasterizer::with_config(config).unwrap()
+}
+
+/// Unhinted outline rendering, so results don't depend on FreeType's hinting engines.
+fn outline_options(antialias: bool, rgba: Rgba) -> RenderOptions {
+    RenderOptions {
+        antialias: Some(antialias),
+        hinting: Some(false),
+        rgba: Some(rgba),
+        lcdfilter: Some(LcdFilter::Default),
+        autohint: Some(false),
+        ..RenderOptions::default()
+    }
+}
+
+/// Load the regular style of `family`.
+fn load(
+    rasterizer: &mut FreeTypeRasterizer,
+    family: &str,
+    size: Size,
+    options: &RenderOptions,
+) -> FontKey {
+    let style = Style::Description { slant: Slant::Normal, weight: Weight::Normal };
+    rasterizer.load_font_with_options(&FontDesc::new(family, style), size, options).unwrap()
+}
+
+/// Rasterize `chars` and compare them to the snapshot `name`.
+fn check_snapshot(
+    name: &str,
+    rasterizer: &mut FreeTypeRasterizer,
+    family: &str,
+    options: &RenderOptions,
+    chars: &[char],
+) {
+    let size = Size::new(12.);
+    let font_key = load(rasterizer, family, size, options);
+
+    let mut snapshot = String::new();
+    for &character in chars {
+        let glyph = rasterizer.get_glyph(GlyphKey { font_key, character, size }).unwrap();
+        write_glyph(&mut snapshot, &glyph);
+    }
+
+    assert_snapshot(name, &snapshot);
+}
+
+/// Compare rasterized glyphs to the snapshot `name`.
+fn assert_snapshot(name: &str, snapshot: &str) {
+    let path = snapshot_dir().join(format!("{name}.txt"));
+    if env::var_os("CROSSFONT_BLESS").is_some() {
+        fs::create_dir_all(snapshot_dir()).unwrap();
+        fs::write(&path, snapshot).unwrap();
+        return;
+    }
+
+    let expected = fs::read_to_string(&path).unwrap_or_else(|err| {
+        panic!("Unable to read {path:?}: {err}, run with CROSSFONT_BLESS=1 to create it")
+    });
+    assert!(
+        expected == snapshot,
+        "Rasterized glyphs differ from {path:?}, run with CROSSFONT_BLESS=1 to update \
+         it:\n{snapshot}"
+    );
+}
+
+fn snapshot_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden")
+}
+
+/// Write the glyph's metrics followed by one line of hex encoded pixels per row.
+fn write_glyph(snapshot: &mut String, glyph: &RasterizedGlyph) {
+    let (kind, buffer, channels) = match &glyph.buffer {
+        BitmapBuffer::Rgb(buffer) => ("rgb", buffer, 3),
+        BitmapBuffer::Rgba(buffer) => ("rgba", buffer, 4),
+    };
+
+    let _ = writeln!(
+        snapshot,
+        "{:?} {}x{} left={} top={} advance={},{} {kind}",
+        glyph.character,
+        glyph.width,
+        glyph.height,
+        glyph.left,
+        glyph.top,
+        glyph.advance.0,
+        glyph.advance.1,
+    );
+
+    let width = glyph.width as usize * channels;
+    for row in buffer.chunks(width.max(1)) {
+        let pixels: Vec<_> = row
+            .chunks(channels)
+            .map(|pixel| pixel.iter().map(|channel| format!("{channel:02x}")).collect::<String>())
+            .collect();
+        let _ = writeln!(snapshot, "{}", pixels.join(" "));
+    }
+}
+
+#[test]
+fn gray() {
+    let mut rasterizer = rasterizer(fc::Rules::new());
+    let options = outline_options(true, Rgba::None);
+    check_snapshot("gray", &mut rasterizer, OUTLINE_FAMILY, &options, &OUTLINE_CHARS);
+}
+
+#[test]
+fn mono() {
+    let mut rasterizer = rasterizer(fc::Rules::new());
+    let options = outline_options(false, Rgba::None);
+    check_snapshot("mono", &mut rasterizer, OUTLINE_FAMILY, &options, &OUTLINE_CHARS);
+}
+
+#[test]
+fn lcd_rgb() {
+    let mut rasterizer = rasterizer(fc::Rules::new());
+    let options = outline_options(true, Rgba::Rgb);
+    check_snapshot("lcd_rgb", &mut rasterizer, OUTLINE_FAMILY, &options, &OUTLINE_CHARS);
+}
+
+#[test]
+fn lcd_bgr() {
+    let mut rasterizer = rasterizer(fc::Rules::new());
+    let options = outline_options(true, Rgba::Bgr);
+    check_snapshot("lcd_bgr", &mut rasterizer, OUTLINE_FAMILY, &options, &OUTLINE_CHARS);
+}
+
+#[test]
+fn lcd_v() {
+    let mut rasterizer = rasterizer(fc::Rules::new());
+    let options = outline_options(true, Rgba::Vrgb);
+    check_snapshot("lcd_v", &mut rasterizer, OUTLINE_FAMILY, &options, &OUTLINE_CHARS);
+}
+
+#[test]
+fn embolden() {
+    let rules = fc::Rules::new().set(OUTLINE_FAMILY, fc::Property::Embolden(true));
+    let mut rasterizer = rasterizer(rules);
+    let options = outline_options(true, Rgba::None);
+    check_snapshot("embolden", &mut rasterizer, OUTLINE_FAMILY, &options, &OUTLINE_CHARS);
+}
+
+#[test]
+fn color_bitmap() {
+    // The 32px strike is downsampled to 16px.
+    let mut rasterizer = rasterizer(fc::Rules::new());
+    let options = RenderOptions::default();
+    check_snapshot("color_bitmap", &mut rasterizer, BITMAP_FAMILY, &options, &BITMAP_CHARS);
+}
+
+#[test]
+fn colr_layers() {
+    let mut rasterizer = rasterizer(fc::Rules::new());
+    let options = RenderOptions::default();
+    check_snapshot("colr_layers", &mut rasterizer, COLOR_FAMILY, &options, &COLR_LAYER_CHARS);
+}
+
+#[test]
+fn colr_paint() {
+    let mut rasterizer = rasterizer(fc::Rules::new());
+    let size = Size::new(12.);
+    let font_key = load(&mut rasterizer, COLOR_FAMILY, size, &RenderOptions::default());
+
+    // Paint graphs are only rendered with FreeType 2.13+, older versions use the outlines.
+    let glyph_key = GlyphKey { font_key, character: COLR_PAINT_CHARS[0], size };
+    if let BitmapBuffer::Rgb(_) = rasterizer.get_glyph(glyph_key).unwrap().buffer {
+        return;
+    }
+
+    // Solid layers, gradients, transforms and clip boxes.
+    let options = RenderOptions::default();
+    check_snapshot("colr_paint", &mut rasterizer, COLOR_FAMILY, &options, &COLR_PAINT_CHARS);
+}
+
+#[test]
+fn colr_fallback() {
+    let mut rasterizer = rasterizer(fc::Rules::new());
+    let size = Size::new(12.);
+    let options = RenderOptions::default();
+    let outline_key = load(&mut rasterizer, OUTLINE_FAMILY, size, &options);
+    let color_key = load(&mut rasterizer, COLOR_FAMILY, size, &options);
+
+    // Characters missing from the primary font are taken from the `COLR` font.
+    let character = COLR_LAYER_CHARS[0];
+    let glyph = rasterizer.get_glyph(GlyphKey { font_key: outline_key, character, size }).unwrap();
+    let expected = rasterizer.get_glyph(GlyphKey { font_key: color_key, character, size }).unwrap();
+    assert!(matches!(glyph.buffer, BitmapBuffer::Rgba(_)));
+
+    let (mut snapshot, mut expected_snapshot) = (String::new(), String::new());
+    write_glyph(&mut snapshot, &glyph);
+    write_glyph(&mut expected_snapshot, &expected);
+    assert_eq!(snapshot, expected_snapshot);
+}
+
+#[cfg(feature = "svg")]
+#[test]
+fn svg() {
+    // Translucent fills and gradients are premultiplied.
+    let mut rasterizer = rasterizer(fc::Rules::new());
+    let options = RenderOptions::default();
+    check_snapshot("svg", &mut rasterizer, SVG_FAMILY, &options, &SVG_CHARS);
+}
+
+#[cfg(feature = "svg")]
+#[test]
+fn svg_fallback() {
+    let mut rasterizer = rasterizer(fc::Rules::new());
+    let size = Size::new(12.);
+    let options = RenderOptions::default();
+    let outline_key = load(&mut rasterizer, OUTLINE_FAMILY, size, &options);
+    let svg_key = load(&mut rasterizer, SVG_FAMILY, size, &options);
+
+    // Characters missing from the primary font are taken from the `SVG ` font.
+    let character = SVG_CHARS[0];
+    let glyph = rasterizer.get_glyph(GlyphKey { font_key: outline_key, character, size }).unwrap();
+    let expected = rasterizer.get_glyph(GlyphKey { font_key: svg_key, character, size }).unwrap();
+    assert!(matches!(glyph.buffer, BitmapBuffer::Rgba(_)));
+
+    let (mut snapshot, mut expected_snapshot) = (String::new(), String::new());
+    write_glyph(&mut snapshot, &glyph);
+    write_glyph(&mut expected_snapshot, &expected);
+    assert_eq!(snapshot, expected_snapshot);
+}
+
+#[test]
+fn flag_cluster() {
+    let mut rasterizer = rasterizer(fc::Rules::new());
+    let size = Size::new(12.);
+    let font_key = load(&mut rasterizer, OUTLINE_FAMILY, size, &RenderOptions::default());
+
+    // The flag ligature of the two regional indicators is picked from the fallback font, while
+    // clusters without a ligature render their first character.
+    let mut snapshot = String::new();
+    for text in ["\u{1F1FA}\u{1F1F8}", "\u{1F1FA}\u{FE0F}\u{1F1F8}", "\u{1F1F8}\u{1F1FA}"] {
+        let glyph = rasterizer.get_cluster(&ClusterKey { text: text.into(), font_key, size });
+        write_glyph(&mut snapshot, &glyph.unwrap());
+    }
+
+    assert_snapshot("flag_cluster", &snapshot);
+}
+
+#[test]
+fn variation_selectors() {
+    let mut rasterizer = rasterizer(fc::Rules::new());
+    let size = Size::new(12.);
+    let options = outline_options(true, Rgba::None);
+    let outline_key = load(&mut rasterizer, OUTLINE_FAMILY, size, &options);
+    let bitmap_key = load(&mut rasterizer, BITMAP_FAMILY, size, &options);
+
+    // Presentation selectors prefer fallback fonts with or without colored glyphs, while other
+    // selectors use the cmap's glyph for the sequence if there is one.
+    let clusters = [
+        (outline_key, "\u{2764}\u{FE0F}"),
+        (outline_key, "\u{2764}\u{FE0E}"),
+        (bitmap_key, "\u{2764}\u{FE0E}"),
+        (bitmap_key, "\u{2764}\u{FE0F}"),
+        (outline_key, "H\u{FE00}"),
+        (outline_key, "H\u{FE01}"),
+    ];
+
+    let mut snapshot = String::new();
+    for (font_key, text) in clusters {
+        let glyph = rasterizer.get_cluster(&ClusterKey { text: text.into(), font_key, size });
+        write_glyph(&mut snapshot, &glyph.unwrap());
+    }
+
+    assert_snapshot("variation_selectors", &snapshot);
+}
+
+#[test]
+fn kerning() {
+    let mut rasterizer = rasterizer(fc::Rules::new());
+    // FreeType reduces `kern` table values below 25 pixels per em.
+    let size = Size::from_px(32.);
+    let font_key = load(&mut rasterizer, OUTLINE_FAMILY, size, &outline_options(true, Rgba::None));
+    let key = |character| GlyphKey { font_key, character, size };
+
+    // `GPOS` takes precedence over the `kern` table, which is still used for other pairs.
+    assert_eq!(rasterizer.kerning(key('A'), key('o')), (-4., 0.));
+    assert_eq!(rasterizer.kerning(key('A'), key('H')), (-8., 0.));
+    assert_eq!(rasterizer.kerning(key('H'), key('o')), (0., 0.));
+}
+
+#[test]
+fn emoji_presentation() {
+    let mut rasterizer = rasterizer(fc::Rules::new());
+    let size = Size::new(12.);
+    let font_key = load(&mut rasterizer, OUTLINE_FAMILY, size, &RenderOptions::default());
+
+    // Characters with emoji presentation stay with the primary font by default.
+    let star = GlyphKey { font_key, character: '\u{2B50}', size };
+    let glyph = rasterizer.get_glyph(star).unwrap();
+    assert!(matches!(glyph.buffer, BitmapBuffer::Rgb(_)));
+
+    // With emoji presentation, they use the colored glyph of the fallback font instead.
+    rasterizer.set_emoji_presentation(EmojiPresentation::Emoji);
+    for _ in 0..2 {
+        let glyph = rasterizer.get_glyph(star).unwrap();
+        assert!(matches!(glyph.buffer, BitmapBuffer::Rgba(_)));
+    }
+
+    // Other characters stay with the primary font.
+    let heart = GlyphKey { font_key, character: '\u{2764}', size };
+    let glyph = rasterizer.get_glyph(heart).unwrap();
+    assert!(matches!(glyph.buffer, BitmapBuffer::Rgb(_)));
+
+    rasterizer.set_emoji_presentation(EmojiPresentation::Text);
+    let glyph = rasterizer.get_glyph(star).unwrap();
+    assert!(matches!(glyph.buffer, BitmapBuffer::Rgb(_)));
+}
+
+#[test]
+fn fitted_glyphs() {
+    let mut rasterizer = rasterizer(fc::Rules::new());
+    let size = Size::new(12.);
+    let options = outline_options(true, Rgba::None);
+    let outline_key = load(&mut rasterizer, OUTLINE_FAMILY, size, &options);
+    let bitmap_key = load(&mut rasterizer, BITMAP_FAMILY, size, &RenderOptions::default());
+
+    let cell_box = CellBox { cells: 1, cell_width: 6, cell_height: 12, baseline: 3 };
+    let mut snapshot = String::new();
+    for (font_key, character) in [(outline_key, 'A'), (outline_key, 'o'), (bitmap_key, '\u{E200}')]
+    {
+        let glyph_key = GlyphKey { font_key, character, size };
+        let glyph = rasterizer.get_fitted_glyph(glyph_key, cell_box).unwrap();
+
+        assert!(glyph.left >= 0 && glyph.left + glyph.width <= 6, "{glyph:?}");
+        assert!(glyph.top <= 9 && glyph.top - glyph.height >= -3, "{glyph:?}");
+        write_glyph(&mut snapshot, &glyph);
+    }
+
+    // Glyphs fitting into the box are not modified.
+    let cell_box = CellBox { cells: 2, ..cell_box };
+    let glyph_key = GlyphKey { font_key: outline_key, character: 'A', size };
+    let glyph = rasterizer.get_fitted_glyph(glyph_key, cell_box).unwrap();
+    let unmodified = rasterizer.get_glyph(glyph_key).unwrap();
+    assert_eq!(
+        (glyph.left, glyph.top, glyph.width),
+        (unmodified.left, unmodified.top, unmodified.width)
+    );
+
+    assert_snapshot("fitted_glyphs", &snapshot);
+}
+
+#[test]
+fn glyph_constraints() {
+    let mut rasterizer = rasterizer(fc::Rules::new());
+    let size = Size::new(12.);
+    let options = outline_options(true, Rgba::None);
+    let outline_key = load(&mut rasterizer, OUTLINE_FAMILY, size, &options);
+    let bitmap_key = load(&mut rasterizer, BITMAP_FAMILY, size, &RenderOptions::default());
+
+    let stretch = GlyphConstraint { size: ConstraintSize::Stretch, ..Default::default() };
+    let fit = GlyphConstraint {
+        size: ConstraintSize::Fit,
+        center_horizontally: true,
+        center_vertically: true,
+        cells: 2,
+    };
+    rasterizer.set_glyph_constraints([
+        ('\u{E0B0}'..='\u{E0B0}', stretch),
+        ('\u{E000}'..='\u{F8FF}', fit),
+    ]);
+
+    let cell_box = CellBox { cells: 2, cell_width: 6, cell_height: 12, baseline: 3 };
+    let mut snapshot = String::new();
+    for (font_key, character, width) in
+        [(outline_key, '\u{E0B0}', 6), (outline_key, '\u{F101}', 12), (bitmap_key, '\u{E200}', 12)]
+    {
+        let glyph_key = GlyphKey { font_key, character, size };
+        let glyph = rasterizer.get_fitted_glyph(glyph_key, cell_box).unwrap();
+
+        assert_eq!(glyph.advance, (width, 0));
+        assert!(glyph.left >= 0 && glyph.left + glyph.width <= width, "{glyph:?}");
+        assert!(glyph.top <= 9 && glyph.top - glyph.height >= -3, "{glyph:?}");
+        write_glyph(&mut snapshot, &glyph);
+    }
+
+    // Characters without constraints are not modified.
+    let glyph_key = GlyphKey { font_key: outline_key, character: 'A', size };
+    let glyph = rasterizer.get_fitted_glyph(glyph_key, cell_box).unwrap();
+    let unmodified = rasterizer.get_glyph(glyph_key).unwrap();
+    assert_eq!(
+        (glyph.left, glyph.top, glyph.width, glyph.advance),
+        (unmodified.left, unmodified.top, unmodified.width, unmodified.advance)
+    );
+
+    assert_snapshot("glyph_constraints", &snapshot);
+}
+
+#[test]
+fn integer_scaling() {
+    let mut rasterizer = rasterizer(fc::Rules::new());
+    let size = Size::from_px(16.);
+    let font_key = load(&mut rasterizer, PIXEL_FAMILY, size, &RenderOptions::default());
+
+    let strike = BitmapStrike { width: 6, height: 8, pixelsize: 8. };
+    assert_eq!(rasterizer.bitmap_strikes(font_key), [strike]);
+
+    // Without integer scaling, the strike is used as is.
+    let glyph_key = GlyphKey { font_key, character: 'A', size };
+    let glyph = rasterizer.get_glyph(glyph_key).unwrap();
+    assert_eq!((glyph.width, glyph.height, glyph.advance), (4, 6, (6, 0)));
+    assert_eq!(rasterizer.metrics(font_key, size).unwrap().line_height, 8.);
+
+    rasterizer.set_integer_scaling(true);
+    let glyph = rasterizer.get_glyph(glyph_key).unwrap();
+    assert_eq!(rasterizer.metrics(font_key, size).unwrap().line_height, 16.);
+
+    let mut snapshot = String::new();
+    write_glyph(&mut snapshot, &glyph);
+    assert_snapshot("integer_scaling", &snapshot);
+}